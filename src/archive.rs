@@ -0,0 +1,61 @@
+//! Todos moved out of `tasks/` into `archive/YYYY/MM/`, by the month they
+//! were closed in.
+//!
+//! Archived todos keep their ids, which are never handed out again, and
+//! are left out of the collection unless a command asks for them.
+
+use crate::{Collection, DataId, TodoFile};
+use anyhow::anyhow;
+use chrono::Local;
+use std::path::{Path, PathBuf};
+
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// The directory a todo closed at `at` is archived in.
+pub fn dir_for(data_dir: &Path, at: chrono::DateTime<chrono::Utc>) -> PathBuf {
+    let at = at.with_timezone(&Local);
+    data_dir
+        .join(ARCHIVE_DIR)
+        .join(at.format("%Y").to_string())
+        .join(at.format("%m").to_string())
+}
+
+/// The paths of every archived todo file, parsing or not.
+pub async fn files(data_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut pending = vec![data_dir.join(ARCHIVE_DIR)];
+    while let Some(dir) = pending.pop() {
+        if !tokio::fs::try_exists(dir.as_path()).await? {
+            continue;
+        }
+        let mut entries = tokio::fs::read_dir(dir.as_path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "md") {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Every archived todo. Files that don't parse are skipped, like
+/// `load_collection` does.
+pub async fn load(data_dir: &Path) -> anyhow::Result<Collection> {
+    let mut collection = Collection::new();
+    for path in files(data_dir).await? {
+        if let Ok(file) = TodoFile::load_file(path.as_path()).await {
+            if collection.insert(file.data.front_matter.id, file).is_some() {
+                return Err(anyhow!("duplicate content id"));
+            }
+        }
+    }
+    Ok(collection)
+}
+
+/// The highest archived id, so new todos don't reuse it.
+pub async fn max_id(data_dir: &Path) -> anyhow::Result<Option<DataId>> {
+    Ok(load(data_dir).await?.keys().next_back().copied())
+}
@@ -0,0 +1,163 @@
+//! A Unix socket for creating todos without starting the CLI each time.
+//!
+//! Clients such as editor snippets or window manager key bindings connect
+//! and write one JSON object per line, like
+//! `{"title": "Call back", "tags": ["work"]}`, with an optional
+//! `template` and `due` date. Each line is answered with `{"id": 7, "path": "tasks/..."}`
+//! or `{"error": "..."}`.
+
+use crate::{archive, dates};
+use crate::reservations::Reservations;
+use crate::{CommandProcessor, DataId, TemplateVars, TodoFile};
+use anyhow::anyhow;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+pub const SOCKET_NAME: &str = "todo.sock";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    template: Option<String>,
+    due: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum Response {
+    Created { id: DataId, path: String },
+    Failed { error: String },
+}
+
+type Job = (Request, oneshot::Sender<Response>);
+
+/// `$XDG_RUNTIME_DIR/todo.sock`.
+pub fn default_socket() -> anyhow::Result<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .ok_or_else(|| anyhow!("XDG_RUNTIME_DIR is not set, pass --socket"))?;
+    Ok(PathBuf::from(runtime_dir).join(SOCKET_NAME))
+}
+
+/// Binds `socket`, replacing a stale socket file left by a server that
+/// did not shut down cleanly.
+pub async fn bind(socket: &Path) -> anyhow::Result<UnixListener> {
+    if tokio::fs::try_exists(socket).await? {
+        if UnixStream::connect(socket).await.is_ok() {
+            return Err(anyhow!("already serving captures on {}", socket.display()));
+        }
+        tokio::fs::remove_file(socket).await?;
+    }
+    Ok(UnixListener::bind(socket)?)
+}
+
+/// Accepts captures until interrupted, then removes the socket file.
+/// Connections are read concurrently but todos are created one at a time,
+/// so ids are handed out in order.
+pub async fn serve(
+    proc: &mut CommandProcessor,
+    listener: UnixListener,
+    socket: &Path,
+    mut captured: impl FnMut(&CommandProcessor, &TodoFile),
+) -> anyhow::Result<()> {
+    let (jobs, mut queue) = mpsc::channel::<Job>(16);
+    let accept = async {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            tokio::spawn(handle_connection(stream, jobs.clone()));
+        }
+    };
+    let process = async {
+        while let Some((request, reply)) = queue.recv().await {
+            let response = match capture(proc, request).await {
+                Ok(id) => {
+                    let todo_file = proc.get(id).unwrap();
+                    captured(proc, todo_file);
+                    Response::Created {
+                        id: todo_file.data.front_matter.id,
+                        path: todo_file
+                            .path
+                            .strip_prefix(&proc.data_dir)
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned(),
+                    }
+                }
+                Err(err) => Response::Failed {
+                    error: err.to_string(),
+                },
+            };
+            let _ = reply.send(response);
+        }
+    };
+
+    let result: anyhow::Result<()> = tokio::select! {
+        result = accept => result,
+        _ = process => Ok(()),
+        result = tokio::signal::ctrl_c() => result.map_err(Into::into),
+    };
+    tokio::fs::remove_file(socket).await?;
+    result
+}
+
+async fn handle_connection(stream: UnixStream, jobs: mpsc::Sender<Job>) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(line.as_str()) {
+            Ok(request) => {
+                let (reply, response) = oneshot::channel();
+                jobs.send((request, reply)).await?;
+                response.await?
+            }
+            Err(err) => Response::Failed {
+                error: format!("invalid capture request: {err}"),
+            },
+        };
+        let mut out = serde_json::to_string(&response)?;
+        out.push('\n');
+        writer.write_all(out.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Creates the todo and adds it to the collection, so the next capture
+/// sees its id as taken.
+async fn capture(proc: &mut CommandProcessor, request: Request) -> anyhow::Result<DataId> {
+    let reservations = Reservations::load(&proc.data_dir).await?;
+    let archived = archive::max_id(&proc.data_dir).await?;
+    let mut id = proc.next_data_id(&reservations, archived);
+    // the collection was loaded at startup, so skip ids the CLI took since
+    while tokio::fs::try_exists(TodoFile::gen_filepath(&proc.tasks_dir, &proc.config.ids, id))
+        .await?
+    {
+        id += 1;
+    }
+
+    let due_at = request
+        .due
+        .map(|due| dates::parse(due.as_str(), Local::now()))
+        .transpose()?;
+    let mut template_vars = TemplateVars::new(id);
+    template_vars.title = Some(request.title);
+    template_vars.tags = request.tags;
+    let template = request.template.unwrap_or("task".to_string());
+    let mut todo_file = proc
+        .new_todo_from_template(template.as_str(), template_vars)
+        .await?;
+    if due_at.is_some() {
+        todo_file.data.front_matter.due_at = due_at;
+    }
+    todo_file.write_file().await?;
+    proc.collection.insert(id, todo_file);
+    Ok(id)
+}
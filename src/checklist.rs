@@ -0,0 +1,144 @@
+//! Checklists inside todo bodies: `##` sections made up only of
+//! checkbox items.
+//!
+//! A checklist named "Acceptance Criteria" lists what has to hold for the
+//! todo to count as done; it is reported apart from the others and `done`
+//! refuses while any of it is unchecked.
+//!
+//! Named checklists can be kept in `templates/checklists/<name>.md` and
+//! injected into existing todos.
+
+use anyhow::anyhow;
+use std::path::Path;
+
+/// Where injected checklists go; without it they are appended.
+pub const MARKER: &str = "<!-- checklists -->";
+
+pub const CHECKLISTS_DIR: &str = "checklists";
+
+pub const ACCEPTANCE_CRITERIA: &str = "Acceptance Criteria";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checklist {
+    pub name: String,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub done: bool,
+    pub text: String,
+}
+
+fn parse_item(line: &str) -> Option<Item> {
+    let line = line.trim_start();
+    let (done, text) = if let Some(text) = line.strip_prefix("- [ ] ") {
+        (false, text)
+    } else {
+        let text = line
+            .strip_prefix("- [x] ")
+            .or_else(|| line.strip_prefix("- [X] "))?;
+        (true, text)
+    };
+    Some(Item {
+        done,
+        text: text.to_string(),
+    })
+}
+
+/// Splits a body into the text outside of checklists and the checklists.
+pub fn split(body: &str) -> (String, Vec<Checklist>) {
+    let mut text = vec![];
+    let mut checklists = vec![];
+    let mut lines = body.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let Some(name) = line.strip_prefix("## ") else {
+            text.push(line);
+            continue;
+        };
+
+        let mut section = vec![];
+        while let Some(next) = lines.peek() {
+            if next.starts_with("## ") {
+                break;
+            }
+            section.push(lines.next().unwrap());
+        }
+
+        let items: Option<Vec<Item>> = section
+            .iter()
+            .filter(|line| !line.trim().is_empty() && line.trim() != MARKER)
+            .map(|line| parse_item(line))
+            .collect();
+
+        match items {
+            Some(items) if !items.is_empty() => checklists.push(Checklist {
+                name: name.trim().to_string(),
+                items,
+            }),
+            _ => {
+                text.push(line);
+                text.extend(section);
+            }
+        }
+    }
+
+    (text.join("\n").trim().to_string(), checklists)
+}
+
+/// Reads a named checklist. Files without their own `##` heading get
+/// one derived from the name.
+pub async fn load_template(templates_dir: &Path, name: &str) -> anyhow::Result<Checklist> {
+    let path = templates_dir.join(CHECKLISTS_DIR).join(format!("{name}.md"));
+    let content = tokio::fs::read_to_string(path.as_path())
+        .await
+        .map_err(|err| anyhow!("cannot read checklist '{name}' ({}): {err}", path.display()))?;
+
+    let content = if content.trim_start().starts_with("## ") {
+        content
+    } else {
+        format!("## {name}\n\n{content}")
+    };
+
+    match split(content.as_str()).1.into_iter().next() {
+        Some(checklist) => Ok(checklist),
+        None => Err(anyhow!("checklist '{name}' has no checkbox items")),
+    }
+}
+
+impl Checklist {
+    pub fn is_acceptance_criteria(&self) -> bool {
+        self.name.eq_ignore_ascii_case(ACCEPTANCE_CRITERIA)
+    }
+
+    pub fn unchecked(&self) -> impl Iterator<Item = &Item> {
+        self.items.iter().filter(|item| !item.done)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut section = format!("## {}\n", self.name);
+        for item in &self.items {
+            let mark = if item.done { 'x' } else { ' ' };
+            section.push_str(&format!("\n- [{mark}] {}", item.text));
+        }
+        section
+    }
+}
+
+/// Inserts `checklist` before the marker, or at the end of `content`.
+pub fn inject(content: &str, checklist: &Checklist) -> String {
+    let section = checklist.to_markdown();
+    match content.find(MARKER) {
+        Some(at) => format!("{}{section}\n\n{}", &content[..at], &content[at..]),
+        None => format!("{}\n\n{section}\n", content.trim_end()),
+    }
+}
+
+/// Checked and total items over `checklists`, like `(2, 5)`.
+pub fn progress<'a>(checklists: impl IntoIterator<Item = &'a Checklist>) -> (usize, usize) {
+    checklists.into_iter().fold((0, 0), |(done, total), checklist| {
+        let checked = checklist.items.iter().filter(|item| item.done).count();
+        (done + checked, total + checklist.items.len())
+    })
+}
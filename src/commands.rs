@@ -0,0 +1,2388 @@
+//! What each subcommand does, called from `main` with the arguments clap
+//! parsed.
+
+use crate::config::Config;
+use crate::markup::Markup;
+use crate::reservations::Reservations;
+use crate::{
+    archive, capture, cooccurrence, dates, fields, fixtures, fsck, git, github, groom, heatmap,
+    ics, markup, merge, pattern, porcelain, print, replicate, sandbox, shorthand, sync, table,
+    timesheet, trello, validate,
+};
+use crate::{
+    ask_new_fields, confirm, copy_to_clipboard, exit_if_failed, fade_rows, failure, format_due,
+    init_hbs, load_collection, load_collection_with_archive, print_changes, print_next_occurrence,
+    print_preview, select_todos, subtask_tree, suggestions, tag_counts, truncate, BlockedOn,
+    CloneLink, Collection, CommandProcessor, Commands, DataId, FrontMatterStyle, ListArgs,
+    ListFilter, NewArgs, OutputMode, Priority, SandboxCommands, Selection, Status, SuggestKind,
+    TemplateVars, TodoData, TodoFile, Urgency, MAX_EXCERPT_LENGTH,
+};
+use anyhow::anyhow;
+use chrono::{Datelike, Local, Utc};
+use handlebars::Handlebars;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The processor for the todos in `data_dir`.
+async fn processor(data_dir: &Path) -> CommandProcessor {
+    let collection = load_collection(data_dir).await.unwrap_or_else(|err| failure(err));
+    with_collection(Handlebars::new(), collection, data_dir).await
+}
+
+/// Like `processor`, with the templates registered for rendering.
+async fn processor_with_templates(data_dir: &Path) -> CommandProcessor {
+    let hbs = init_hbs(data_dir).unwrap_or_else(|err| failure(err));
+    let collection = load_collection(data_dir).await.unwrap_or_else(|err| failure(err));
+    with_collection(hbs, collection, data_dir).await
+}
+
+/// Like `processor`, with the archived todos in the collection if asked to.
+async fn processor_with_archive(data_dir: &Path, include_archived: bool) -> CommandProcessor {
+    let collection = load_collection_with_archive(data_dir, include_archived)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    with_collection(Handlebars::new(), collection, data_dir).await
+}
+
+async fn with_collection(
+    hbs: Handlebars<'static>,
+    collection: Collection,
+    data_dir: &Path,
+) -> CommandProcessor {
+    let config = Config::load(data_dir).await.unwrap_or_else(|err| failure(err));
+    CommandProcessor::new(hbs, collection, config, data_dir.to_path_buf())
+}
+
+pub async fn new(current_dir: PathBuf, output: OutputMode, args: NewArgs) {
+    let NewArgs {
+        text,
+        template,
+        title,
+        mut tags,
+        id,
+        due,
+        priority,
+        parent,
+        interactive,
+        suggest_due,
+    } = args;
+    let proc = processor_with_templates(&current_dir).await;
+    let mut reservations = Reservations::load(&proc.data_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let mut due_at = due
+        .map(|due| dates::parse(due.as_str(), Local::now()))
+        .transpose()
+        .unwrap_or_else(|err| failure(err));
+    let mut title = title;
+    let mut priority = priority;
+    if let Some(text) = text {
+        let shorthand = shorthand::parse(text.as_str(), Local::now());
+        title = Some(shorthand.title).filter(|title| !title.is_empty());
+        for tag in shorthand.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        due_at = due_at.or(shorthand.due_at);
+        priority = priority.or(shorthand.priority);
+    }
+    let parent = parent
+        .map(|parent| {
+            let parent = proc.resolve_id(parent.as_str())?;
+            proc.get(parent).map(|_| parent)
+        })
+        .transpose()
+        .unwrap_or_else(|err| failure(err));
+    let claimed = id.is_some();
+    let id = match id {
+        Some(id) => proc
+            .claim_reservation(&mut reservations, id.as_str())
+            .unwrap_or_else(|err| failure(err)),
+        None => {
+            let archived = archive::max_id(&proc.data_dir)
+                .await
+                .unwrap_or_else(|err| failure(err));
+            proc.next_data_id(&reservations, archived)
+        }
+    };
+
+    let template = template.unwrap_or("task".to_string());
+    let mut template_vars = TemplateVars::new(id);
+    template_vars.title = title;
+    template_vars.tags = tags;
+    let todo_file_result = proc
+        .new_todo_from_template(template.as_str(), template_vars)
+        .await;
+
+    let mut todo_file = match todo_file_result {
+        Ok(todo_file) => todo_file,
+        Err(err) => {
+            failure(err);
+        }
+    };
+    if due_at.is_some() {
+        todo_file.data.front_matter.due_at = due_at;
+    }
+    if priority.is_some() {
+        todo_file.data.front_matter.priority = priority;
+    }
+    if parent.is_some() {
+        todo_file.data.front_matter.parent = parent;
+    }
+
+    let asking = interactive && std::io::IsTerminal::is_terminal(&std::io::stdin());
+    if todo_file.data.front_matter.due_at.is_none() && (asking || suggest_due) {
+        let tags = &todo_file.data.front_matter.tags;
+        let suggestion = proc
+            .suggest_due(template.as_str(), tags)
+            .unwrap_or_else(|err| failure(err));
+        if let Some((due_at, reason)) = suggestion {
+            eprintln!("note: suggesting due {} for {reason}", format_due(Some(due_at)));
+            todo_file.data.front_matter.due_at = Some(due_at);
+        }
+    }
+
+    if asking {
+        let answers = ask_new_fields(&todo_file.data, &proc.config.tags);
+        let mut template_vars = TemplateVars::new(id);
+        template_vars.title = Some(answers.title);
+        template_vars.tags = answers.tags;
+        todo_file = proc
+            .new_todo_from_template(template.as_str(), template_vars)
+            .await
+            .unwrap_or_else(|err| failure(err));
+        todo_file.data.front_matter.due_at = answers.due_at;
+        todo_file.data.front_matter.priority = answers.priority;
+        todo_file.data.front_matter.parent = parent;
+    } else if interactive {
+        eprintln!("note: stdin is not a terminal, creating the todo without asking");
+    }
+
+    if let Err(err) = todo_file.write_file().await {
+        failure(err);
+    }
+    if claimed {
+        if let Err(err) = reservations.write(&proc.data_dir).await {
+            failure(err);
+        }
+    }
+
+    let rel_path = todo_file.path.strip_prefix(&proc.data_dir).unwrap();
+    match output {
+        OutputMode::Human => println!(
+            "{} {}",
+            rel_path.to_str().unwrap(),
+            todo_file.path.file_name().unwrap().to_str().unwrap()
+        ),
+        OutputMode::Quiet => println!("{}", todo_file.data.front_matter.id),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::Record::Created {
+                id: todo_file.data.front_matter.id,
+                path: rel_path.to_str().unwrap(),
+            }
+        ),
+    }
+}
+
+pub async fn clone(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: String,
+    title: Option<String>,
+    keep: Vec<CloneLink>,
+) {
+    let mut proc = processor(&current_dir).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let clone_id = proc
+        .clone_todo(id, title.as_deref(), &keep)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    let todo_file = proc.get(clone_id).unwrap_or_else(|err| failure(err));
+    let rel_path = todo_file.path.strip_prefix(&proc.data_dir).unwrap();
+    match output {
+        OutputMode::Human => println!(
+            "todo {} cloned from {} as {}",
+            proc.config.ids.display(clone_id),
+            proc.config.ids.display(id),
+            rel_path.to_str().unwrap()
+        ),
+        OutputMode::Quiet => println!("{clone_id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&[
+                "cloned",
+                &clone_id.to_string(),
+                &id.to_string(),
+                rel_path.to_str().unwrap(),
+            ])
+        ),
+    }
+}
+
+pub async fn init(current_dir: PathBuf, output: OutputMode) {
+    let proc = CommandProcessor::new(
+        Handlebars::new(),
+        Collection::new(),
+        Config::default(),
+        current_dir,
+    );
+
+    if let Err(err) = proc.init().await {
+        failure(err);
+    }
+
+    if output == OutputMode::Porcelain {
+        println!(
+            "{}",
+            porcelain::line(&["initialized", proc.data_dir.to_str().unwrap()])
+        );
+    }
+}
+
+pub async fn copy(current_dir: PathBuf, output: OutputMode, id: String, markup: Markup) {
+    let proc = processor(&current_dir).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let todo_file = proc.get(id).unwrap_or_else(|err| failure(err));
+
+    let rendered = markup::render(todo_file.data.content.as_str(), markup);
+    if let Err(err) = copy_to_clipboard(rendered.as_str()).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human => println!(
+            "copied todo {} to the clipboard",
+            proc.config.ids.display(id)
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["copied", &id.to_string()]))
+        }
+    }
+}
+
+pub async fn list(current_dir: PathBuf, output: OutputMode, args: ListArgs) {
+    let ListArgs {
+        tags,
+        overdue,
+        status,
+        priority,
+        excerpt,
+        tree,
+        sort,
+        reverse,
+        limit,
+        include_archived,
+    } = args;
+    let proc = processor_with_archive(&current_dir, include_archived).await;
+    let done_since = match (output, status.is_empty()) {
+        (OutputMode::Human, true) => proc.done_visible_since(Utc::now()),
+        _ => None,
+    };
+    let filter = ListFilter {
+        tags,
+        overdue,
+        status,
+        priority,
+        done_since,
+        sort,
+        reverse,
+        limit,
+    };
+    let todos = proc.list(&filter, Utc::now());
+    let todos = match tree {
+        true => subtask_tree(todos),
+        false => todos.into_iter().map(|todo| (0, todo)).collect(),
+    };
+
+    match output {
+        OutputMode::Human if todos.is_empty() => println!("no matching todos"),
+        OutputMode::Human => {
+            let styled = std::io::IsTerminal::is_terminal(&std::io::stdout());
+            // only done todos kept visible stand out, not those asked for
+            let done: Vec<bool> = todos
+                .iter()
+                .map(|(_, todo)| todo.data.front_matter.status == Status::Done)
+                .map(|done| done && filter.status.is_empty())
+                .collect();
+            let rows: Vec<_> = todos
+                .iter()
+                .zip(&done)
+                .map(|((depth, todo), done)| {
+                    let front_matter = &todo.data.front_matter;
+                    let title = todo.data.title().unwrap_or("-");
+                    let mark = if *done && !styled { " (done)" } else { "" };
+                    let mut row = vec![
+                        proc.config.ids.display(front_matter.id),
+                        format!("{}{title}{mark}", "  ".repeat(*depth)),
+                        front_matter.tags.join(", "),
+                        format_due(front_matter.due_at),
+                    ];
+                    if excerpt {
+                        let text = todo.data.excerpt().unwrap_or_default();
+                        row.push(truncate(text, MAX_EXCERPT_LENGTH));
+                    }
+                    row
+                })
+                .collect();
+            let mut headers = vec!["ID", "TITLE", "TAGS", "DUE"];
+            if excerpt {
+                headers.push("EXCERPT");
+            }
+            let table = table::render(&headers, &rows);
+            match styled {
+                true => print!("{}", fade_rows(&table, &done)),
+                false => print!("{table}"),
+            }
+        }
+        OutputMode::Quiet => {
+            for (_, todo) in &todos {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            for (_, todo) in &todos {
+                let front_matter = &todo.data.front_matter;
+                let record = porcelain::Record::Todo {
+                    id: front_matter.id,
+                    due_at: front_matter.due_at,
+                    tags: &front_matter.tags,
+                    title: todo.data.title().unwrap_or_default(),
+                };
+                println!("{record}");
+            }
+        }
+    }
+}
+
+pub async fn subtasks(current_dir: PathBuf, output: OutputMode, id: String) {
+    let proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let subtasks = proc.subtasks(id).unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human if subtasks.is_empty() => {
+            println!("todo {} has no subtasks", proc.config.ids.display(id))
+        }
+        OutputMode::Human => {
+            let rows: Vec<_> = subtasks
+                .iter()
+                .map(|(depth, todo)| {
+                    let front_matter = &todo.data.front_matter;
+                    let title = todo.data.title().unwrap_or("-");
+                    vec![
+                        proc.config.ids.display(front_matter.id),
+                        format!("{}{title}", "  ".repeat(*depth)),
+                        front_matter.status.name().to_string(),
+                        format_due(front_matter.due_at),
+                    ]
+                })
+                .collect();
+            print!("{}", table::render(&["ID", "TITLE", "STATUS", "DUE"], &rows));
+        }
+        OutputMode::Quiet => {
+            for (_, todo) in &subtasks {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            for (_, todo) in &subtasks {
+                let front_matter = &todo.data.front_matter;
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "subtask",
+                        &front_matter.id.to_string(),
+                        &front_matter.parent.unwrap_or_default().to_string(),
+                        front_matter.status.name(),
+                        todo.data.title().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+    }
+}
+
+pub async fn show(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: String,
+    include_archived: bool,
+    activity: bool,
+) {
+    let proc = processor_with_archive(&current_dir, include_archived).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let todo_file = proc.get(id).unwrap_or_else(|err| failure(err));
+    let fields = proc.fields(todo_file);
+    let activity = match activity {
+        true => proc.activity(todo_file).await,
+        false => vec![],
+    };
+
+    match output {
+        OutputMode::Human => {
+            println!(
+                "{} {}",
+                proc.config.ids.display(id),
+                todo_file.data.title().unwrap_or("-")
+            );
+            let width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            for (name, value) in &fields {
+                println!("{:<width$}  {value}", format!("{name}:"), width = width + 1);
+            }
+            let body = todo_file.data.body().trim_end();
+            if !body.is_empty() {
+                println!("\n{body}");
+            }
+            if !activity.is_empty() {
+                println!("\nactivity:");
+                let rows: Vec<_> = activity
+                    .iter()
+                    .map(|event| {
+                        vec![
+                            event.at.format("%Y-%m-%d %H:%M").to_string(),
+                            event.kind.to_string(),
+                            event.detail.clone(),
+                        ]
+                    })
+                    .collect();
+                print!("{}", table::render(&["AT", "EVENT", "DETAIL"], &rows));
+            }
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            for (name, value) in &fields {
+                println!("{}", porcelain::line(&["field", name, value]));
+            }
+            for event in &activity {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "activity",
+                        &event.at.to_rfc3339(),
+                        event.kind,
+                        event.detail.as_str(),
+                    ])
+                );
+            }
+        }
+    }
+}
+
+pub async fn get(current_dir: PathBuf, id: String, path: String) {
+    let proc = processor(&current_dir).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let todo_file = proc.get(id).unwrap_or_else(|err| failure(err));
+    let document = fields::document(&todo_file.data).unwrap_or_else(|err| failure(err));
+    let value = fields::get(&document, path.as_str()).unwrap_or_else(|err| failure(err));
+    println!("{}", fields::raw(value));
+}
+
+pub async fn set_json(current_dir: PathBuf, output: OutputMode, id: String, patch: String) {
+    let mut proc = processor(&current_dir).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let patch: serde_json::Value = serde_json::from_str(patch.as_str())
+        .unwrap_or_else(|err| failure(anyhow!("invalid patch: {err}")));
+    if let Err(err) = proc.patch(id, &patch).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human => println!("updated todo {}", proc.config.ids.display(id)),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["updated", &id.to_string()]))
+        }
+    }
+}
+
+pub async fn reapply_template(
+    current_dir: PathBuf,
+    output: OutputMode,
+    template: String,
+    tags: Vec<String>,
+    apply: bool,
+) {
+    let mut proc = processor_with_templates(&current_dir).await;
+    let ids: Vec<_> = proc
+        .collection
+        .values()
+        .filter(|todo| tags.iter().all(|tag| todo.data.front_matter.tags.contains(tag)))
+        .map(|todo| todo.data.front_matter.id)
+        .collect();
+
+    let changes = proc
+        .reapply_template(template.as_str(), &ids, apply)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    for (id, added) in &changes {
+        match output {
+            OutputMode::Human => {
+                println!("{}", proc.config.ids.display(*id));
+                for (key, value) in added {
+                    println!("  + {key} = {value}");
+                }
+            }
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                for (key, value) in added {
+                    println!(
+                        "{}",
+                        porcelain::line(&[
+                            if apply { "added" } else { "would-add" },
+                            &id.to_string(),
+                            key,
+                            &value.to_string(),
+                        ])
+                    );
+                }
+            }
+        }
+    }
+    if output == OutputMode::Human {
+        match (apply, changes.len()) {
+            (_, 0) => println!("all todos have the fields of template '{template}'"),
+            (true, count) => println!("updated {count} todos"),
+            (false, count) => {
+                println!("would update {count} todos, rerun with --apply to write them")
+            }
+        }
+    }
+}
+
+pub async fn search(
+    current_dir: PathBuf,
+    output: OutputMode,
+    query: String,
+    regex: bool,
+    ignore_case: bool,
+    include_archived: bool,
+) {
+    let proc = processor_with_archive(&current_dir, include_archived).await;
+    let pattern = if regex {
+        pattern::Pattern::regex(query.as_str(), ignore_case)
+            .unwrap_or_else(|err| failure(err))
+    } else {
+        pattern::Pattern::literal(query.as_str(), ignore_case)
+    };
+    let highlight = std::io::IsTerminal::is_terminal(&std::io::stdout());
+
+    let hits = proc.search(&pattern).await.unwrap_or_else(|err| failure(err));
+    for hit in &hits {
+        match output {
+            OutputMode::Human => println!(
+                "{}:{}: {}",
+                proc.config.ids.display(hit.id),
+                hit.line_number,
+                hit.snippet(highlight)
+            ),
+            OutputMode::Quiet => {}
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[
+                    "match",
+                    &hit.id.to_string(),
+                    &hit.line_number.to_string(),
+                    hit.line.as_str(),
+                ])
+            ),
+        }
+    }
+    if output == OutputMode::Quiet {
+        let mut ids: Vec<_> = hits.iter().map(|hit| hit.id).collect();
+        ids.dedup();
+        for id in ids {
+            println!("{id}");
+        }
+    }
+    if hits.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+pub async fn reserve(current_dir: PathBuf, output: OutputMode, count: u32) {
+    let proc = processor(&current_dir).await;
+    let mut reservations = Reservations::load(&proc.data_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    let archived = archive::max_id(&proc.data_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let ids = reservations.reserve(proc.next_data_id(&reservations, archived), count);
+    if let Err(err) = reservations.write(&proc.data_dir).await {
+        failure(err);
+    }
+
+    for id in ids {
+        match output {
+            OutputMode::Human => println!("reserved {}", proc.config.ids.display(id)),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::line(&["reserved", &id.to_string()]))
+            }
+        }
+    }
+}
+
+pub async fn suggest(current_dir: PathBuf, output: OutputMode, prefix: String, kind: SuggestKind) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    for suggestion in suggestions(&collection, kind, prefix.as_str()) {
+        match output {
+            OutputMode::Human | OutputMode::Quiet => println!("{suggestion}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::line(&[kind.name(), suggestion]))
+            }
+        }
+    }
+}
+
+pub async fn ics(current_dir: PathBuf, id: String, target: Option<PathBuf>) {
+    let proc = processor(&current_dir).await;
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let todo_file = proc.get(id).unwrap_or_else(|err| failure(err));
+    let ics =
+        ics::event(todo_file, &proc.config.ids, Utc::now()).unwrap_or_else(|err| failure(err));
+
+    match target {
+        Some(target) => {
+            if let Err(err) = tokio::fs::write(target, ics).await {
+                failure(err);
+            }
+        }
+        None => print!("{ics}"),
+    }
+}
+
+pub async fn fsck(current_dir: PathBuf, output: OutputMode, write_manifest: bool) {
+    let current = fsck::Manifest::build(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    if write_manifest {
+        if let Err(err) = current.write(&current_dir).await {
+            failure(err);
+        }
+        if output == OutputMode::Human {
+            println!(
+                "recorded {} files in {}",
+                current.files.len(),
+                fsck::MANIFEST_FILE
+            );
+        }
+        return;
+    }
+
+    let recorded = fsck::Manifest::load(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let differences = recorded.diff(&current);
+    for difference in &differences {
+        match output {
+            OutputMode::Human => println!("{difference}"),
+            OutputMode::Quiet => println!("{}", difference.path()),
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[difference.kind(), difference.path()])
+            ),
+        }
+    }
+
+    if !differences.is_empty() {
+        failure(format!(
+            "{} of {} files differ from the manifest written at {}",
+            differences.len(),
+            recorded.files.len(),
+            recorded.created_at
+        ));
+    }
+    if output == OutputMode::Human {
+        println!("{} files match the manifest", recorded.files.len());
+    }
+}
+
+pub async fn replicate(current_dir: PathBuf, output: OutputMode, query: String, to: PathBuf) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let query = replicate::Query::parse(query.as_str()).unwrap_or_else(|err| failure(err));
+    let replicated = replicate::run(&current_dir, &collection, &query, &to)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => {
+            let changed: Vec<_> = replicated
+                .iter()
+                .filter(|replicated| replicated.outcome != replicate::Outcome::Unchanged)
+                .collect();
+            for replicated in &changed {
+                println!(
+                    "{} replica {} of todo {}",
+                    replicated.outcome.kind(),
+                    replicated.replica,
+                    replicated.source
+                );
+            }
+            let kept = replicated
+                .iter()
+                .filter(|replicated| replicated.outcome != replicate::Outcome::Removed)
+                .count();
+            println!("{kept} todos replicated, {} changes", changed.len());
+        }
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => {
+            for replicated in &replicated {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        replicated.outcome.kind(),
+                        &replicated.source.to_string(),
+                        &replicated.replica.to_string(),
+                    ])
+                );
+            }
+        }
+    }
+}
+
+pub async fn github_push(
+    current_dir: PathBuf,
+    output: OutputMode,
+    query: String,
+    repo: String,
+    batch: usize,
+    interval: u64,
+) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let query = replicate::Query::parse(query.as_str()).unwrap_or_else(|err| failure(err));
+    let todos: Vec<_> = collection
+        .values()
+        .map(|todo| &todo.data)
+        .filter(|todo| query.matches(todo))
+        .collect();
+    let pacing = github::Pacing {
+        batch,
+        interval: Duration::from_secs(interval),
+    };
+
+    let mut created = 0;
+    let result = github::push(&current_dir, &todos, repo.as_str(), pacing, |pushed| {
+        let issue = &pushed.issue;
+        created += usize::from(pushed.created);
+        match output {
+            OutputMode::Human if pushed.created => println!(
+                "created issue #{} for todo {}: {}",
+                issue.number, issue.todo, issue.url
+            ),
+            OutputMode::Human => {}
+            OutputMode::Quiet => println!("{}", issue.url),
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[
+                    "issue",
+                    &issue.todo.to_string(),
+                    &issue.number.to_string(),
+                    issue.url.as_str(),
+                    if pushed.created { "created" } else { "existing" },
+                ])
+            ),
+        }
+    })
+    .await;
+    if let Err(err) = result {
+        failure(format!("{err}, run the push again to resume"));
+    }
+    if output == OutputMode::Human {
+        println!(
+            "{created} issues created, {} todos already published",
+            todos.len() - created
+        );
+    }
+}
+
+pub async fn heatmap(
+    current_dir: PathBuf,
+    output: OutputMode,
+    year: Option<i32>,
+    metric: heatmap::Metric,
+) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let year = year.unwrap_or_else(|| Local::now().year());
+    let counts = heatmap::counts(&collection, metric, year);
+    let total: usize = counts.values().sum();
+
+    match output {
+        OutputMode::Human => {
+            print!("{}", heatmap::render(year, &counts));
+            println!("{total} todos {} in {year}", metric.name());
+        }
+        OutputMode::Quiet => println!("{total}"),
+        OutputMode::Porcelain => {
+            for (day, count) in &counts {
+                println!(
+                    "{}",
+                    porcelain::line(&[metric.name(), &day.to_string(), &count.to_string()])
+                );
+            }
+        }
+    }
+}
+
+pub async fn validate(current_dir: PathBuf, output: OutputMode, staged: bool) {
+    let config = Config::load(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let worktree = validate::worktree(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let (sources, others) = if staged {
+        let sources = validate::staged(&current_dir)
+            .await
+            .unwrap_or_else(|err| failure(err));
+        (sources, worktree)
+    } else {
+        (worktree, vec![])
+    };
+
+    let diagnostics = validate::check(&sources, &others, &config);
+    for diagnostic in &diagnostics {
+        match output {
+            OutputMode::Human => println!("{diagnostic}"),
+            OutputMode::Quiet => {}
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[
+                    "invalid",
+                    diagnostic.path.as_str(),
+                    &diagnostic.line.to_string(),
+                    &diagnostic.column.to_string(),
+                    diagnostic.message.as_str(),
+                ])
+            ),
+        }
+    }
+
+    let mut invalid: Vec<&str> = diagnostics.iter().map(|d| d.path.as_str()).collect();
+    invalid.dedup();
+    if !invalid.is_empty() {
+        failure(format!("{} of {} files are invalid", invalid.len(), sources.len()));
+    }
+    if output == OutputMode::Human {
+        println!("{} files are valid", sources.len());
+    }
+}
+
+pub async fn migrate(current_dir: PathBuf, output: OutputMode, front_matter: FrontMatterStyle) {
+    let proc = processor(&current_dir).await;
+
+    let (migrated, skipped) = proc.migrate(front_matter).await.unwrap_or_else(|err| failure(err));
+    for id in &migrated {
+        match output {
+            OutputMode::Human => {}
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::line(&["migrated", &id.to_string()]))
+            }
+        }
+    }
+    if skipped > 0 {
+        eprintln!("warning: skipped {skipped} files that don't parse, see `todo validate`");
+    }
+    if output == OutputMode::Human {
+        let style = front_matter.name();
+        match migrated.len() {
+            0 if skipped > 0 => println!("no todo converted to {style} front matter"),
+            0 => println!("every todo has {style} front matter already"),
+            count => println!("converted {count} todos to {style} front matter"),
+        }
+        if proc.config.files.front_matter != front_matter {
+            println!(
+                "new todos still get {} front matter, set `front_matter = \"{style}\"` \
+                 under [files] in config.toml to change that",
+                proc.config.files.front_matter.name()
+            );
+        }
+    }
+    exit_if_failed(skipped);
+}
+
+pub async fn git_merge_driver(base: PathBuf, ours: PathBuf, theirs: PathBuf) {
+    let read = |path: PathBuf| async move {
+        let content = tokio::fs::read_to_string(path.as_path()).await?;
+        TodoData::from_str(content.as_str())
+            .map_err(|err| anyhow!("cannot merge {}: {err}", path.display()))
+    };
+    let (base_data, ours_data, theirs_data) =
+        tokio::try_join!(read(base), read(ours.clone()), read(theirs))
+            .unwrap_or_else(|err| failure(err));
+
+    let merged = merge::merge(&base_data, &ours_data, &theirs_data)
+        .unwrap_or_else(|err| failure(err));
+    if let Err(err) = tokio::fs::write(ours.as_path(), merged.data.to_bytes()).await {
+        failure(err);
+    }
+    if !merged.conflicts.is_empty() {
+        failure(format!(
+            "todo {} has conflicting changes to {}",
+            merged.data.front_matter.id,
+            merged.conflicts.join(", ")
+        ));
+    }
+}
+
+pub async fn diff_branches(current_dir: PathBuf, output: OutputMode, from: String, to: String) {
+    let config = Config::load(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let (old, new) = tokio::try_join!(
+        git::load_at(&current_dir, from.as_str()),
+        git::load_at(&current_dir, to.as_str())
+    )
+    .unwrap_or_else(|err| failure(err));
+
+    let changes = git::diff(&old, &new, &config.diff.ignore_fields);
+    print_changes(&changes, &config.ids, output);
+}
+
+pub async fn sandbox(current_dir: PathBuf, output: OutputMode, command: SandboxCommands) {
+    match command {
+        SandboxCommands::Open => {
+            let sandbox = sandbox::open(&current_dir)
+                .await
+                .unwrap_or_else(|err| failure(err));
+            let path = sandbox.to_str().unwrap();
+            match output {
+                OutputMode::Human => println!(
+                    "opened a sandbox, try changes with `todo --data-dir {path} ...`"
+                ),
+                OutputMode::Quiet => println!("{path}"),
+                OutputMode::Porcelain => println!("{}", porcelain::line(&["sandbox", path])),
+            }
+        }
+        SandboxCommands::Diff => {
+            let sandbox = sandbox::find(&current_dir)
+                .await
+                .unwrap_or_else(|err| failure(err));
+            let config = Config::load(&current_dir)
+                .await
+                .unwrap_or_else(|err| failure(err));
+            let (real, tried) = tokio::try_join!(
+                load_collection(&current_dir),
+                load_collection(&sandbox)
+            )
+            .unwrap_or_else(|err| failure(err));
+            let data = |collection: Collection| -> BTreeMap<DataId, TodoData> {
+                collection.into_iter().map(|(id, todo)| (id, todo.data)).collect()
+            };
+            let (real, tried) = (data(real), data(tried));
+
+            let changes = git::diff(&real, &tried, &config.diff.ignore_fields);
+            if changes.is_empty() && output == OutputMode::Human {
+                println!("no changes in the sandbox");
+            }
+            print_changes(&changes, &config.ids, output);
+        }
+        SandboxCommands::Apply => {
+            if let Err(err) = sandbox::apply(&current_dir).await {
+                failure(err);
+            }
+            if output == OutputMode::Human {
+                println!("applied the sandbox to the collection");
+            }
+        }
+        SandboxCommands::Discard => {
+            if let Err(err) = sandbox::discard(&current_dir).await {
+                failure(err);
+            }
+            if output == OutputMode::Human {
+                println!("discarded the sandbox");
+            }
+        }
+    }
+}
+
+pub async fn edit(current_dir: PathBuf, output: OutputMode, id: String, editor: Option<String>) {
+    let proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let editor = editor
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or("vi".to_string());
+    if let Err(err) = proc.edit(id, editor.as_str()).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human => {}
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["updated", &id.to_string()]))
+        }
+    }
+}
+
+pub async fn delete(current_dir: PathBuf, output: OutputMode, selection: Selection, purge: bool) {
+    let mut proc = processor(&current_dir).await;
+
+    let ids = select_todos(&proc, &selection, output);
+    if selection.dry_run {
+        return print_preview(&proc, "delete", &ids, output);
+    }
+
+    let mut failed = 0;
+    for id in ids {
+        let trashed = match proc.delete(id, purge).await {
+            Ok(trashed) => trashed,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        match output {
+            OutputMode::Human => match trashed {
+                Some(path) => println!(
+                    "moved todo {} to {}",
+                    proc.config.ids.display(id),
+                    path.strip_prefix(&proc.data_dir).unwrap().display()
+                ),
+                None => println!("deleted todo {}", proc.config.ids.display(id)),
+            },
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Deleted { id })
+            }
+        }
+    }
+    exit_if_failed(failed);
+}
+
+pub async fn trash_empty(current_dir: PathBuf, output: OutputMode) {
+    let proc = CommandProcessor::new(
+        Handlebars::new(),
+        Collection::new(),
+        Config::default(),
+        current_dir,
+    );
+    let removed = proc.empty_trash().await.unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!("removed {removed} files from the trash"),
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["purged", &removed.to_string()]))
+        }
+    }
+}
+
+pub async fn tag_graph(current_dir: PathBuf, output: OutputMode, limit: usize) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let report = cooccurrence::report(&collection);
+    let pairs = &report.pairs[..limit.min(report.pairs.len())];
+
+    match output {
+        OutputMode::Human => {
+            if report.pairs.is_empty() && report.orphans.is_empty() {
+                println!("no tags are used together or only once");
+            }
+            if !pairs.is_empty() {
+                let rows: Vec<_> = pairs
+                    .iter()
+                    .map(|pair| {
+                        let (a, b) = pair.tags;
+                        vec![a.to_string(), b.to_string(), pair.count.to_string()]
+                    })
+                    .collect();
+                print!("{}", table::render(&["TAG", "WITH", "TODOS"], &rows));
+            }
+            if !report.synonyms.is_empty() {
+                println!("\nlikely synonyms:");
+            }
+            for synonym in &report.synonyms {
+                let (rare, common) = (synonym.rare, synonym.common);
+                println!(
+                    "  {rare} comes with {common} on {} of {} todos, \
+                     merge with `todo tag merge {rare} --into {common}`",
+                    synonym.shared, synonym.uses
+                );
+            }
+            if !report.orphans.is_empty() {
+                println!("\nused once:");
+            }
+            for orphan in &report.orphans {
+                match orphan.closest {
+                    Some(closest) => println!(
+                        "  {}, did you mean {closest}? \
+                         `todo tag merge {} --into {closest}`",
+                        orphan.tag, orphan.tag
+                    ),
+                    None => println!("  {}", orphan.tag),
+                }
+            }
+        }
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => {
+            for pair in pairs {
+                let (a, b) = pair.tags;
+                println!("{}", porcelain::line(&["pair", a, b, &pair.count.to_string()]));
+            }
+            for synonym in &report.synonyms {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "synonym",
+                        synonym.rare,
+                        synonym.common,
+                        &synonym.shared.to_string(),
+                        &synonym.uses.to_string(),
+                    ])
+                );
+            }
+            for orphan in &report.orphans {
+                let closest = orphan.closest.unwrap_or_default();
+                println!("{}", porcelain::line(&["orphan", orphan.tag, closest]));
+            }
+        }
+    }
+}
+
+/// `tag rename` and `tag merge`, see `CommandProcessor::replace_tags`.
+pub async fn retag(
+    current_dir: PathBuf,
+    output: OutputMode,
+    tags: Vec<String>,
+    into: String,
+    renaming: bool,
+) {
+    let mut proc = processor(&current_dir).await;
+    let retagged = proc
+        .replace_tags(&tags, into.as_str(), renaming)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    for (id, tags) in &retagged {
+        match output {
+            OutputMode::Human => println!(
+                "todo {} is tagged {}",
+                proc.config.ids.display(*id),
+                tags.join(", ")
+            ),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Tagged { id: *id, tags })
+            }
+        }
+    }
+}
+
+pub async fn tag(
+    current_dir: PathBuf,
+    output: OutputMode,
+    selection: Selection,
+    add: Vec<String>,
+    remove: Vec<String>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let ids = select_todos(&proc, &selection, output);
+    if selection.dry_run {
+        return print_preview(&proc, "retag", &ids, output);
+    }
+
+    let mut failed = 0;
+    for id in ids {
+        let tags = match proc.retag(id, add.clone(), remove.clone()).await {
+            Ok(tags) => tags,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        match output {
+            OutputMode::Human if tags.is_empty() => {
+                println!("todo {} has no tags", proc.config.ids.display(id))
+            }
+            OutputMode::Human => println!(
+                "todo {} is tagged {}",
+                proc.config.ids.display(id),
+                tags.join(", ")
+            ),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Tagged { id, tags: &tags })
+            }
+        }
+    }
+    exit_if_failed(failed);
+}
+
+pub async fn tags(current_dir: PathBuf, output: OutputMode) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let counts = tag_counts(&collection);
+
+    match output {
+        OutputMode::Human => {
+            let rows: Vec<_> = counts
+                .iter()
+                .map(|(tag, count)| vec![tag.to_string(), count.to_string()])
+                .collect();
+            print!("{}", table::render(&["TAG", "TODOS"], &rows));
+        }
+        OutputMode::Quiet => {
+            for (tag, _) in &counts {
+                println!("{tag}");
+            }
+        }
+        OutputMode::Porcelain => {
+            for (tag, count) in &counts {
+                println!("{}", porcelain::line(&["tag", tag, &count.to_string()]));
+            }
+        }
+    }
+}
+
+pub async fn today_or_overdue(current_dir: PathBuf, output: OutputMode, command: Commands) {
+    let proc = processor(&current_dir).await;
+    let mut agenda = proc.agenda(Utc::now());
+    if matches!(command, Commands::Overdue) {
+        agenda.retain(|urgency, _| *urgency == Urgency::Overdue);
+    }
+    let done = match command {
+        Commands::Today => proc.done_visible_since(Utc::now()),
+        _ => None,
+    }
+    .map(|since| proc.done_since(since))
+    .unwrap_or_default();
+
+    match output {
+        OutputMode::Human if agenda.is_empty() && done.is_empty() => match command {
+            Commands::Overdue => println!("no todos are overdue"),
+            _ => println!("nothing is due this week"),
+        },
+        OutputMode::Human => {
+            let row = |todo: &&TodoFile| {
+                let front_matter = &todo.data.front_matter;
+                vec![
+                    proc.config.ids.display(front_matter.id),
+                    todo.data.title().unwrap_or("-").to_string(),
+                    front_matter.tags.join(", "),
+                    format_due(front_matter.due_at),
+                ]
+            };
+            for (i, (urgency, todos)) in agenda.iter().enumerate() {
+                let rows: Vec<_> = todos.iter().map(row).collect();
+                if i > 0 {
+                    println!();
+                }
+                println!("{}:", urgency.heading());
+                print!("{}", table::render(&["ID", "TITLE", "TAGS", "DUE"], &rows));
+            }
+            if !done.is_empty() {
+                if !agenda.is_empty() {
+                    println!();
+                }
+                println!("done recently:");
+                let rows: Vec<_> = done.iter().map(row).collect();
+                let table = table::render(&["ID", "TITLE", "TAGS", "DUE"], &rows);
+                match std::io::IsTerminal::is_terminal(&std::io::stdout()) {
+                    true => print!("{}", fade_rows(&table, &vec![true; rows.len()])),
+                    false => print!("{table}"),
+                }
+            }
+        }
+        OutputMode::Quiet => {
+            for todo in agenda.values().flatten() {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            for (urgency, todos) in &agenda {
+                for todo in todos {
+                    let front_matter = &todo.data.front_matter;
+                    println!(
+                        "{}",
+                        porcelain::line(&[
+                            urgency.name(),
+                            &front_matter.id.to_string(),
+                            &front_matter.due_at.map(|due| due.to_rfc3339()).unwrap_or_default(),
+                            todo.data.title().unwrap_or_default(),
+                        ])
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub async fn agenda(current_dir: PathBuf, output: OutputMode, days: u64, week: bool) {
+    let proc = processor(&current_dir).await;
+    let days = if week { 7 } else { days };
+    let schedule = proc.schedule(Utc::now(), days);
+
+    match output {
+        OutputMode::Human => {
+            let today = Local::now().date_naive();
+            let mut sections = vec![];
+            if !schedule.overdue.is_empty() {
+                sections.push(("overdue".to_string(), &schedule.overdue));
+            }
+            for (day, todos) in &schedule.days {
+                let mut heading = day.format("%a %Y-%m-%d").to_string();
+                if *day == today {
+                    heading.push_str(" (today)");
+                }
+                sections.push((heading, todos));
+            }
+            sections.push(("unscheduled".to_string(), &schedule.unscheduled));
+
+            for (i, (heading, todos)) in sections.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("{heading}:");
+                if todos.is_empty() {
+                    println!("nothing due");
+                    continue;
+                }
+                let rows: Vec<_> = todos
+                    .iter()
+                    .map(|todo| {
+                        vec![
+                            proc.config.ids.display(todo.data.front_matter.id),
+                            todo.data.title().unwrap_or("-").to_string(),
+                            todo.data.front_matter.tags.join(", "),
+                        ]
+                    })
+                    .collect();
+                print!("{}", table::render(&["ID", "TITLE", "TAGS"], &rows));
+            }
+        }
+        OutputMode::Quiet => {
+            let days = schedule.days.values().flatten();
+            for todo in schedule.overdue.iter().chain(days).chain(&schedule.unscheduled) {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            let mut records: Vec<(&str, String, &TodoFile)> = vec![];
+            for todo in &schedule.overdue {
+                let due = format_due(todo.data.front_matter.due_at);
+                records.push(("overdue", due, todo));
+            }
+            for (day, todos) in &schedule.days {
+                records.extend(todos.iter().map(|todo| ("day", day.to_string(), *todo)));
+            }
+            for todo in &schedule.unscheduled {
+                records.push(("unscheduled", String::new(), todo));
+            }
+            for (bucket, day, todo) in records {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        bucket,
+                        &todo.data.front_matter.id.to_string(),
+                        &day,
+                        todo.data.title().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+    }
+}
+
+pub async fn serve(current_dir: PathBuf, output: OutputMode, socket: Option<PathBuf>) {
+    let mut proc = processor_with_templates(&current_dir).await;
+    let socket = socket
+        .map_or_else(capture::default_socket, Ok)
+        .unwrap_or_else(|err| failure(err));
+    let listener = capture::bind(&socket)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!("listening on {}", socket.display()),
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["listening", &socket.to_string_lossy()]))
+        }
+    }
+    let captured = |proc: &CommandProcessor, todo_file: &TodoFile| {
+        let id = todo_file.data.front_matter.id;
+        match output {
+            OutputMode::Human => println!("captured todo {}", proc.config.ids.display(id)),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::line(&["created", &id.to_string()]))
+            }
+        }
+    };
+    if let Err(err) = capture::serve(&mut proc, listener, &socket, captured).await {
+        failure(err);
+    }
+}
+
+pub async fn due(current_dir: PathBuf, output: OutputMode, id: String, date: Option<String>) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let due_at = date
+        .map(|date| dates::parse(date.as_str(), Local::now()))
+        .transpose()
+        .unwrap_or_else(|err| failure(err));
+    if let Err(err) = proc.set_due(id, due_at).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human if due_at.is_none() => {
+            println!("todo {} has no due date", proc.config.ids.display(id))
+        }
+        OutputMode::Human => println!(
+            "todo {} is due {}",
+            proc.config.ids.display(id),
+            format_due(due_at)
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&[
+                "due",
+                &id.to_string(),
+                &due_at.map(|due| due.to_rfc3339()).unwrap_or_default(),
+            ])
+        ),
+    }
+}
+
+pub async fn priority(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: String,
+    priority: Option<Priority>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    if let Err(err) = proc.set_priority(id, priority).await {
+        failure(err);
+    }
+
+    let name = priority.map(|priority| priority.name()).unwrap_or_default();
+    match output {
+        OutputMode::Human if priority.is_none() => {
+            println!("todo {} has no priority", proc.config.ids.display(id))
+        }
+        OutputMode::Human => {
+            println!("todo {} has {name} priority", proc.config.ids.display(id))
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["priority", &id.to_string(), name]))
+        }
+    }
+}
+
+pub async fn recur(current_dir: PathBuf, output: OutputMode, id: String, rule: Option<String>) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    if let Err(err) = proc.set_recurrence(id, rule.clone()).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human => match &rule {
+            Some(rule) => println!("todo {} recurs {rule}", proc.config.ids.display(id)),
+            None => println!("todo {} no longer recurs", proc.config.ids.display(id)),
+        },
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&["recurrence", &id.to_string(), &rule.unwrap_or_default()])
+        ),
+    }
+}
+
+pub async fn link(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: String,
+    depends_on: Vec<String>,
+    remove: Vec<String>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let resolve = |ids: Vec<String>| {
+        ids.iter()
+            .map(|id| proc.resolve_id(id))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap_or_else(|err| failure(err))
+    };
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let (add, remove) = (resolve(depends_on), resolve(remove));
+    let dependencies = proc
+        .link(id, add, remove)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human if dependencies.is_empty() => {
+            println!("todo {} has no dependencies", proc.config.ids.display(id))
+        }
+        OutputMode::Human => {
+            let display: Vec<String> =
+                dependencies.iter().map(|id| proc.config.ids.display(*id)).collect();
+            println!(
+                "todo {} depends on {}",
+                proc.config.ids.display(id),
+                display.join(", ")
+            )
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            let ids: Vec<String> = dependencies.iter().map(|id| id.to_string()).collect();
+            println!("{}", porcelain::line(&["linked", &id.to_string(), &ids.join(",")]))
+        }
+    }
+}
+
+pub async fn next(current_dir: PathBuf, output: OutputMode, count: usize) {
+    let proc = processor(&current_dir).await;
+    let mut todos = proc.ranked(Utc::now());
+    todos.truncate(count);
+
+    match output {
+        OutputMode::Human if todos.is_empty() => println!("nothing can be worked on"),
+        OutputMode::Human => {
+            let rows: Vec<_> = todos
+                .iter()
+                .map(|(score, todo)| {
+                    let front_matter = &todo.data.front_matter;
+                    vec![
+                        proc.config.ids.display(front_matter.id),
+                        todo.data.title().unwrap_or("-").to_string(),
+                        front_matter.tags.join(", "),
+                        format_due(front_matter.due_at),
+                        format!("{score:.2}"),
+                    ]
+                })
+                .collect();
+            let headers = ["ID", "TITLE", "TAGS", "DUE", "SCORE"];
+            print!("{}", table::render(&headers, &rows));
+        }
+        OutputMode::Quiet => {
+            for (_, todo) in &todos {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            for (score, todo) in &todos {
+                let front_matter = &todo.data.front_matter;
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "next",
+                        &front_matter.id.to_string(),
+                        &front_matter.due_at.map(|due| due.to_rfc3339()).unwrap_or_default(),
+                        &format!("{score:.4}"),
+                        todo.data.title().unwrap_or_default(),
+                    ])
+                );
+            }
+        }
+    }
+}
+
+pub async fn groom(
+    current_dir: PathBuf,
+    output: OutputMode,
+    older_than: String,
+    dry_run: bool,
+    yes: bool,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let cutoff = dates::back(Utc::now(), older_than.as_str())
+        .unwrap_or_else(|err| failure(err));
+    // collections outside of git only have the dates in their files
+    let changed = git::last_changed(&proc.data_dir).await.unwrap_or_default();
+    let last_changed = proc
+        .collection
+        .values()
+        .filter_map(|todo| {
+            let path = todo.path.strip_prefix(&proc.data_dir).ok()?.to_str()?;
+            Some((todo.data.front_matter.id, *changed.get(path)?))
+        })
+        .collect();
+    let actions = groom::suggest(&proc.collection, &last_changed, cutoff);
+
+    let mut accepted = 0;
+    for action in &actions {
+        let title = |id: DataId| {
+            let todo = &proc.collection[&id].data;
+            format!("{} \"{}\"", proc.config.ids.display(id), todo.title().unwrap_or("-"))
+        };
+        let (description, detail) = match action {
+            groom::Action::Close { id, idle_since } => (
+                format!(
+                    "close {}, no activity since {}",
+                    title(*id),
+                    idle_since.format("%Y-%m-%d")
+                ),
+                idle_since.to_rfc3339(),
+            ),
+            groom::Action::Merge {
+                duplicate,
+                original,
+            } => (
+                format!("merge {} into {}", title(*duplicate), title(*original)),
+                original.to_string(),
+            ),
+            groom::Action::Demote { id, to } => (
+                format!("demote {} to {}, overdue for long", title(*id), to.name()),
+                to.name().to_string(),
+            ),
+        };
+
+        let outcome = if dry_run {
+            "suggested"
+        } else if yes || confirm(description.as_str()) {
+            "accepted"
+        } else {
+            "skipped"
+        };
+        if outcome == "accepted" {
+            let applied = match action {
+                groom::Action::Close { id, idle_since } => {
+                    let reason =
+                        format!("stale, no activity since {}", idle_since.date_naive());
+                    proc.cancel(*id, reason, None).await
+                }
+                groom::Action::Merge {
+                    duplicate,
+                    original,
+                } => {
+                    let reason =
+                        format!("duplicate of {}", proc.config.ids.display(*original));
+                    proc.cancel(*duplicate, reason, Some(*original)).await
+                }
+                groom::Action::Demote { id, to } => proc.set_priority(*id, Some(*to)).await,
+            };
+            if let Err(err) = applied {
+                failure(err);
+            }
+            accepted += 1;
+        }
+
+        let id = action.id().to_string();
+        match output {
+            OutputMode::Human if dry_run || yes => println!("{description}"),
+            OutputMode::Human => {}
+            OutputMode::Quiet if outcome == "accepted" => println!("{id}"),
+            OutputMode::Quiet => {}
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[action.kind(), &id, detail.as_str(), outcome])
+            ),
+        }
+    }
+
+    if output == OutputMode::Human {
+        match actions.len() {
+            0 => println!("nothing to groom"),
+            _ if dry_run => {}
+            total => println!("accepted {accepted} of {total} suggestions"),
+        }
+    }
+}
+
+pub async fn snooze(current_dir: PathBuf, output: OutputMode, id: String, duration: String) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let due_at = proc
+        .snooze(id, duration.as_str())
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!(
+            "todo {} is due {}",
+            proc.config.ids.display(id),
+            format_due(Some(due_at))
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&["due", &id.to_string(), &due_at.to_rfc3339()])
+        ),
+    }
+}
+
+pub async fn archive(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: Option<String>,
+    done: bool,
+    cancelled: bool,
+    older_than: Option<String>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let ids = match id {
+        Some(id) => vec![proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err))],
+        None => {
+            let cutoff = older_than
+                .map(|duration| dates::back(Utc::now(), duration.as_str()))
+                .transpose()
+                .unwrap_or_else(|err| failure(err));
+            let statuses: Vec<_> = [(done, Status::Done), (cancelled, Status::Cancelled)]
+                .into_iter()
+                .filter_map(|(wanted, status)| wanted.then_some(status))
+                .collect();
+            proc.closed_before(&statuses, cutoff)
+        }
+    };
+    if ids.is_empty() && output == OutputMode::Human {
+        println!("nothing to archive");
+    }
+    let mut failed = 0;
+    for id in ids {
+        let path = match proc.archive(id).await {
+            Ok(path) => path,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        let rel_path = path.strip_prefix(&proc.data_dir).unwrap().to_string_lossy();
+        match output {
+            OutputMode::Human => println!(
+                "archived todo {} to {rel_path}",
+                proc.config.ids.display(id)
+            ),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&["archived", &id.to_string(), &rel_path])
+            ),
+        }
+    }
+    exit_if_failed(failed);
+}
+
+pub async fn start(current_dir: PathBuf, output: OutputMode, id: String) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let stopped = proc
+        .start(id, Utc::now())
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => {
+            if let Some((stopped, tracked)) = stopped {
+                println!(
+                    "stopped todo {} after {}",
+                    proc.config.ids.display(stopped),
+                    timesheet::format(tracked)
+                );
+            }
+            println!("started todo {}", proc.config.ids.display(id));
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            if let Some((stopped, tracked)) = stopped {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "stopped",
+                        &stopped.to_string(),
+                        &tracked.num_seconds().to_string(),
+                    ])
+                );
+            }
+            println!("{}", porcelain::line(&["started", &id.to_string()]));
+        }
+    }
+}
+
+pub async fn stop(current_dir: PathBuf, output: OutputMode) {
+    let mut proc = processor(&current_dir).await;
+
+    let (id, tracked) = proc.stop(Utc::now()).await.unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!(
+            "stopped todo {} after {}",
+            proc.config.ids.display(id),
+            timesheet::format(tracked)
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&[
+                "stopped",
+                &id.to_string(),
+                &tracked.num_seconds().to_string(),
+            ])
+        ),
+    }
+}
+
+pub async fn time_report(current_dir: PathBuf, output: OutputMode, week: bool) {
+    let proc = processor(&current_dir).await;
+
+    let now = Local::now();
+    let since = if week {
+        let today = now.date_naive();
+        let monday =
+            today - chrono::Days::new(today.weekday().num_days_from_monday().into());
+        let start = monday.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Local);
+        Some(start.earliest().unwrap_or(now).with_timezone(&Utc))
+    } else {
+        None
+    };
+    let report = timesheet::report(&proc.collection, since, now.with_timezone(&Utc));
+
+    match output {
+        OutputMode::Human if report.todos.is_empty() => println!("no time tracked"),
+        OutputMode::Human => {
+            let rows: Vec<_> = report
+                .todos
+                .iter()
+                .map(|(id, tracked)| {
+                    vec![
+                        proc.config.ids.display(*id),
+                        proc.collection[id].data.title().unwrap_or("-").to_string(),
+                        timesheet::format(*tracked),
+                    ]
+                })
+                .collect();
+            print!("{}", table::render(&["ID", "TITLE", "TIME"], &rows));
+            if !report.tags.is_empty() {
+                let rows: Vec<_> = report
+                    .tags
+                    .iter()
+                    .map(|(tag, tracked)| vec![tag.clone(), timesheet::format(*tracked)])
+                    .collect();
+                println!();
+                print!("{}", table::render(&["TAG", "TIME"], &rows));
+            }
+            println!("\ntotal {}", timesheet::format(report.total));
+        }
+        OutputMode::Quiet => {
+            for (id, _) in &report.todos {
+                println!("{id}");
+            }
+        }
+        OutputMode::Porcelain => {
+            for (id, tracked) in &report.todos {
+                println!(
+                    "{}",
+                    porcelain::line(&[
+                        "todo",
+                        &id.to_string(),
+                        &tracked.num_seconds().to_string(),
+                    ])
+                );
+            }
+            for (tag, tracked) in &report.tags {
+                println!(
+                    "{}",
+                    porcelain::line(&["tag", tag, &tracked.num_seconds().to_string()])
+                );
+            }
+        }
+    }
+}
+
+pub async fn reopen(current_dir: PathBuf, output: OutputMode, id: String) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let restored = proc.reopen(id).await.unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human if restored => println!(
+            "reopened todo {} and restored it from the archive",
+            proc.config.ids.display(id)
+        ),
+        OutputMode::Human => println!("reopened todo {}", proc.config.ids.display(id)),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["reopened", &id.to_string()]))
+        }
+    }
+}
+
+pub async fn status(current_dir: PathBuf, output: OutputMode, id: String, status: Status) {
+    let mut proc = processor_with_templates(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let next = proc
+        .set_status(id, status)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => {
+            println!("todo {} is {}", proc.config.ids.display(id), status.name())
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["status", &id.to_string(), status.name()]))
+        }
+    }
+    if let Some(next) = next {
+        print_next_occurrence(&proc, id, next, output);
+    }
+}
+
+pub async fn block(current_dir: PathBuf, output: OutputMode, id: String, on: String) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let on = proc.blocked_on(on.as_str());
+    let index = proc
+        .block(id, on.clone(), Utc::now())
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!(
+            "todo {} is blocked by {}, blocker #{index}",
+            proc.config.ids.display(id),
+            proc.describe_blocker(&on)
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&["blocked", &id.to_string(), &index.to_string()])
+        ),
+    }
+}
+
+pub async fn unblock(
+    current_dir: PathBuf,
+    output: OutputMode,
+    id: String,
+    index: Option<usize>,
+    reason: Option<String>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    let cleared = proc
+        .unblock(id, index, reason, Utc::now())
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let status = proc.get(id).unwrap_or_else(|err| failure(err)).data.front_matter.status;
+
+    match output {
+        OutputMode::Human => {
+            let indexes: Vec<String> = cleared.iter().map(|i| format!("#{i}")).collect();
+            println!(
+                "cleared {} of todo {}, it is {}",
+                indexes.join(", "),
+                proc.config.ids.display(id),
+                status.name()
+            )
+        }
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            for index in &cleared {
+                println!(
+                    "{}",
+                    porcelain::line(&["unblocked", &id.to_string(), &index.to_string()])
+                );
+            }
+        }
+    }
+}
+
+pub async fn blocked(current_dir: PathBuf, output: OutputMode) {
+    let proc = processor(&current_dir).await;
+    let blocked = proc.blocked();
+
+    match output {
+        OutputMode::Human if blocked.is_empty() => println!("no todos are blocked"),
+        OutputMode::Human => {
+            let mut rows = vec![];
+            for (todo, blockers) in &blocked {
+                let front_matter = &todo.data.front_matter;
+                let head = [
+                    proc.config.ids.display(front_matter.id),
+                    todo.data.title().unwrap_or("-").to_string(),
+                ];
+                if blockers.is_empty() {
+                    let rest = [String::new(), "-".to_string(), String::new()];
+                    rows.push([head.as_slice(), &rest].concat());
+                }
+                for (i, (index, blocker)) in blockers.iter().enumerate() {
+                    let mut row = match i {
+                        0 => head.to_vec(),
+                        _ => vec![String::new(); 2],
+                    };
+                    row.extend([
+                        format!("#{index}"),
+                        proc.describe_blocker(&blocker.on),
+                        blocker.since.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+                    ]);
+                    rows.push(row);
+                }
+            }
+            let headers = ["ID", "TITLE", "#", "BLOCKED BY", "SINCE"];
+            print!("{}", table::render(&headers, &rows));
+        }
+        OutputMode::Quiet => {
+            for (todo, _) in &blocked {
+                println!("{}", todo.data.front_matter.id);
+            }
+        }
+        OutputMode::Porcelain => {
+            for (todo, blockers) in &blocked {
+                let id = todo.data.front_matter.id.to_string();
+                if blockers.is_empty() {
+                    println!("{}", porcelain::line(&["blocker", &id, "", "", "", ""]));
+                }
+                for (index, blocker) in blockers {
+                    let (kind, on) = match &blocker.on {
+                        BlockedOn::Todo(other) => ("todo", other.to_string()),
+                        BlockedOn::External(what) => ("external", what.clone()),
+                    };
+                    println!(
+                        "{}",
+                        porcelain::line(&[
+                            "blocker",
+                            &id,
+                            &index.to_string(),
+                            kind,
+                            &on,
+                            &blocker.since.to_rfc3339(),
+                        ])
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub async fn done(current_dir: PathBuf, output: OutputMode, selection: Selection) {
+    let mut proc = processor_with_templates(&current_dir).await;
+
+    let ids = select_todos(&proc, &selection, output);
+    if selection.dry_run {
+        return print_preview(&proc, "complete", &ids, output);
+    }
+
+    let mut failed = 0;
+    for id in ids {
+        let next = match proc.complete(id).await {
+            Ok(next) => next,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+
+        match output {
+            OutputMode::Human => {
+                println!("completed todo {}", proc.config.ids.display(id))
+            }
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Completed { id })
+            }
+        }
+        if let Some(next) = next {
+            print_next_occurrence(&proc, id, next, output);
+        }
+    }
+    exit_if_failed(failed);
+}
+
+pub async fn cancel(
+    current_dir: PathBuf,
+    output: OutputMode,
+    selection: Selection,
+    reason: String,
+    superseded_by: Option<String>,
+) {
+    let mut proc = processor(&current_dir).await;
+
+    let ids = select_todos(&proc, &selection, output);
+    let superseded_by = superseded_by
+        .map(|other| proc.resolve_id(other.as_str()))
+        .transpose()
+        .unwrap_or_else(|err| failure(err));
+    if selection.dry_run {
+        return print_preview(&proc, "cancel", &ids, output);
+    }
+
+    let mut failed = 0;
+    for id in ids {
+        if let Err(err) = proc.cancel(id, reason.clone(), superseded_by).await {
+            eprintln!("Error: {err}");
+            failed += 1;
+            continue;
+        }
+
+        match output {
+            OutputMode::Human => {
+                println!("cancelled todo {}", proc.config.ids.display(id))
+            }
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Cancelled { id })
+            }
+        }
+    }
+    exit_if_failed(failed);
+}
+
+pub async fn checklist_add(current_dir: PathBuf, output: OutputMode, id: String, from: String) {
+    let mut proc = processor(&current_dir).await;
+
+    let id = proc.resolve_id(id.as_str()).unwrap_or_else(|err| failure(err));
+    if let Err(err) = proc.add_checklist(id, from.as_str()).await {
+        failure(err);
+    }
+
+    match output {
+        OutputMode::Human => println!(
+            "added checklist '{from}' to todo {}",
+            proc.config.ids.display(id)
+        ),
+        OutputMode::Quiet => println!("{id}"),
+        OutputMode::Porcelain => {
+            println!("{}", porcelain::line(&["updated", &id.to_string()]))
+        }
+    }
+}
+
+pub async fn template_test(current_dir: PathBuf, output: OutputMode, update: bool) {
+    let hbs = Arc::new(init_hbs(&current_dir).unwrap_or_else(|err| failure(err)));
+    let fixtures = fixtures::discover(&current_dir.join("templates"))
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    let mut failed = 0;
+    for fixture in &fixtures {
+        let outcome = fixtures::run(hbs.clone(), fixture, update)
+            .await
+            .unwrap_or_else(|err| failure(err));
+        if outcome.is_failure() {
+            failed += 1;
+        }
+        match output {
+            OutputMode::Human => println!("{}: {outcome}", fixture.name()),
+            OutputMode::Quiet => {
+                if outcome.is_failure() {
+                    println!("{}", fixture.name())
+                }
+            }
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[outcome.kind(), fixture.name().as_str()])
+            ),
+        }
+    }
+
+    if failed > 0 {
+        failure(format!("{failed} of {} template fixtures failed", fixtures.len()));
+    }
+    if output == OutputMode::Human && fixtures.is_empty() {
+        println!("no fixtures in templates/{}", fixtures::TESTS_DIR);
+    }
+}
+
+pub async fn sync_templates(current_dir: PathBuf, output: OutputMode) {
+    let config = Config::load(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let synced = sync::pull(&current_dir, &config.templates)
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    match output {
+        OutputMode::Human => println!(
+            "synced {} files from {} at {} into templates/{}",
+            synced.files,
+            synced.url,
+            &synced.revision[..synced.revision.len().min(12)],
+            sync::MANAGED_DIR
+        ),
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&["synced", synced.revision.as_str(), synced.url.as_str()])
+        ),
+    }
+}
+
+pub async fn print(
+    current_dir: PathBuf,
+    layout: print::Layout,
+    tags: Vec<String>,
+    target: Option<PathBuf>,
+) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let config = Config::load(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let mut todos: Vec<_> = collection
+        .values()
+        .filter(|todo| {
+            tags.iter()
+                .all(|tag| todo.data.front_matter.tags.contains(tag))
+        })
+        .collect();
+    todos.sort_by_key(|todo| {
+        let front_matter = &todo.data.front_matter;
+        (front_matter.due_at.is_none(), front_matter.due_at, front_matter.id)
+    });
+
+    let title = format!("Todos {}", Utc::now().format("%Y-%m-%d"));
+    let html = print::render(title.as_str(), &todos, layout, &config.ids);
+    match target {
+        Some(target) => {
+            if let Err(err) = tokio::fs::write(target, html).await {
+                failure(err);
+            }
+        }
+        None => print!("{html}"),
+    }
+}
+
+pub async fn generate(output: OutputMode, count: usize, seed: Option<u64>, into: Option<PathBuf>) {
+    let seed = seed.unwrap_or_else(|| Utc::now().timestamp_micros() as u64);
+    let into = match into {
+        Some(into) => std::path::absolute(into).unwrap_or_else(|err| failure(err)),
+        None => std::env::temp_dir().join(format!("todo-generated-{seed}")),
+    };
+    let target = CommandProcessor::new(
+        Handlebars::new(),
+        Collection::new(),
+        Config::default(),
+        into,
+    );
+    let initialized = target
+        .is_initialized()
+        .await
+        .unwrap_or_else(|err| failure(err));
+    if !initialized {
+        if let Err(err) = target.init().await {
+            failure(err);
+        }
+    }
+    let proc = processor(&target.data_dir).await;
+
+    if let Err(err) = proc.generate(count, seed).await {
+        failure(err);
+    }
+
+    let data_dir = proc.data_dir.to_str().unwrap();
+    match output {
+        OutputMode::Human => {
+            println!("generated {count} todos in {data_dir} with seed {seed}")
+        }
+        OutputMode::Quiet => println!("{data_dir}"),
+        OutputMode::Porcelain => println!(
+            "{}",
+            porcelain::line(&[
+                "generated",
+                data_dir,
+                &count.to_string(),
+                &seed.to_string(),
+            ])
+        ),
+    }
+}
+
+pub async fn import_trello(current_dir: PathBuf, output: OutputMode, path: PathBuf) {
+    let proc = processor(&current_dir).await;
+
+    let board: trello::Board = tokio::fs::read_to_string(path.as_path())
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|content| Ok(serde_json::from_str(content.as_str())?))
+        .unwrap_or_else(|err| failure(format!("cannot read {}: {err}", path.display())));
+
+    let imported = proc
+        .import_drafts(board.into_drafts())
+        .await
+        .unwrap_or_else(|err| failure(err));
+
+    for todo_file in &imported {
+        let id = todo_file.data.front_matter.id;
+        match output {
+            OutputMode::Human => {}
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => println!(
+                "{}",
+                porcelain::line(&[
+                    "created",
+                    &id.to_string(),
+                    todo_file
+                        .path
+                        .strip_prefix(&proc.data_dir)
+                        .unwrap()
+                        .to_str()
+                        .unwrap(),
+                ])
+            ),
+        }
+    }
+    if output == OutputMode::Human {
+        println!("imported {} todos from trello", imported.len());
+    }
+}
+
+pub async fn export_trello(current_dir: PathBuf, target: Option<PathBuf>) {
+    let collection = load_collection(&current_dir)
+        .await
+        .unwrap_or_else(|err| failure(err));
+    let name = current_dir
+        .file_name()
+        .map_or("todo".into(), |name| name.to_string_lossy());
+    let board = trello::Board::from_collection(&name, &collection);
+    let json = serde_json::to_string_pretty(&board).unwrap();
+
+    match target {
+        Some(target) => {
+            if let Err(err) = tokio::fs::write(target, json).await {
+                failure(err);
+            }
+        }
+        None => println!("{json}"),
+    }
+}
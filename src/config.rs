@@ -0,0 +1,424 @@
+//! Per-collection settings, read from `config.toml` in the data dir.
+//!
+//! Every section is optional; a missing file or key falls back to the
+//! defaults below.
+
+use crate::{dates, DataId, FrontMatterStyle};
+use anyhow::anyhow;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Deserialize;
+use std::path::Path;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub title: TitleRules,
+    pub tags: TagRules,
+    pub ids: IdFormat,
+    pub templates: TemplateSource,
+    pub done: DoneRules,
+    pub diff: DiffRules,
+    pub next: NextWeights,
+    pub due: DueRules,
+    pub files: FileRules,
+}
+
+impl Config {
+    pub async fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(CONFIG_FILE);
+        if !tokio::fs::try_exists(path.as_path()).await? {
+            return Ok(Self::default());
+        }
+
+        let content = tokio::fs::read_to_string(path.as_path()).await?;
+        toml::from_str(content.as_str()).map_err(|err| anyhow!("invalid {CONFIG_FILE}: {err}"))
+    }
+}
+
+/// Normalization applied to every title before a todo is written.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TitleRules {
+    pub casing: TitleCasing,
+    pub strip_emoji: bool,
+    pub max_length: Option<usize>,
+    pub truncate: TruncatePolicy,
+}
+
+impl Default for TitleRules {
+    fn default() -> Self {
+        Self {
+            casing: TitleCasing::Keep,
+            strip_emoji: false,
+            max_length: None,
+            truncate: TruncatePolicy::Ellipsis,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleCasing {
+    /// Leave casing as typed.
+    Keep,
+    /// Upper-case the first letter only.
+    Sentence,
+    /// Upper-case the first letter of every word but short joining words.
+    Title,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TruncatePolicy {
+    /// Cut at `max_length` and mark the cut with `…`.
+    Ellipsis,
+    /// Cut at `max_length` without a marker.
+    Cut,
+    /// Refuse titles longer than `max_length`.
+    Reject,
+}
+
+/// Words kept lower-case by title casing unless they start the title.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "the", "to",
+    "via", "vs",
+];
+
+impl TitleRules {
+    /// Applies the rules to `title`. Whitespace is always trimmed and
+    /// collapsed; `None` is returned when nothing is left.
+    pub fn normalize(&self, title: &str) -> anyhow::Result<Option<String>> {
+        let title: String = if self.strip_emoji {
+            title.chars().filter(|ch| !is_emoji(*ch)).collect()
+        } else {
+            title.to_string()
+        };
+
+        let words: Vec<_> = title.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(None);
+        }
+
+        let words: Vec<String> = match self.casing {
+            TitleCasing::Keep => words.into_iter().map(str::to_string).collect(),
+            TitleCasing::Sentence => words
+                .into_iter()
+                .enumerate()
+                .map(|(i, word)| if i == 0 { capitalize(word) } else { word.to_string() })
+                .collect(),
+            TitleCasing::Title => words
+                .into_iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i > 0 && MINOR_WORDS.contains(&word.to_lowercase().as_str()) {
+                        word.to_lowercase()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+        };
+
+        let title = words.join(" ");
+        match self.max_length {
+            Some(max) if title.chars().count() > max => match self.truncate {
+                TruncatePolicy::Reject => Err(anyhow!(
+                    "title is longer than the configured maximum of {max} characters"
+                )),
+                TruncatePolicy::Cut => Ok(Some(title.chars().take(max).collect())),
+                TruncatePolicy::Ellipsis => {
+                    let cut: String = title.chars().take(max.saturating_sub(1)).collect();
+                    Ok(Some(format!("{}…", cut.trim_end())))
+                }
+            },
+            _ => Ok(Some(title)),
+        }
+    }
+}
+
+/// Character policy enforced on tags before a todo is written.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct TagRules {
+    pub lowercase: bool,
+    /// Replaces runs of whitespace, so `Work Stuff` becomes `work-stuff`.
+    pub separator: String,
+    /// Characters allowed in a tag besides letters and digits.
+    pub allowed_separators: Vec<char>,
+}
+
+impl Default for TagRules {
+    fn default() -> Self {
+        Self {
+            lowercase: true,
+            separator: "-".to_string(),
+            allowed_separators: vec!['-', '_', ':', '/', '.'],
+        }
+    }
+}
+
+impl TagRules {
+    /// Returns the tag as the policy wants it, or an error naming the
+    /// offending character when normalizing can't fix it.
+    pub fn normalize(&self, tag: &str) -> anyhow::Result<String> {
+        let mut normalized = tag.split_whitespace().collect::<Vec<_>>().join(&self.separator);
+        if self.lowercase {
+            normalized = normalized.to_lowercase();
+        }
+
+        if normalized.is_empty() {
+            return Err(anyhow!("empty tags are not allowed"));
+        }
+        if let Some(invalid) = normalized
+            .chars()
+            .find(|ch| !ch.is_alphanumeric() && !self.allowed_separators.contains(ch))
+        {
+            return Err(anyhow!(
+                "tag '{tag}' contains '{invalid}', only letters, digits and {} are allowed",
+                self.allowed_separators
+                    .iter()
+                    .map(|ch| format!("'{ch}'"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ));
+        }
+
+        Ok(normalized)
+    }
+
+    /// Normalizes and de-duplicates `tags`, in order, reporting every tag
+    /// that changed on stderr.
+    pub fn normalize_all(&self, tags: Vec<String>) -> anyhow::Result<Vec<String>> {
+        let mut normalized: Vec<String> = vec![];
+        for tag in tags {
+            let fixed = self.normalize(tag.as_str())?;
+            if fixed != tag {
+                eprintln!("note: tag '{tag}' normalized to '{fixed}'");
+            }
+            if !normalized.contains(&fixed) {
+                normalized.push(fixed);
+            }
+        }
+        Ok(normalized)
+    }
+}
+
+/// How ids are shown to humans and in filenames, e.g. `T-42` or `0x2a`.
+/// Porcelain and quiet output always use plain decimal ids.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdFormat {
+    pub prefix: String,
+    /// Zero-padded width; filenames fall back to 10 digits when unset.
+    pub width: Option<usize>,
+    pub radix: IdRadix,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdRadix {
+    #[default]
+    Decimal,
+    Hex,
+}
+
+/// Filenames have always been padded to 10 digits.
+const FILENAME_ID_WIDTH: usize = 10;
+
+impl IdFormat {
+    pub fn display(&self, id: DataId) -> String {
+        self.format(id, self.width.unwrap_or(0))
+    }
+
+    pub fn file_stem(&self, id: DataId) -> String {
+        self.format(id, self.width.unwrap_or(FILENAME_ID_WIDTH))
+    }
+
+    fn format(&self, id: DataId, width: usize) -> String {
+        match self.radix {
+            IdRadix::Decimal => format!("{}{id:0width$}", self.prefix),
+            IdRadix::Hex => format!("{}{id:0width$x}", self.prefix),
+        }
+    }
+
+    /// Accepts the configured form with or without prefix and padding,
+    /// `#42`, and `0x2a` regardless of the configured radix.
+    pub fn parse(&self, input: &str) -> anyhow::Result<DataId> {
+        let mut digits = input.trim();
+        digits = digits.strip_prefix('#').unwrap_or(digits);
+        if !self.prefix.is_empty()
+            && digits
+                .get(..self.prefix.len())
+                .is_some_and(|head| head.eq_ignore_ascii_case(self.prefix.as_str()))
+        {
+            digits = &digits[self.prefix.len()..];
+        }
+
+        let parsed = match digits.strip_prefix("0x") {
+            Some(hex) => DataId::from_str_radix(hex, 16),
+            None => match self.radix {
+                IdRadix::Decimal => digits.parse(),
+                IdRadix::Hex => DataId::from_str_radix(digits, 16),
+            },
+        };
+        parsed.map_err(|_| anyhow!("'{input}' is not a valid id, expected e.g. {}", self.display(42)))
+    }
+}
+
+/// Checks `done` makes before completing a todo.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct DoneRules {
+    /// Refuse while the "Acceptance Criteria" checklist has unchecked items.
+    pub require_acceptance_criteria: bool,
+    /// Days a completed todo stays in the default `list` and in `today`,
+    /// faded, before only filters for done todos show it.
+    pub keep_visible_days: u64,
+}
+
+impl Default for DoneRules {
+    fn default() -> Self {
+        Self {
+            require_acceptance_criteria: true,
+            keep_visible_days: 0,
+        }
+    }
+}
+
+/// How versions of a todo are compared by `diff-branches` and
+/// `show --activity`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiffRules {
+    /// Front matter fields whose changes alone don't count as an edit, like
+    /// an `updated_at` or etag a sync tool keeps current.
+    pub ignore_fields: Vec<String>,
+}
+
+/// How much each factor counts when `next` picks the most urgent todo.
+#[derive(Deserialize, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct NextWeights {
+    /// How close the due date is.
+    pub due: f64,
+    pub priority: f64,
+    /// How long ago the todo was created.
+    pub age: f64,
+    /// How many open todos wait for this one.
+    pub dependents: f64,
+}
+
+impl Default for NextWeights {
+    fn default() -> Self {
+        Self {
+            due: 4.0,
+            priority: 3.0,
+            age: 1.0,
+            dependents: 2.0,
+        }
+    }
+}
+
+/// How `new` suggests a due date for todos created without one.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DueRules {
+    /// Open todos due on one day before a suggestion moves to the next.
+    pub max_per_day: Option<usize>,
+    /// Tried in order; the first matching rule gives the suggestion.
+    pub rules: Vec<DueRule>,
+}
+
+/// Suggests `due`, anything `dates::parse` understands like `in 3 days` or
+/// `end of month`, for todos from `template` or carrying `tag`, or both
+/// when both are set.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DueRule {
+    pub template: Option<String>,
+    pub tag: Option<String>,
+    pub due: String,
+}
+
+impl DueRules {
+    /// The due date the first rule matching a todo from `template` with
+    /// `tags` suggests, with the reason, moved past days on which
+    /// `due_on` counts `max_per_day` todos or more.
+    pub fn suggest(
+        &self,
+        template: &str,
+        tags: &[String],
+        now: DateTime<Local>,
+        due_on: impl Fn(NaiveDate) -> usize,
+    ) -> anyhow::Result<Option<(DateTime<Utc>, String)>> {
+        let Some(rule) = self.rules.iter().find(|rule| {
+            rule.template.as_ref().is_none_or(|wanted| wanted == template)
+                && rule.tag.as_ref().is_none_or(|wanted| tags.contains(wanted))
+        }) else {
+            return Ok(None);
+        };
+        let mut due_at = dates::parse(rule.due.as_str(), now)
+            .map_err(|err| anyhow!("invalid due rule in {CONFIG_FILE}: {err}"))?;
+        let mut reason = match (&rule.template, &rule.tag) {
+            (Some(template), Some(tag)) => format!("template {template} with tag {tag}"),
+            (Some(template), None) => format!("template {template}"),
+            (None, Some(tag)) => format!("tag {tag}"),
+            (None, None) => "default".to_string(),
+        };
+
+        if let Some(max) = self.max_per_day {
+            let day = due_at.with_timezone(&Local).date_naive();
+            let free = day.iter_days().take(366).find(|day| due_on(*day) < max);
+            if let Some(free) = free.filter(|free| *free != day) {
+                let time = due_at.with_timezone(&Local).time();
+                due_at = free
+                    .and_time(time)
+                    .and_local_timezone(Local)
+                    .latest()
+                    .map_or(due_at, |at| at.with_timezone(&Utc));
+                reason.push_str(", moved past days already full");
+            }
+        }
+        Ok(Some((due_at, reason)))
+    }
+}
+
+/// Where `sync-templates` pulls a shared template set from.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateSource {
+    /// Anything `git clone` accepts.
+    pub source: Option<String>,
+    /// Branch or tag to check out instead of the default branch.
+    pub rev: Option<String>,
+}
+
+/// How todo files are written.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileRules {
+    /// The front matter style of new todos; existing files keep theirs
+    /// until `migrate` converts them.
+    pub front_matter: FrontMatterStyle,
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_emoji(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1F000..=0x1FAFF // pictographs, emoticons, symbols, flags
+        | 0x2600..=0x27BF // misc symbols and dingbats
+        | 0x2B00..=0x2BFF // arrows, stars
+        | 0xFE00..=0xFE0F // variation selectors
+        | 0x200D          // zero width joiner
+        | 0xE0020..=0xE007F // tag sequences
+    )
+}
@@ -0,0 +1,174 @@
+//! How tags are used together, for cleaning up a tag vocabulary: which
+//! tags co-occur most, which are likely synonyms and which are used once.
+
+use crate::{closest_match, Collection};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Share of the rarer tag's todos that also carry the other tag from
+/// which the two are taken for synonyms.
+const SYNONYM_OVERLAP: f64 = 0.8;
+
+/// Two tags on the same todos.
+#[derive(Debug, PartialEq)]
+pub struct Pair<'a> {
+    pub tags: (&'a str, &'a str),
+    pub count: usize,
+}
+
+/// A tag that, where it is used, nearly always comes with `common`.
+#[derive(Debug, PartialEq)]
+pub struct Synonym<'a> {
+    pub rare: &'a str,
+    pub common: &'a str,
+    /// Todos carrying both tags.
+    pub shared: usize,
+    /// Todos carrying `rare`.
+    pub uses: usize,
+}
+
+/// A tag used by a single todo, with the known tag closest in spelling.
+#[derive(Debug, PartialEq)]
+pub struct Orphan<'a> {
+    pub tag: &'a str,
+    pub closest: Option<&'a str>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report<'a> {
+    /// Most frequent first.
+    pub pairs: Vec<Pair<'a>>,
+    pub synonyms: Vec<Synonym<'a>>,
+    pub orphans: Vec<Orphan<'a>>,
+}
+
+pub fn report(collection: &Collection) -> Report<'_> {
+    let mut uses: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut pairs: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for todo in collection.values() {
+        let tags: BTreeSet<&str> = todo.data.front_matter.tags.iter().map(String::as_str).collect();
+        for (i, tag) in tags.iter().enumerate() {
+            *uses.entry(tag).or_default() += 1;
+            for other in tags.iter().skip(i + 1) {
+                *pairs.entry((tag, other)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut report = Report::default();
+    for (&(a, b), &shared) in &pairs {
+        let (rare, common) = match uses[a] <= uses[b] {
+            true => (a, b),
+            false => (b, a),
+        };
+        // a tag used once trivially overlaps with every tag next to it
+        if uses[rare] > 1 && shared as f64 >= SYNONYM_OVERLAP * uses[rare] as f64 {
+            let uses = uses[rare];
+            report.synonyms.push(Synonym { rare, common, shared, uses });
+        }
+        report.pairs.push(Pair { tags: (a, b), count: shared });
+    }
+    report.pairs.sort_by(|a, b| b.count.cmp(&a.count).then(a.tags.cmp(&b.tags)));
+    report.synonyms.sort_by(|a, b| b.shared.cmp(&a.shared).then(a.rare.cmp(b.rare)));
+
+    for (&tag, _) in uses.iter().filter(|(_, &count)| count == 1) {
+        let others = uses.iter().filter(|(&other, &count)| other != tag && count > 1);
+        let closest = closest_match(tag, others.map(|(&other, _)| other));
+        report.orphans.push(Orphan { tag, closest });
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TodoData, TodoFile};
+    use std::str::FromStr;
+
+    fn collection(tags: &[&[&str]]) -> Collection {
+        (1..)
+            .zip(tags)
+            .map(|(id, tags)| {
+                let tags: Vec<String> = tags.iter().map(|tag| format!("{tag:?}")).collect();
+                let text = format!(
+                    "+++\nid = {id}\ncreated_at = \"2025-01-01T09:00:00Z\"\n\
+                     tags = [{}]\n+++\n\n# Todo {id}\n",
+                    tags.join(", ")
+                );
+                let data = TodoData::from_str(&text).unwrap();
+                (id, TodoFile { path: format!("{id}.md").into(), data })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_pairs_most_frequent_first() {
+        let collection = collection(&[
+            &["backend", "api"],
+            &["api", "backend", "urgent"],
+            &["backend", "api"],
+            &["urgent", "home"],
+            &["urgent", "home", "urgent"],
+            &[],
+        ]);
+        let report = report(&collection);
+        let pairs: Vec<_> = report.pairs.iter().map(|pair| (pair.tags, pair.count)).collect();
+        assert_eq!(
+            pairs,
+            [
+                (("api", "backend"), 3),
+                (("home", "urgent"), 2),
+                (("api", "urgent"), 1),
+                (("backend", "urgent"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_synonyms_by_overlap() {
+        let collection = collection(&[
+            &["bug", "defect"],
+            &["bug", "defect"],
+            &["bug", "defect"],
+            &["bug", "defect"],
+            &["defect"],
+            &["bug", "ui"],
+            &["ui"],
+            &["ui", "home"],
+        ]);
+        let report = report(&collection);
+        // defect is on 4 of 5 todos with bug, ui on 1 of 3
+        assert_eq!(
+            report.synonyms,
+            [Synonym { rare: "bug", common: "defect", shared: 4, uses: 5 }]
+        );
+    }
+
+    #[test]
+    fn finds_orphans_with_their_closest_tag() {
+        let collection = collection(&[
+            &["backend"],
+            &["backend"],
+            &["bakend", "misc"],
+            &["frontend", "frontend"],
+        ]);
+        let report = report(&collection);
+        assert_eq!(
+            report.orphans,
+            [
+                Orphan { tag: "bakend", closest: Some("backend") },
+                Orphan { tag: "frontend", closest: None },
+                Orphan { tag: "misc", closest: None },
+            ]
+        );
+        // orphans next to each other are no synonyms
+        assert!(report.synonyms.is_empty());
+    }
+
+    #[test]
+    fn empty_collection() {
+        let collection = Collection::new();
+        let report = report(&collection);
+        assert!(report.pairs.is_empty() && report.synonyms.is_empty() && report.orphans.is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+//! Reading dates the way people type them.
+//!
+//! Besides RFC 3339 timestamps and `YYYY-MM-DD`, this understands
+//! `today`/`eod`, `tomorrow`, weekday names (`friday`, `next fri`),
+//! `next week`/`next month`, `end of month`/`eom` and `in 3 days` (or
+//! weeks, months). Anything short of a timestamp means the last second of
+//! that day in local time.
+//!
+//! Durations, as taken by `snooze` and `archive`, are a count with a unit: `12h`, `3d`, `2w` or
+//! `1m` for a month.
+
+use anyhow::anyhow;
+use chrono::{
+    DateTime, Datelike, Days, Local, Months, NaiveDate, TimeDelta, TimeZone, Utc, Weekday,
+};
+
+const EXPECTED: &str =
+    "expected YYYY-MM-DD, an RFC 3339 timestamp, or something like 'tomorrow' or 'in 3 days'";
+
+/// Parses `text` relative to `now`.
+pub fn parse(text: &str, now: DateTime<Local>) -> anyhow::Result<DateTime<Utc>> {
+    parse_in(text, now)
+}
+
+/// Parses `text` relative to `now`, with days in the time zone of `now`.
+fn parse_in<Tz: TimeZone>(text: &str, now: DateTime<Tz>) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let day = parse_day(text.trim().to_lowercase().as_str(), now.date_naive())
+        .ok_or_else(|| anyhow!("invalid date '{text}', {EXPECTED}"))?;
+    end_of_day_in(day, &now.timezone())
+}
+
+/// The last second of `day` in local time.
+pub fn end_of_day(day: NaiveDate) -> anyhow::Result<DateTime<Utc>> {
+    end_of_day_in(day, &Local)
+}
+
+/// The last second of `day` in `tz`, the later one if the clock is set
+/// back over it.
+fn end_of_day_in<Tz: TimeZone>(day: NaiveDate, tz: &Tz) -> anyhow::Result<DateTime<Utc>> {
+    day.and_hms_opt(23, 59, 59)
+        .unwrap()
+        .and_local_timezone(tz.clone())
+        .latest()
+        .map(|at| at.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("{day} has no end of day in the local time zone"))
+}
+
+fn parse_day(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(day) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(day);
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        ["today" | "eod"] => Some(today),
+        ["tomorrow"] => today.checked_add_days(Days::new(1)),
+        ["next", "week"] => today.checked_add_days(Days::new(7)),
+        ["next", "month"] => today.checked_add_months(Months::new(1)),
+        ["end", "of", "month"] | ["eom"] => {
+            let first = today.with_day(1)?;
+            first.checked_add_months(Months::new(1))?.pred_opt()
+        }
+        ["next", weekday] | [weekday] => {
+            let weekday = weekday.parse::<Weekday>().ok()?;
+            // always a day after today, so `friday` on a friday is a week out
+            let ahead = (weekday.days_since(today.weekday()) + 6) % 7 + 1;
+            today.checked_add_days(Days::new(ahead.into()))
+        }
+        ["in", count, unit] => {
+            let count: u32 = count.parse().ok()?;
+            match unit.trim_end_matches('s') {
+                "day" => today.checked_add_days(Days::new(count.into())),
+                "week" => today.checked_add_days(Days::new(u64::from(count) * 7)),
+                "month" => today.checked_add_months(Months::new(count)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Moves `at` forward by a duration like `3d`.
+pub fn shift(at: DateTime<Utc>, duration: &str) -> anyhow::Result<DateTime<Utc>> {
+    let shifted = match parse_duration(duration)? {
+        (count, 'h') => at.checked_add_signed(TimeDelta::hours(count.into())),
+        (count, 'd') => at.checked_add_days(Days::new(count.into())),
+        (count, 'w') => at.checked_add_days(Days::new(u64::from(count) * 7)),
+        (count, _) => at.checked_add_months(Months::new(count)),
+    };
+    shifted.ok_or_else(|| anyhow!("{at} plus {duration} is out of range"))
+}
+
+/// Moves `at` back by a duration like `30d`.
+pub fn back(at: DateTime<Utc>, duration: &str) -> anyhow::Result<DateTime<Utc>> {
+    let shifted = match parse_duration(duration)? {
+        (count, 'h') => at.checked_sub_signed(TimeDelta::hours(count.into())),
+        (count, 'd') => at.checked_sub_days(Days::new(count.into())),
+        (count, 'w') => at.checked_sub_days(Days::new(u64::from(count) * 7)),
+        (count, _) => at.checked_sub_months(Months::new(count)),
+    };
+    shifted.ok_or_else(|| anyhow!("{at} minus {duration} is out of range"))
+}
+
+/// Splits a duration into its count and a unit of `h`, `d`, `w` or `m`.
+fn parse_duration(duration: &str) -> anyhow::Result<(u32, char)> {
+    let invalid =
+        || anyhow!("invalid duration '{duration}', expected a number followed by h, d, w or m");
+    let unit = duration.chars().last().ok_or_else(invalid)?;
+    let count: u32 = duration[..duration.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    match unit {
+        'h' | 'd' | 'w' | 'm' => Ok((count, unit)),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveDateTime};
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    fn local(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    /// A zone an hour ahead of UTC, two hours ahead in summer, whose local
+    /// clocks skip the `gap` in spring and go through the `fold` twice in
+    /// autumn.
+    #[derive(Clone)]
+    struct Dst {
+        gap: (NaiveDateTime, NaiveDateTime),
+        fold: (NaiveDateTime, NaiveDateTime),
+    }
+
+    impl Dst {
+        fn berlin_2025() -> Self {
+            Self {
+                gap: (local("2025-03-30 02:00:00"), local("2025-03-30 03:00:00")),
+                fold: (local("2025-10-26 02:00:00"), local("2025-10-26 03:00:00")),
+            }
+        }
+
+        fn offset(hours: i32) -> FixedOffset {
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Dst {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Self::berlin_2025()
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            if *local < self.gap.0 || *local >= self.fold.1 {
+                LocalResult::Single(Self::offset(1))
+            } else if *local < self.gap.1 {
+                LocalResult::None
+            } else if *local < self.fold.0 {
+                LocalResult::Single(Self::offset(2))
+            } else {
+                LocalResult::Ambiguous(Self::offset(2), Self::offset(1))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(12, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = self.gap.0 - TimeDelta::hours(1)..self.fold.1 - TimeDelta::hours(2);
+            match summer.contains(utc) {
+                true => Self::offset(2),
+                false => Self::offset(1),
+            }
+        }
+    }
+
+    #[test]
+    fn relative_days() {
+        // a friday
+        let today = day("2025-01-03");
+        let cases = [
+            ("today", "2025-01-03"),
+            ("eod", "2025-01-03"),
+            ("tomorrow", "2025-01-04"),
+            ("next week", "2025-01-10"),
+            ("next month", "2025-02-03"),
+            ("monday", "2025-01-06"),
+            ("next mon", "2025-01-06"),
+            ("in 0 days", "2025-01-03"),
+            ("in 1 day", "2025-01-04"),
+            ("in 3 weeks", "2025-01-24"),
+            ("in 2 months", "2025-03-03"),
+            ("2025-02-28", "2025-02-28"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_day(text, today), Some(day(expected)), "{text}");
+        }
+    }
+
+    #[test]
+    fn weekday_of_today_is_a_week_out() {
+        let friday = day("2025-01-03");
+        assert_eq!(parse_day("friday", friday), Some(day("2025-01-10")));
+        assert_eq!(parse_day("next fri", friday), Some(day("2025-01-10")));
+        assert_eq!(parse_day("thursday", friday), Some(day("2025-01-09")));
+    }
+
+    #[test]
+    fn month_ends() {
+        assert_eq!(parse_day("tomorrow", day("2025-12-31")), Some(day("2026-01-01")));
+        assert_eq!(parse_day("next month", day("2025-01-31")), Some(day("2025-02-28")));
+        assert_eq!(parse_day("in 1 month", day("2024-01-31")), Some(day("2024-02-29")));
+        assert_eq!(parse_day("in 1 month", day("2025-03-31")), Some(day("2025-04-30")));
+        assert_eq!(parse_day("eom", day("2024-02-01")), Some(day("2024-02-29")));
+        assert_eq!(parse_day("end of month", day("2025-02-28")), Some(day("2025-02-28")));
+        assert_eq!(parse_day("eom", day("2025-12-15")), Some(day("2025-12-31")));
+    }
+
+    #[test]
+    fn invalid_days() {
+        let today = day("2025-01-03");
+        for text in [
+            "",
+            "someday",
+            "2025-02-30",
+            "in x days",
+            "in -1 days",
+            "in 3 fortnights",
+            "next",
+            "next year",
+            "fri day",
+        ] {
+            assert_eq!(parse_day(text, today), None, "{text}");
+        }
+        let now = Utc.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        let err = parse_in("someday", now).unwrap_err().to_string();
+        assert_eq!(err, format!("invalid date 'someday', {EXPECTED}"));
+    }
+
+    #[test]
+    fn parse_is_case_insensitive_and_keeps_timestamps() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 3, 9, 0, 0).unwrap();
+        assert_eq!(parse_in(" Tomorrow ", now).unwrap(), utc("2025-01-04T23:59:59Z"));
+        assert_eq!(
+            parse_in("2025-05-01T10:00:00+02:00", now).unwrap(),
+            utc("2025-05-01T08:00:00Z")
+        );
+    }
+
+    #[test]
+    fn days_end_in_the_time_zone_of_now() {
+        let now = FixedOffset::east_opt(-5 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2025, 1, 3, 22, 0, 0)
+            .unwrap();
+        // already the 4th in UTC, but tomorrow is the 4th in New York
+        assert_eq!(parse_in("tomorrow", now).unwrap(), utc("2025-01-05T04:59:59Z"));
+    }
+
+    #[test]
+    fn days_end_across_daylight_saving_changes() {
+        let zone = Dst::berlin_2025();
+        let at = |text: &str| end_of_day_in(day(text), &zone).unwrap();
+        assert_eq!(at("2025-03-29"), utc("2025-03-29T22:59:59Z"));
+        assert_eq!(at("2025-03-30"), utc("2025-03-30T21:59:59Z"));
+        assert_eq!(at("2025-10-25"), utc("2025-10-25T21:59:59Z"));
+        assert_eq!(at("2025-10-26"), utc("2025-10-26T22:59:59Z"));
+
+        let now = zone.with_ymd_and_hms(2025, 3, 29, 12, 0, 0).unwrap();
+        assert_eq!(parse_in("tomorrow", now).unwrap(), utc("2025-03-30T21:59:59Z"));
+    }
+
+    #[test]
+    fn end_of_day_in_a_clock_change() {
+        // clocks jump over midnight, so the 30th has no 23:59:59
+        let mut zone = Dst::berlin_2025();
+        zone.gap = (local("2025-03-30 23:30:00"), local("2025-03-31 00:30:00"));
+        let err = end_of_day_in(day("2025-03-30"), &zone).unwrap_err().to_string();
+        assert_eq!(err, "2025-03-30 has no end of day in the local time zone");
+
+        // clocks go back over midnight, the later 23:59:59 is taken
+        let mut zone = Dst::berlin_2025();
+        zone.fold = (local("2025-10-26 23:30:00"), local("2025-10-27 00:30:00"));
+        let end = end_of_day_in(day("2025-10-26"), &zone).unwrap();
+        assert_eq!(end, utc("2025-10-26T22:59:59Z"));
+    }
+
+    #[test]
+    fn shift_and_back_by_durations() {
+        let at = utc("2025-01-31T12:00:00Z");
+        assert_eq!(shift(at, "12h").unwrap(), utc("2025-02-01T00:00:00Z"));
+        assert_eq!(shift(at, "3d").unwrap(), utc("2025-02-03T12:00:00Z"));
+        assert_eq!(shift(at, "2w").unwrap(), utc("2025-02-14T12:00:00Z"));
+        assert_eq!(shift(at, "1m").unwrap(), utc("2025-02-28T12:00:00Z"));
+        assert_eq!(shift(utc("2024-01-31T12:00:00Z"), "1m").unwrap(), utc("2024-02-29T12:00:00Z"));
+        assert_eq!(back(utc("2025-03-31T12:00:00Z"), "1m").unwrap(), utc("2025-02-28T12:00:00Z"));
+        assert_eq!(back(at, "30d").unwrap(), utc("2025-01-01T12:00:00Z"));
+        assert_eq!(back(at, "0h").unwrap(), at);
+    }
+
+    #[test]
+    fn invalid_durations() {
+        let at = utc("2025-01-31T12:00:00Z");
+        for duration in ["", "d", "3", "3x", "-1d", "1.5d", "3 d", "dd"] {
+            let err = back(at, duration).unwrap_err().to_string();
+            let expected = "expected a number followed by h, d, w or m";
+            assert_eq!(err, format!("invalid duration '{duration}', {expected}"));
+        }
+        let err = shift(DateTime::<Utc>::MAX_UTC, "1d").unwrap_err().to_string();
+        assert!(err.ends_with("plus 1d is out of range"), "{err}");
+    }
+}
@@ -0,0 +1,248 @@
+//! Todos as JSON documents for scripts and hooks, which can read single
+//! fields with `get` and change them with `set-json` instead of parsing
+//! the markdown themselves.
+//!
+//! A document holds the `front_matter`, the `content` and, derived from
+//! the content and read-only, the `title` and the `checklist` progress:
+//!
+//! ```json
+//! {"front_matter": {"id": 3, "due_at": null, ...}, "content": "...",
+//!  "title": "...", "checklist": {"completed_count": 1, "total_count": 4}}
+//! ```
+
+use crate::checklist;
+use crate::{FrontMatter, TodoData};
+use anyhow::anyhow;
+use serde_json::{json, Map, Value};
+
+const READ_ONLY: [&str; 2] = ["title", "checklist"];
+
+pub fn document(todo: &TodoData) -> anyhow::Result<Value> {
+    let (_, checklists) = checklist::split(todo.body());
+    let (completed_count, total_count) = checklist::progress(&checklists);
+    Ok(json!({
+        "front_matter": serde_json::to_value(&todo.front_matter)?,
+        "content": todo.content,
+        "title": todo.title(),
+        "checklist": {
+            "completed_count": completed_count,
+            "total_count": total_count,
+        },
+    }))
+}
+
+/// The value at a dot-separated `path` like `front_matter.tags.0`, where
+/// numbers index into arrays. A missing last field is null, as optional
+/// front matter fields are left out when unset.
+pub fn get<'a>(document: &'a Value, path: &str) -> anyhow::Result<&'a Value> {
+    if path.split('.').any(str::is_empty) {
+        return Err(anyhow!("'{path}' has an empty field, expected e.g. front_matter.due_at"));
+    }
+    let mut value = document;
+    let mut fields = path.split('.').peekable();
+    while let Some(field) = fields.next() {
+        let next = match value {
+            Value::Object(map) => map.get(field),
+            Value::Array(items) => field.parse().ok().and_then(|index: usize| items.get(index)),
+            _ => return Err(anyhow!("'{path}' goes into '{field}' of a plain value")),
+        };
+        value = match next {
+            Some(next) => next,
+            None if fields.peek().is_none() && value.is_object() => &Value::Null,
+            None => return Err(anyhow!("there is no field '{field}' in '{path}'")),
+        };
+    }
+    Ok(value)
+}
+
+/// How `get` prints a value: strings as they are, null as nothing and
+/// everything else as JSON.
+pub fn raw(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Applies a JSON merge patch (RFC 7386) to the document of `todo`.
+pub fn patch(todo: &mut TodoData, patch: &Value) -> anyhow::Result<()> {
+    if let Some(field) = READ_ONLY.iter().find(|field| patch.get(**field).is_some()) {
+        return Err(anyhow!("'{field}' is derived from the content and cannot be patched"));
+    }
+
+    let mut document = document(todo)?;
+    merge(&mut document, patch);
+    let front_matter: FrontMatter = serde_json::from_value(document["front_matter"].take())
+        .map_err(|err| anyhow!("invalid front_matter after patching: {err}"))?;
+    if front_matter.id != todo.front_matter.id {
+        return Err(anyhow!("the id of a todo cannot be changed"));
+    }
+    let content = match document["content"].take() {
+        Value::String(content) => content,
+        _ => return Err(anyhow!("content has to be a string")),
+    };
+
+    todo.front_matter = front_matter;
+    todo.content = content;
+    Ok(())
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().unwrap();
+    for (key, value) in patch {
+        match value {
+            Value::Null => {
+                target.remove(key);
+            }
+            value => merge(target.entry(key.as_str()).or_insert(Value::Null), value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn todo() -> TodoData {
+        TodoData::from_str(
+            "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\n\
+             due_at = \"2099-01-31T23:59:59Z\"\ntags = [\"a\", \"b\"]\nowner = \"kim\"\n+++\n\n\
+             # Plan\n\n## Steps\n\n- [x] one\n- [ ] two\n",
+        )
+        .unwrap()
+    }
+
+    fn get_raw(path: &str) -> anyhow::Result<String> {
+        Ok(raw(get(&document(&todo())?, path)?))
+    }
+
+    fn patched(patch_text: &str) -> anyhow::Result<TodoData> {
+        let mut todo = todo();
+        patch(&mut todo, &serde_json::from_str(patch_text)?)?;
+        Ok(todo)
+    }
+
+    fn patch_error(patch_text: &str) -> String {
+        patched(patch_text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn gets_fields() {
+        assert_eq!(get_raw("front_matter.id").unwrap(), "3");
+        assert_eq!(get_raw("front_matter.due_at").unwrap(), "2099-01-31T23:59:59Z");
+        assert_eq!(get_raw("front_matter.tags").unwrap(), r#"["a","b"]"#);
+        assert_eq!(get_raw("front_matter.tags.1").unwrap(), "b");
+        assert_eq!(get_raw("front_matter.owner").unwrap(), "kim");
+        assert_eq!(get_raw("title").unwrap(), "Plan");
+        assert_eq!(get_raw("checklist.completed_count").unwrap(), "1");
+        assert_eq!(get_raw("checklist.total_count").unwrap(), "2");
+        // unset optional fields are left out of the front matter
+        assert_eq!(get_raw("front_matter.priority").unwrap(), "");
+    }
+
+    #[test]
+    fn unknown_paths() {
+        let error = |path: &str| get_raw(path).unwrap_err().to_string();
+        assert_eq!(error("front_matter.tags.2"), "there is no field '2' in 'front_matter.tags.2'");
+        assert_eq!(error("front_matter.tags.x"), "there is no field 'x' in 'front_matter.tags.x'");
+        assert_eq!(error("nothing.due_at"), "there is no field 'nothing' in 'nothing.due_at'");
+        assert_eq!(
+            error("front_matter.id.value"),
+            "'front_matter.id.value' goes into 'value' of a plain value"
+        );
+        let empty = "has an empty field, expected e.g. front_matter.due_at";
+        assert_eq!(error(""), format!("'' {empty}"));
+        assert_eq!(error("front_matter..id"), format!("'front_matter..id' {empty}"));
+    }
+
+    #[test]
+    fn patches_front_matter_and_content() {
+        let todo = patched(
+            r#"{"front_matter": {"priority": "high", "due_at": null, "owner": null,
+                "tags": ["c"], "extra": {"nested": 1}}, "content": "\n# New\n"}"#,
+        )
+        .unwrap();
+        let front_matter = &todo.front_matter;
+        assert_eq!(front_matter.priority, Some(crate::Priority::High));
+        assert_eq!(front_matter.due_at, None);
+        assert_eq!(front_matter.tags, ["c"]);
+        assert!(!front_matter.extra.contains_key("owner"));
+        assert_eq!(front_matter.extra["extra"]["nested"].as_integer(), Some(1));
+        assert_eq!(todo.title(), Some("New"));
+    }
+
+    #[test]
+    fn empty_patch_changes_nothing() {
+        let todo = patched("{}").unwrap();
+        assert_eq!(todo.to_bytes(), super::tests::todo().to_bytes());
+    }
+
+    #[test]
+    fn rejected_patches() {
+        assert!(patch_error(r#"{"front_matter": {"tags": "a"}}"#)
+            .starts_with("invalid front_matter after patching: invalid type: string \"a\""));
+        assert!(patch_error(r#"{"front_matter": {"due_at": "soon"}}"#)
+            .starts_with("invalid front_matter after patching:"));
+        assert_eq!(
+            patch_error(r#"{"front_matter": {"created_at": null}}"#),
+            "invalid front_matter after patching: missing field `created_at`"
+        );
+        let id_change = patch_error(r#"{"front_matter": {"id": 4}}"#);
+        assert_eq!(id_change, "the id of a todo cannot be changed");
+        assert_eq!(patch_error(r#"{"content": 7}"#), "content has to be a string");
+        assert_eq!(patch_error(r#"{"content": null}"#), "content has to be a string");
+        assert_eq!(
+            patch_error(r#"{"title": "Other"}"#),
+            "'title' is derived from the content and cannot be patched"
+        );
+        assert_eq!(
+            patch_error(r#"{"checklist": null}"#),
+            "'checklist' is derived from the content and cannot be patched"
+        );
+        assert!(patch_error(r#"{"front_matter": 5}"#).starts_with("invalid front_matter"));
+    }
+
+    #[test]
+    fn failed_patches_leave_the_todo_alone() {
+        let mut todo = todo();
+        let before = todo.to_bytes();
+        let patch_value = serde_json::json!({"front_matter": {"id": 9}, "content": "x"});
+        assert!(patch(&mut todo, &patch_value).is_err());
+        assert_eq!(todo.to_bytes(), before);
+    }
+
+    #[test]
+    fn merge_follows_rfc_7386() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+        for (mut target, patch, expected) in cases {
+            merge(&mut target, &patch);
+            assert_eq!(target, expected, "{patch}");
+        }
+    }
+}
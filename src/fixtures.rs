@@ -0,0 +1,137 @@
+//! Golden rendering tests for templates.
+//!
+//! A fixture is a pair of files below `templates/tests/<template>/`:
+//! `<case>.toml` holds the variables to render with and `<case>.md` the
+//! expected output.
+
+use crate::render_sandboxed;
+use anyhow::anyhow;
+use handlebars::Handlebars;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const TESTS_DIR: &str = "tests";
+
+#[derive(Debug)]
+pub struct Fixture {
+    pub template: String,
+    pub case: String,
+    vars: PathBuf,
+}
+
+impl Fixture {
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.template, self.case)
+    }
+
+    fn golden(&self) -> PathBuf {
+        self.vars.with_extension("md")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    /// The first line that differs, 1-based.
+    Failed(usize),
+    /// No golden output was recorded yet.
+    Missing,
+    Updated,
+}
+
+impl Outcome {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Outcome::Passed => "passed",
+            Outcome::Failed(_) => "failed",
+            Outcome::Missing => "missing",
+            Outcome::Updated => "updated",
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Outcome::Failed(_) | Outcome::Missing)
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Failed(line) => write!(f, "failed, output differs at line {line}"),
+            Outcome::Missing => write!(f, "missing, rerun with --update to record it"),
+            outcome => write!(f, "{}", outcome.kind()),
+        }
+    }
+}
+
+/// Every fixture below `templates/tests/`, sorted by name.
+pub async fn discover(templates_dir: &Path) -> anyhow::Result<Vec<Fixture>> {
+    let tests_dir = templates_dir.join(TESTS_DIR);
+    let mut fixtures = vec![];
+    if !tokio::fs::try_exists(tests_dir.as_path()).await? {
+        return Ok(fixtures);
+    }
+
+    let mut templates = tokio::fs::read_dir(tests_dir.as_path()).await?;
+    while let Some(template) = templates.next_entry().await? {
+        if !template.file_type().await?.is_dir() {
+            continue;
+        }
+        let mut cases = tokio::fs::read_dir(template.path()).await?;
+        while let Some(case) = cases.next_entry().await? {
+            let path = case.path();
+            if path.extension().is_none_or(|ext| ext != "toml") {
+                continue;
+            }
+            fixtures.push(Fixture {
+                template: template.file_name().to_string_lossy().into_owned(),
+                case: path.file_stem().unwrap().to_string_lossy().into_owned(),
+                vars: path,
+            });
+        }
+    }
+
+    fixtures.sort_by_key(Fixture::name);
+    Ok(fixtures)
+}
+
+/// Renders the fixture and compares it against, or with `update`
+/// records, its golden output.
+pub async fn run(
+    hbs: Arc<Handlebars<'static>>,
+    fixture: &Fixture,
+    update: bool,
+) -> anyhow::Result<Outcome> {
+    if hbs.get_template(fixture.template.as_str()).is_none() {
+        return Err(anyhow!(
+            "fixture {} is for template '{}', which does not exist",
+            fixture.name(),
+            fixture.template
+        ));
+    }
+
+    let vars: toml::Table = toml::from_str(&tokio::fs::read_to_string(&fixture.vars).await?)
+        .map_err(|err| anyhow!("invalid fixture {}: {err}", fixture.name()))?;
+    let rendered = render_sandboxed(hbs, fixture.template.as_str(), vars).await?;
+
+    let golden = fixture.golden();
+    if update {
+        tokio::fs::write(golden.as_path(), rendered).await?;
+        return Ok(Outcome::Updated);
+    }
+    if !tokio::fs::try_exists(golden.as_path()).await? {
+        return Ok(Outcome::Missing);
+    }
+
+    let expected = tokio::fs::read_to_string(golden.as_path()).await?;
+    if expected == rendered {
+        return Ok(Outcome::Passed);
+    }
+    let line = expected
+        .split('\n')
+        .zip(rendered.split('\n'))
+        .take_while(|(expected, actual)| expected == actual)
+        .count();
+    Ok(Outcome::Failed(line + 1))
+}
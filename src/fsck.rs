@@ -0,0 +1,141 @@
+//! Content-hash manifest of a collection, used to detect files changed
+//! behind the tool's back (bit-rot, sync tools, stray edits).
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "manifest.toml";
+
+/// Paths below the data dir that make up the collection.
+const TRACKED: &[&str] = &[
+    "tasks",
+    crate::archive::ARCHIVE_DIR,
+    "templates",
+    crate::config::CONFIG_FILE,
+];
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// sha256 per file, keyed by path relative to the data dir
+    pub files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    pub async fn build(data_dir: &Path) -> anyhow::Result<Self> {
+        let mut files = BTreeMap::new();
+        for path in tracked_files(data_dir).await? {
+            let content = tokio::fs::read(data_dir.join(path.as_path())).await?;
+            files.insert(manifest_key(path.as_path()), hash(content.as_slice()));
+        }
+
+        Ok(Self {
+            created_at: chrono::Utc::now(),
+            files,
+        })
+    }
+
+    pub async fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(MANIFEST_FILE);
+        let content = tokio::fs::read_to_string(path.as_path())
+            .await
+            .map_err(|err| anyhow!("cannot read {MANIFEST_FILE}: {err}"))?;
+        Ok(toml::from_str(content.as_str())?)
+    }
+
+    pub async fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(data_dir.join(MANIFEST_FILE), toml::to_string(self)?).await?;
+        Ok(())
+    }
+
+    /// Lists every file whose state differs between `self` (the recorded
+    /// manifest) and `current`.
+    pub fn diff(&self, current: &Manifest) -> Vec<Difference> {
+        let mut differences = vec![];
+        for (path, recorded) in &self.files {
+            match current.files.get(path) {
+                None => differences.push(Difference::Missing(path.clone())),
+                Some(actual) if actual != recorded => {
+                    differences.push(Difference::Modified(path.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for path in current.files.keys() {
+            if !self.files.contains_key(path) {
+                differences.push(Difference::Added(path.clone()));
+            }
+        }
+        differences
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Difference {
+    Modified(String),
+    Missing(String),
+    Added(String),
+}
+
+impl Difference {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Difference::Modified(_) => "modified",
+            Difference::Missing(_) => "missing",
+            Difference::Added(_) => "added",
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            Difference::Modified(path) | Difference::Missing(path) | Difference::Added(path) => {
+                path
+            }
+        }
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<9} {}", self.kind(), self.path())
+    }
+}
+
+fn hash(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Uses `/` separators so manifests stay comparable across platforms.
+fn manifest_key(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the tracked files, relative to `data_dir`.
+pub async fn tracked_files(data_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut pending: Vec<PathBuf> = TRACKED.iter().map(PathBuf::from).collect();
+
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = tokio::fs::metadata(data_dir.join(path.as_path())).await else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.push(path);
+            continue;
+        }
+
+        let mut dir = tokio::fs::read_dir(data_dir.join(path.as_path())).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            pending.push(path.join(entry.file_name()));
+        }
+    }
+
+    Ok(files)
+}
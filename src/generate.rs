@@ -0,0 +1,179 @@
+//! Synthetic todos for benchmarks, demos and reproducing bugs at scale
+//! without sharing anyone's actual tasks.
+//!
+//! The same seed always gives the same todos. Dates are spread around the
+//! day they are generated on, so a collection has overdue todos, todos due
+//! soon and todos closed a while ago.
+
+use crate::{Cancellation, DataId, FrontMatter, FrontMatterStyle, Priority, Status, TodoData};
+use chrono::{DateTime, Days, NaiveDate, TimeDelta, Utc};
+
+const VERBS: [&str; 12] = [
+    "Review", "Fix", "Write", "Update", "Plan", "Call", "Order", "Clean up", "Refactor", "Book",
+    "Prepare", "Migrate",
+];
+const OBJECTS: [&str; 16] = [
+    "the quarterly report",
+    "login page",
+    "dentist appointment",
+    "release notes",
+    "garden shed",
+    "database backups",
+    "team offsite",
+    "tax documents",
+    "onboarding guide",
+    "flaky test suite",
+    "birthday present",
+    "invoice template",
+    "CI pipeline",
+    "travel insurance",
+    "API documentation",
+    "bike repair",
+];
+const TAGS: [&str; 10] = [
+    "work", "home", "urgent", "errand", "health", "finance", "backend", "docs", "travel", "later",
+];
+const SENTENCES: [&str; 10] = [
+    "Check with the others before starting.",
+    "The last attempt stalled halfway through.",
+    "Needs about an hour of focused time.",
+    "Keep the old version around until this is confirmed to work.",
+    "Ask for feedback once a first draft exists.",
+    "There is a reminder about this in the shared calendar.",
+    "Collect the open questions first.",
+    "Nothing urgent, but it keeps coming up.",
+    "Link the result in the weekly notes.",
+    "Compare prices before ordering anything.",
+];
+const STEPS: [&str; 6] = [
+    "gather requirements",
+    "draft a plan",
+    "get approval",
+    "do the work",
+    "double-check the result",
+    "tell everyone involved",
+];
+const REASONS: [&str; 3] = [
+    "no longer needed",
+    "someone else took care of it",
+    "out of scope for now",
+];
+
+/// A small seeded generator (SplitMix64), good enough for test data and
+/// stable across platforms and releases.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..bound`.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// A todo created up to a year before `today`, all times in UTC.
+pub fn todo(rng: &mut Rng, id: DataId, today: NaiveDate) -> TodoData {
+    let now = today.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let created_at = now - TimeDelta::seconds(rng.below(365 * 24 * 60 * 60) as i64 + 1);
+    let title = format!("{} {}", rng.pick(&VERBS), rng.pick(&OBJECTS));
+
+    let mut tags = vec![];
+    for _ in 0..rng.below(4) {
+        let tag = rng.pick(&TAGS).to_string();
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let due_at = rng
+        .chance(40)
+        .then(|| end_of_utc_day(created_at, rng.below(90)));
+    let priority = match rng.below(10) {
+        0 => Some(Priority::High),
+        1 | 2 => Some(Priority::Medium),
+        3 => Some(Priority::Low),
+        _ => None,
+    };
+
+    let closed_at = created_at + TimeDelta::seconds(rng.below(30 * 24 * 60 * 60) as i64);
+    let (status, completed_at, cancellation) = match rng.below(20) {
+        _ if closed_at >= now => (Status::Open, None, None),
+        0..=4 => (Status::Done, Some(closed_at), None),
+        5 => {
+            let cancellation = Cancellation {
+                at: closed_at,
+                reason: rng.pick(&REASONS).to_string(),
+                superseded_by: None,
+            };
+            (Status::Cancelled, None, Some(cancellation))
+        }
+        _ => (Status::Open, None, None),
+    };
+
+    let mut content = format!("\n# {title}\n\n");
+    let first = rng.below(SENTENCES.len() as u64) as usize;
+    let sentences: Vec<&str> = SENTENCES
+        .iter()
+        .cycle()
+        .skip(first)
+        .take(rng.below(3) as usize + 1)
+        .copied()
+        .collect();
+    content.push_str(&sentences.join(" "));
+    content.push('\n');
+    if rng.chance(30) {
+        content.push_str("\n## Steps\n\n");
+        let done = status == Status::Done;
+        for step in &STEPS[..rng.below(STEPS.len() as u64 - 1) as usize + 2] {
+            let mark = if done || rng.chance(30) { 'x' } else { ' ' };
+            content.push_str(&format!("- [{mark}] {step}\n"));
+        }
+    }
+
+    TodoData {
+        front_matter: FrontMatter {
+            id,
+            created_at,
+            due_at,
+            tags,
+            priority,
+            recurrence: None,
+            parent: None,
+            depends_on: vec![],
+            blocked_by: vec![],
+            summary: None,
+            status,
+            completed_at,
+            cancellation,
+            time_entries: vec![],
+            extra: toml::Table::new(),
+        },
+        content,
+        style: FrontMatterStyle::default(),
+    }
+}
+
+/// The last second of the UTC day `days` after `at`.
+fn end_of_utc_day(at: DateTime<Utc>, days: u64) -> DateTime<Utc> {
+    let day = at.date_naive() + Days::new(days);
+    day.and_hms_opt(23, 59, 59).unwrap().and_utc()
+}
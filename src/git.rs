@@ -0,0 +1,183 @@
+//! Reading collections out of git history by shelling out to `git`.
+
+use crate::{DataId, TodoData};
+use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Runs `git` inside `dir` and returns its stdout.
+pub async fn run(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .map_err(|err| anyhow!("cannot run git: {err}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Loads the todos of `tasks/` as they are at `rev`. Files that don't
+/// parse are skipped, like `load_collection` does.
+pub async fn load_at(data_dir: &Path, rev: &str) -> anyhow::Result<BTreeMap<DataId, TodoData>> {
+    let listing = run(data_dir, &["ls-tree", "-r", "--name-only", rev, "--", "tasks"]).await?;
+
+    let mut todos = BTreeMap::new();
+    for path in listing.lines().filter(|path| path.ends_with(".md")) {
+        let content = run(data_dir, &["show", format!("{rev}:./{path}").as_str()]).await?;
+        if let Ok(data) = TodoData::from_str(content.as_str()) {
+            todos.insert(data.front_matter.id, data);
+        }
+    }
+    Ok(todos)
+}
+
+/// Loads a single todo file, relative to `data_dir`, as it is at `rev`.
+pub async fn load_file_at(data_dir: &Path, rev: &str, path: &Path) -> anyhow::Result<TodoData> {
+    let path = path.to_str().ok_or_else(|| anyhow!("path is not valid UTF-8"))?;
+    let content = run(data_dir, &["show", format!("{rev}:./{path}").as_str()]).await?;
+    TodoData::from_str(content.as_str())
+}
+
+#[derive(Debug)]
+pub struct Commit {
+    pub hash: String,
+    /// Author date.
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub subject: String,
+}
+
+/// Commits that touched `path`, relative to `data_dir`, oldest first.
+pub async fn file_log(data_dir: &Path, path: &Path) -> anyhow::Result<Vec<Commit>> {
+    let path = path.to_str().ok_or_else(|| anyhow!("path is not valid UTF-8"))?;
+    let log = run(data_dir, &["log", "--follow", "--format=%H%x09%aI%x09%s", "--", path]).await?;
+    log.lines()
+        .rev()
+        .map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let hash = fields.next().unwrap_or_default().to_string();
+            let at = chrono::DateTime::parse_from_rfc3339(fields.next().unwrap_or_default())?
+                .with_timezone(&chrono::Utc);
+            let subject = fields.next().unwrap_or_default().to_string();
+            Ok(Commit { hash, at, subject })
+        })
+        .collect()
+}
+
+/// The author date of the latest commit touching each file below
+/// `tasks/`, keyed by path relative to `data_dir`.
+pub async fn last_changed(
+    data_dir: &Path,
+) -> anyhow::Result<BTreeMap<String, chrono::DateTime<chrono::Utc>>> {
+    let args = ["log", "--format=%x00%aI", "--name-only", "--relative", "--", "tasks"];
+    let log = run(data_dir, &args).await?;
+    let mut changed = BTreeMap::new();
+    // newest first, so the first date seen for a path is its latest
+    for commit in log.split('\0').filter(|commit| !commit.is_empty()) {
+        let mut lines = commit.lines();
+        let at = chrono::DateTime::parse_from_rfc3339(lines.next().unwrap_or_default())?
+            .with_timezone(&chrono::Utc);
+        for path in lines.filter(|path| !path.is_empty()) {
+            changed.entry(path.to_string()).or_insert(at);
+        }
+    }
+    Ok(changed)
+}
+
+/// A task-level change between two versions of a collection.
+#[derive(Debug)]
+pub enum Change<'a> {
+    Created(&'a TodoData),
+    Removed(&'a TodoData),
+    Rescheduled(&'a TodoData, &'a TodoData),
+    Edited(&'a TodoData, &'a TodoData),
+}
+
+impl Change<'_> {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::Created(_) => "created",
+            Change::Removed(_) => "removed",
+            Change::Rescheduled(..) => "rescheduled",
+            Change::Edited(..) => "edited",
+        }
+    }
+
+    /// The todo as it is after the change, or before it when removed.
+    pub fn todo(&self) -> &TodoData {
+        match self {
+            Change::Created(todo) | Change::Removed(todo) => todo,
+            Change::Rescheduled(_, todo) | Change::Edited(_, todo) => todo,
+        }
+    }
+}
+
+/// Compares two collections by id. A todo whose due date moved is
+/// reported as rescheduled, even when other fields changed too. Changes
+/// to only the front matter fields in `ignored` are not reported.
+pub fn diff<'a>(
+    from: &'a BTreeMap<DataId, TodoData>,
+    to: &'a BTreeMap<DataId, TodoData>,
+    ignored: &[String],
+) -> Vec<Change<'a>> {
+    let mut changes = vec![];
+    let due_ignored = ignored.iter().any(|field| field == "due_at");
+    for (id, old) in from {
+        match to.get(id) {
+            None => changes.push(Change::Removed(old)),
+            Some(new) if !due_ignored && old.front_matter.due_at != new.front_matter.due_at => {
+                changes.push(Change::Rescheduled(old, new))
+            }
+            Some(new) if differs(old, new, ignored) => changes.push(Change::Edited(old, new)),
+            Some(_) => {}
+        }
+    }
+    for (id, new) in to {
+        if !from.contains_key(id) {
+            changes.push(Change::Created(new));
+        }
+    }
+    changes.sort_by_key(|change| change.todo().front_matter.id);
+    changes
+}
+
+/// Whether two versions of a todo differ in anything but the front matter
+/// fields in `ignored`.
+pub fn differs(old: &TodoData, new: &TodoData, ignored: &[String]) -> bool {
+    if old.content != new.content {
+        return true;
+    }
+    let fields = |data: &TodoData| {
+        let mut fields = toml::Table::try_from(&data.front_matter).unwrap_or_default();
+        fields.retain(|field, _| !ignored.iter().any(|ignored| ignored == field));
+        fields
+    };
+    fields(old) != fields(new)
+}
+
+/// Names the parts of a todo that differ between two versions.
+pub fn edited_parts(old: &TodoData, new: &TodoData) -> Vec<&'static str> {
+    let mut parts = vec![];
+    if old.title() != new.title() {
+        parts.push("title");
+    }
+    if old.front_matter.tags != new.front_matter.tags {
+        parts.push("tags");
+    }
+    if old.front_matter.status != new.front_matter.status {
+        parts.push("status");
+    }
+    if old.body() != new.body() {
+        parts.push("body");
+    }
+    parts
+}
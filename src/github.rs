@@ -0,0 +1,188 @@
+//! Publishing todos as GitHub issues through the `gh` CLI, which takes
+//! care of authentication.
+//!
+//! Which todo became which issue is recorded in `github.toml` right after
+//! each issue is created. A push that stopped halfway, on a rate limit or
+//! a network error, picks up where it left off when run again, and todos
+//! already published are never published twice.
+
+use crate::{DataId, TodoData};
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+pub const ISSUES_FILE: &str = "github.toml";
+
+/// Requests left in the rate limit below which a push waits for the reset.
+const RESERVE: u64 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Issues {
+    #[serde(default)]
+    repos: Vec<Repo>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Repo {
+    /// `owner/name`
+    name: String,
+    #[serde(default)]
+    issues: Vec<Issue>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Issue {
+    pub todo: DataId,
+    pub number: u64,
+    pub url: String,
+}
+
+impl Issues {
+    async fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(ISSUES_FILE);
+        if !tokio::fs::try_exists(path.as_path()).await? {
+            return Ok(Self::default());
+        }
+
+        let content = tokio::fs::read_to_string(path.as_path()).await?;
+        toml::from_str(content.as_str()).map_err(|err| anyhow!("invalid {ISSUES_FILE}: {err}"))
+    }
+
+    async fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(data_dir.join(ISSUES_FILE), toml::to_string(self)?).await?;
+        Ok(())
+    }
+
+    fn repo(&mut self, name: &str) -> &mut Repo {
+        match self.repos.iter().position(|repo| repo.name == name) {
+            Some(index) => &mut self.repos[index],
+            None => {
+                self.repos.push(Repo {
+                    name: name.to_string(),
+                    issues: vec![],
+                });
+                self.repos.last_mut().unwrap()
+            }
+        }
+    }
+}
+
+/// How many issues to create between rate limit checks, and how long to
+/// pause between two of them.
+#[derive(Debug, Clone, Copy)]
+pub struct Pacing {
+    pub batch: usize,
+    pub interval: Duration,
+}
+
+#[derive(Debug)]
+pub struct Pushed {
+    pub issue: Issue,
+    /// Whether the issue was created now rather than by an earlier push.
+    pub created: bool,
+}
+
+/// Makes sure every todo in `todos` has an issue in `repo`, reporting each
+/// to `pushed` as soon as it is recorded.
+pub async fn push(
+    data_dir: &Path,
+    todos: &[&TodoData],
+    repo: &str,
+    pacing: Pacing,
+    mut pushed: impl FnMut(&Pushed),
+) -> anyhow::Result<()> {
+    let mut issues = Issues::load(data_dir).await?;
+    let mut pending = vec![];
+    for todo in todos {
+        let id = todo.front_matter.id;
+        match issues.repo(repo).issues.iter().find(|issue| issue.todo == id) {
+            Some(issue) => pushed(&Pushed {
+                issue: issue.clone(),
+                created: false,
+            }),
+            None => pending.push(*todo),
+        }
+    }
+
+    for (index, todo) in pending.iter().enumerate() {
+        if index % pacing.batch.max(1) == 0 {
+            wait_for_rate_limit(pacing.batch as u64).await?;
+        } else {
+            tokio::time::sleep(pacing.interval).await;
+        }
+
+        let issue = create_issue(repo, todo).await?;
+        issues.repo(repo).issues.push(issue.clone());
+        issues.write(data_dir).await?;
+        pushed(&Pushed {
+            issue,
+            created: true,
+        });
+    }
+    Ok(())
+}
+
+async fn create_issue(repo: &str, todo: &TodoData) -> anyhow::Result<Issue> {
+    let id = todo.front_matter.id;
+    let title = todo.title().unwrap_or("untitled");
+    let mut args = vec![
+        "api".to_string(),
+        "--method".to_string(),
+        "POST".to_string(),
+        format!("repos/{repo}/issues"),
+        "-f".to_string(),
+        format!("title={title}"),
+        "-f".to_string(),
+        format!("body={}", todo.body().trim_end()),
+    ];
+    for tag in &todo.front_matter.tags {
+        args.extend(["-f".to_string(), format!("labels[]={tag}")]);
+    }
+
+    let response: serde_json::Value = serde_json::from_str(gh(&args).await?.as_str())?;
+    let number = response["number"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("unexpected response creating an issue for todo {id}"))?;
+    Ok(Issue {
+        todo: id,
+        number,
+        url: response["html_url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Sleeps until the rate limit resets when fewer than `needed` requests,
+/// plus a reserve, are left.
+async fn wait_for_rate_limit(needed: u64) -> anyhow::Result<()> {
+    let args = ["api".to_string(), "rate_limit".to_string()];
+    let response: serde_json::Value = serde_json::from_str(gh(&args).await?.as_str())?;
+    let core = &response["resources"]["core"];
+    let (Some(remaining), Some(reset)) = (core["remaining"].as_u64(), core["reset"].as_i64()) else {
+        return Ok(());
+    };
+    if remaining >= needed + RESERVE {
+        return Ok(());
+    }
+
+    let wait = (reset - chrono::Utc::now().timestamp()).max(0) as u64 + 1;
+    eprintln!("note: {remaining} requests left in the rate limit, waiting {wait}s for the reset");
+    tokio::time::sleep(Duration::from_secs(wait)).await;
+    Ok(())
+}
+
+async fn gh(args: &[String]) -> anyhow::Result<String> {
+    let output = tokio::process::Command::new("gh")
+        .args(args)
+        .output()
+        .await
+        .map_err(|err| anyhow!("cannot run gh, the GitHub CLI: {err}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "gh {} failed: {}",
+            args[..args.len().min(4)].join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
@@ -0,0 +1,120 @@
+//! Suggestions for tidying up a backlog, each to be accepted or skipped.
+
+use crate::{Collection, DataId, Priority};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug)]
+pub enum Action {
+    /// Cancel a todo nothing happened to since `idle_since`.
+    Close {
+        id: DataId,
+        idle_since: DateTime<Utc>,
+    },
+    /// Cancel `duplicate` as superseded by the older `original`.
+    Merge { duplicate: DataId, original: DataId },
+    /// Lower the priority of a todo that has been overdue for long.
+    Demote { id: DataId, to: Priority },
+}
+
+impl Action {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Close { .. } => "close",
+            Action::Merge { .. } => "merge",
+            Action::Demote { .. } => "demote",
+        }
+    }
+
+    /// The todo the action changes.
+    pub fn id(&self) -> DataId {
+        match self {
+            Action::Close { id, .. } | Action::Demote { id, .. } => *id,
+            Action::Merge { duplicate, .. } => *duplicate,
+        }
+    }
+}
+
+/// Suggests actions on open todos: closing those without activity since
+/// `cutoff`, merging those with the same title into the oldest one, and
+/// demoting prioritized ones due before `cutoff`. `last_changed` holds the
+/// latest commit touching each todo, where known.
+pub fn suggest(
+    collection: &Collection,
+    last_changed: &BTreeMap<DataId, DateTime<Utc>>,
+    cutoff: DateTime<Utc>,
+) -> Vec<Action> {
+    let open: Vec<_> = collection
+        .values()
+        .map(|todo| &todo.data)
+        .filter(|todo| todo.front_matter.status.is_open())
+        .collect();
+    let mut actions = vec![];
+
+    let mut originals: HashMap<String, DataId> = HashMap::new();
+    for todo in &open {
+        let front_matter = &todo.front_matter;
+        let Some(title) = todo.title().map(normalize) else {
+            continue;
+        };
+        match originals.get(&title) {
+            Some(&original) => actions.push(Action::Merge {
+                duplicate: front_matter.id,
+                original,
+            }),
+            None => {
+                originals.insert(title, front_matter.id);
+            }
+        }
+    }
+
+    for todo in &open {
+        let front_matter = &todo.front_matter;
+        let merging = |action: &Action| match action {
+            Action::Merge {
+                duplicate,
+                original,
+            } => [*duplicate, *original].contains(&front_matter.id),
+            _ => false,
+        };
+        if actions.iter().any(merging) {
+            continue;
+        }
+
+        let idle_since = front_matter
+            .time_entries
+            .iter()
+            .map(|entry| entry.end.unwrap_or(Utc::now()))
+            .chain(last_changed.get(&front_matter.id).copied())
+            .fold(front_matter.created_at, DateTime::max);
+        if idle_since < cutoff {
+            actions.push(Action::Close {
+                id: front_matter.id,
+                idle_since,
+            });
+            continue;
+        }
+
+        let lower = front_matter.priority.and_then(|priority| priority.lower());
+        if let Some(to) = lower.filter(|_| front_matter.due_at.is_some_and(|due| due < cutoff)) {
+            actions.push(Action::Demote {
+                id: front_matter.id,
+                to,
+            });
+        }
+    }
+
+    actions.sort_by_key(Action::id);
+    actions
+}
+
+/// Lower-cased words without punctuation, so `Fix login!` and `fix  login`
+/// count as the same title.
+fn normalize(title: &str) -> String {
+    title
+        .split(|ch: char| !ch.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
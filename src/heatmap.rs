@@ -0,0 +1,90 @@
+//! A year of activity as a grid of days, one column per week.
+
+use crate::Collection;
+use chrono::{Datelike, Days, Local, NaiveDate};
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+
+/// Shades from a few to the most todos on a single day.
+const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+const EMPTY: char = '·';
+const WEEKDAY_LABELS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// todos created per day
+    Created,
+    /// todos completed per day
+    Completed,
+}
+
+impl Metric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Created => "created",
+            Metric::Completed => "completed",
+        }
+    }
+}
+
+/// Todos per local day of `year`, leaving out days without any.
+pub fn counts(collection: &Collection, metric: Metric, year: i32) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for todo in collection.values() {
+        let front_matter = &todo.data.front_matter;
+        let at = match metric {
+            Metric::Created => Some(front_matter.created_at),
+            Metric::Completed => front_matter.completed_at,
+        };
+        let Some(day) = at.map(|at| at.with_timezone(&Local).date_naive()) else {
+            continue;
+        };
+        if day.year() == year {
+            *counts.entry(day).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Renders weekdays as rows and weeks as columns, starting with the week
+/// of January 1st, under a row of month names.
+pub fn render(year: i32, counts: &BTreeMap<NaiveDate, usize>) -> String {
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+    let last = NaiveDate::from_ymd_opt(year, 12, 31).unwrap();
+    let start = first - Days::new(first.weekday().num_days_from_monday().into());
+    let weeks = (last - start).num_days() as usize / 7 + 1;
+    let max = counts.values().copied().max().unwrap_or(0);
+
+    let mut months = vec![' '; weeks];
+    for month in 1..=12 {
+        let day = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        let week = (day - start).num_days() as usize / 7;
+        let name = day.format("%b").to_string();
+        let end = week + name.len();
+        if end <= weeks && months[week..end].iter().all(|ch| *ch == ' ') {
+            months.splice(week..end, name.chars());
+        }
+    }
+
+    let mut out = format!("    {}\n", months.iter().collect::<String>().trim_end());
+    for (weekday, label) in WEEKDAY_LABELS.iter().enumerate() {
+        let cells: String = (0..weeks)
+            .map(|week| {
+                let day = start + Days::new((week * 7 + weekday) as u64);
+                if day < first || day > last {
+                    return ' ';
+                }
+                match counts.get(&day) {
+                    None => EMPTY,
+                    Some(&count) => SHADES[(count * SHADES.len()).div_ceil(max) - 1],
+                }
+            })
+            .collect();
+        out.push_str(format!("{label:<4}{cells}").trim_end());
+        out.push('\n');
+    }
+
+    let legend: Vec<String> = std::iter::once(EMPTY).chain(SHADES).map(String::from).collect();
+    out.push_str(&format!("\n    less {} more\n", legend.join(" ")));
+    out
+}
@@ -0,0 +1,97 @@
+//! Single-event iCalendar files (RFC 5545) for a todo's due date.
+
+use crate::config::IdFormat;
+use crate::markup::{self, Markup};
+use crate::TodoFile;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use std::fmt::Write;
+
+/// Longest content line allowed before folding, in octets.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Renders one VEVENT at the todo's due time, with a reminder an hour
+/// before. Fails for todos without a due date.
+pub fn event(todo: &TodoFile, ids: &IdFormat, now: DateTime<Utc>) -> anyhow::Result<String> {
+    let front_matter = &todo.data.front_matter;
+    let display = ids.display(front_matter.id);
+    let due_at = front_matter
+        .due_at
+        .ok_or_else(|| anyhow!("todo {display} has no due date"))?;
+    let summary = format!("{display} {}", todo.data.title().unwrap_or("-"));
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//todo//{}//EN", env!("CARGO_PKG_VERSION")),
+        "BEGIN:VEVENT".to_string(),
+        format!(
+            "UID:todo-{}-{}@todo",
+            front_matter.id,
+            front_matter.created_at.timestamp()
+        ),
+        format!("DTSTAMP:{}", timestamp(now)),
+        format!("DTSTART:{}", timestamp(due_at)),
+        format!("DTEND:{}", timestamp(due_at)),
+        format!("SUMMARY:{}", escape_text(summary.as_str())),
+    ];
+    let description = markup::render(todo.data.body(), Markup::Plain);
+    if !description.trim().is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description.trim())));
+    }
+    if !front_matter.tags.is_empty() {
+        let tags: Vec<_> = front_matter.tags.iter().map(|tag| escape_text(tag)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.extend([
+        "BEGIN:VALARM".to_string(),
+        "ACTION:DISPLAY".to_string(),
+        "TRIGGER:-PT1H".to_string(),
+        format!("DESCRIPTION:{}", escape_text(summary.as_str())),
+        "END:VALARM".to_string(),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ]);
+
+    let mut ics = String::new();
+    for line in lines {
+        write!(ics, "{}\r\n", fold(line.as_str())).unwrap();
+    }
+    Ok(ics)
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Splits a content line into continuation lines of at most
+/// `MAX_LINE_OCTETS`, never inside a UTF-8 sequence.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            // the leading space counts towards the continuation line
+            width = 1;
+        }
+        folded.push(ch);
+        width += ch.len_utf8();
+    }
+    folded
+}
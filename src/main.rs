@@ -1,19 +1,62 @@
+mod archive;
+mod capture;
+mod checklist;
+mod commands;
+mod config;
+mod cooccurrence;
+mod dates;
+mod fields;
+mod fixtures;
+mod fsck;
+mod generate;
+mod git;
+mod github;
+mod groom;
+mod heatmap;
+mod ics;
+mod markup;
+mod merge;
+mod pattern;
+mod porcelain;
+mod print;
+mod recurrence;
+mod replicate;
+mod reservations;
+mod sandbox;
+mod score;
+mod selection;
+mod shorthand;
+mod sync;
+mod table;
+mod timesheet;
+mod trello;
+mod validate;
+mod yaml;
+
 use anyhow::anyhow;
-use chrono::Utc;
-use clap::{Parser, Subcommand};
-use handlebars::{DirectorySourceOptions, Handlebars};
+use chrono::{Local, Utc};
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
+use config::{Config, IdFormat};
+use handlebars::template::{HelperTemplate, Parameter, TemplateElement};
+use handlebars::{DirectorySourceOptions, Handlebars, Template};
+use markup::Markup;
+use reservations::Reservations;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env::current_dir;
 use std::fmt::Display;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use tokio::runtime::Handle;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    let cli = parse_cli();
+    let output = cli.output_mode();
 
     let current_dir = cli.data_dir.unwrap_or(current_dir().unwrap());
 
@@ -23,299 +66,3735 @@ async fn main() {
 
     match cli.command {
         None => {}
-        Some(Commands::New {
+        Some(Commands::New(args)) => commands::new(current_dir, output, args).await,
+
+        Some(Commands::Clone { id, title, keep }) => {
+            commands::clone(current_dir, output, id, title, keep).await
+        }
+
+        Some(Commands::Init) => commands::init(current_dir, output).await,
+
+        Some(Commands::Copy { id, markup }) => {
+            commands::copy(current_dir, output, id, markup).await
+        }
+
+        Some(Commands::List(args)) => commands::list(current_dir, output, args).await,
+
+        Some(Commands::Subtasks { id }) => commands::subtasks(current_dir, output, id).await,
+
+        Some(Commands::Show {
+            id,
+            include_archived,
+            activity,
+        }) => commands::show(current_dir, output, id, include_archived, activity).await,
+
+        Some(Commands::Get { id, path }) => commands::get(current_dir, id, path).await,
+
+        Some(Commands::SetJson { id, patch }) => {
+            commands::set_json(current_dir, output, id, patch).await
+        }
+
+        Some(Commands::ReapplyTemplate {
             template,
-            title,
             tags,
+            apply,
+        }) => commands::reapply_template(current_dir, output, template, tags, apply).await,
+
+        Some(Commands::Search {
+            query,
+            regex,
+            ignore_case,
+            include_archived,
         }) => {
-            let proc = CommandProcessor::new(
-                init_hbs().unwrap(),
-                load_collection().await.unwrap(),
-                current_dir,
-            );
-            let mut template_vars = TemplateVars::new(proc.next_data_id());
-            template_vars.title = title;
-            template_vars.tags = tags;
-            let todo_file_result = proc.new_todo_from_template(
-                template.unwrap_or("task".to_string()).as_str(),
-                template_vars,
-            );
+            commands::search(current_dir, output, query, regex, ignore_case, include_archived).await
+        }
 
-            let todo_file = match todo_file_result {
-                Ok(todo_file) => todo_file,
-                Err(err) => {
-                    failure(err);
-                }
-            };
+        Some(Commands::Reserve { count }) => commands::reserve(current_dir, output, count).await,
+
+        Some(Commands::Suggest { prefix, kind }) => {
+            commands::suggest(current_dir, output, prefix, kind).await
+        }
+
+        Some(Commands::Ics { id, output: target }) => commands::ics(current_dir, id, target).await,
+
+        Some(Commands::Fsck {
+            write_manifest,
+            verify: _,
+        }) => commands::fsck(current_dir, output, write_manifest).await,
+
+        Some(Commands::Replicate { query, to }) => {
+            commands::replicate(current_dir, output, query, to).await
+        }
+
+        Some(Commands::Github {
+            command:
+                GithubCommands::Push {
+                    query,
+                    repo,
+                    batch,
+                    interval,
+                },
+        }) => commands::github_push(current_dir, output, query, repo, batch, interval).await,
+
+        Some(Commands::Heatmap { year, metric }) => {
+            commands::heatmap(current_dir, output, year, metric).await
+        }
+
+        Some(Commands::Validate { staged }) => {
+            commands::validate(current_dir, output, staged).await
+        }
+
+        Some(Commands::Migrate { front_matter }) => {
+            commands::migrate(current_dir, output, front_matter).await
+        }
+
+        Some(Commands::GitMergeDriver { base, ours, theirs }) => {
+            commands::git_merge_driver(base, ours, theirs).await
+        }
+
+        Some(Commands::DiffBranches { from, to }) => {
+            commands::diff_branches(current_dir, output, from, to).await
+        }
+
+        Some(Commands::Sandbox { command }) => {
+            commands::sandbox(current_dir, output, command).await
+        }
+
+        Some(Commands::Edit { id, editor }) => {
+            commands::edit(current_dir, output, id, editor).await
+        }
+
+        Some(Commands::Delete { selection, purge }) => {
+            commands::delete(current_dir, output, selection, purge).await
+        }
+
+        Some(Commands::Trash {
+            command: TrashCommands::Empty,
+        }) => commands::trash_empty(current_dir, output).await,
+
+        Some(Commands::Tag {
+            command: Some(TagCommands::Graph { limit }),
+            ..
+        }) => commands::tag_graph(current_dir, output, limit).await,
+
+        Some(Commands::Tag {
+            command: Some(TagCommands::Rename { from, to }),
+            ..
+        }) => commands::retag(current_dir, output, vec![from], to, true).await,
+
+        Some(Commands::Tag {
+            command: Some(TagCommands::Merge { tags, into }),
+            ..
+        }) => commands::retag(current_dir, output, tags, into, false).await,
+
+        Some(Commands::Tag {
+            command: None,
+            selection,
+            add,
+            remove,
+        }) => commands::tag(current_dir, output, selection, add, remove).await,
+
+        Some(Commands::Tags) => commands::tags(current_dir, output).await,
+
+        Some(command @ (Commands::Today | Commands::Overdue)) => {
+            commands::today_or_overdue(current_dir, output, command).await
+        }
+
+        Some(Commands::Agenda { days, week }) => {
+            commands::agenda(current_dir, output, days, week).await
+        }
+
+        Some(Commands::Serve { socket }) => commands::serve(current_dir, output, socket).await,
+
+        Some(Commands::Due { id, date, clear: _ }) => {
+            commands::due(current_dir, output, id, date).await
+        }
+
+        Some(Commands::Priority {
+            id,
+            priority,
+            clear: _,
+        }) => commands::priority(current_dir, output, id, priority).await,
+
+        Some(Commands::Recur { id, rule, clear: _ }) => {
+            commands::recur(current_dir, output, id, rule).await
+        }
+
+        Some(Commands::Link {
+            id,
+            depends_on,
+            remove,
+        }) => commands::link(current_dir, output, id, depends_on, remove).await,
+
+        Some(Commands::Next { count }) => commands::next(current_dir, output, count).await,
+
+        Some(Commands::Groom {
+            older_than,
+            dry_run,
+            yes,
+        }) => commands::groom(current_dir, output, older_than, dry_run, yes).await,
+
+        Some(Commands::Snooze { id, duration }) => {
+            commands::snooze(current_dir, output, id, duration).await
+        }
+
+        Some(Commands::Archive {
+            id,
+            done,
+            cancelled,
+            older_than,
+        }) => commands::archive(current_dir, output, id, done, cancelled, older_than).await,
+
+        Some(Commands::Start { id }) => commands::start(current_dir, output, id).await,
+
+        Some(Commands::Stop) => commands::stop(current_dir, output).await,
+
+        Some(Commands::Time {
+            command: TimeCommands::Report { week },
+        }) => commands::time_report(current_dir, output, week).await,
+
+        Some(Commands::Reopen { id }) => commands::reopen(current_dir, output, id).await,
+
+        Some(Commands::Status { id, status }) => {
+            commands::status(current_dir, output, id, status).await
+        }
+
+        Some(Commands::Block { id, on }) => commands::block(current_dir, output, id, on).await,
+
+        Some(Commands::Unblock { id, index, reason }) => {
+            commands::unblock(current_dir, output, id, index, reason).await
+        }
+
+        Some(Commands::Blocked) => commands::blocked(current_dir, output).await,
+
+        Some(Commands::Done { selection }) => commands::done(current_dir, output, selection).await,
+
+        Some(Commands::Cancel {
+            selection,
+            reason,
+            superseded_by,
+        }) => commands::cancel(current_dir, output, selection, reason, superseded_by).await,
+
+        Some(Commands::Checklist {
+            command: ChecklistCommands::Add { id, from },
+        }) => commands::checklist_add(current_dir, output, id, from).await,
+
+        Some(Commands::Template {
+            command: TemplateCommands::Test { update },
+        }) => commands::template_test(current_dir, output, update).await,
+
+        Some(Commands::SyncTemplates) => commands::sync_templates(current_dir, output).await,
+
+        Some(Commands::Print {
+            layout,
+            tags,
+            output: target,
+        }) => commands::print(current_dir, layout, tags, target).await,
+
+        Some(Commands::Generate { count, seed, into }) => {
+            commands::generate(output, count, seed, into).await
+        }
+
+        Some(Commands::Import {
+            from: ExchangeFormat::Trello,
+            path,
+        }) => commands::import_trello(current_dir, output, path).await,
+
+        Some(Commands::Export {
+            format: ExchangeFormat::Trello,
+            output: target,
+        }) => commands::export_trello(current_dir, target).await,
+    }
+}
+
+fn failure(err: impl Display) -> ! {
+    eprintln!("Error: {err}");
+    std::process::exit(1);
+}
+
+/// Exits with 1 once a bulk command has tried every todo, if any failed.
+fn exit_if_failed(failed: usize) {
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// The todos a bulk command works on, failing on invalid ids or filters
+/// and telling humans when nothing matched.
+fn select_todos(proc: &CommandProcessor, selection: &Selection, output: OutputMode) -> Vec<DataId> {
+    if selection.ids.is_empty() && selection.filter.is_none() {
+        failure("no todos given, pass ids like 3 7 9-14 or --filter");
+    }
+    let ids = selection::parse_ids(&selection.ids, &proc.config.ids)
+        .unwrap_or_else(|err| failure(err));
+    let filter = selection
+        .filter
+        .as_deref()
+        .map(replicate::Query::parse)
+        .transpose()
+        .unwrap_or_else(|err| failure(err));
+
+    let ids = selection::select(&proc.collection, ids, filter.as_ref());
+    if ids.is_empty() && output == OutputMode::Human {
+        println!("no matching todos");
+    }
+    ids
+}
+
+/// Lists what a bulk command would `action` with `--dry-run`.
+fn print_preview(proc: &CommandProcessor, action: &str, ids: &[DataId], output: OutputMode) {
+    let mut failed = 0;
+    for &id in ids {
+        let todo = match proc.get(id) {
+            Ok(todo) => todo,
+            Err(err) => {
+                eprintln!("Error: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        match output {
+            OutputMode::Human => println!(
+                "would {action} todo {} {}",
+                proc.config.ids.display(id),
+                todo.data.title().unwrap_or("-")
+            ),
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::Record::Would { action, id })
+            }
+        }
+    }
+    exit_if_failed(failed);
+}
+
+/// Parses the command line, adding edit-distance suggestions to unknown
+/// subcommand and flag errors where clap's own matching finds none.
+fn parse_cli() -> Cli {
+    let mut err = match Cli::try_parse() {
+        Ok(cli) => return cli,
+        Err(err) => err,
+    };
+
+    let command = Cli::command();
+    let invalid = |kind| match err.get(kind) {
+        Some(ContextValue::String(value)) => Some(value.clone()),
+        _ => None,
+    };
+    let suggestion = match err.kind() {
+        // clap's own guess ranks `next` above `new` for `nwe`, edit distance
+        // with transpositions catches swapped letters better
+        ErrorKind::InvalidSubcommand => {
+            invalid(ContextKind::InvalidSubcommand).and_then(|name| {
+                let known = command.get_subcommands().map(|sub| sub.get_name());
+                closest_match(name.as_str(), known)
+                    .map(|sub| (ContextKind::SuggestedSubcommand, sub.to_string()))
+            })
+        }
+        ErrorKind::UnknownArgument if err.get(ContextKind::SuggestedArg).is_none() => {
+            invalid(ContextKind::InvalidArg).and_then(|flag| {
+                let mut known: Vec<&str> = command
+                    .get_arguments()
+                    .chain(command.get_subcommands().flat_map(|sub| sub.get_arguments()))
+                    .filter_map(|arg| arg.get_long())
+                    .collect();
+                known.sort_unstable();
+                known.dedup();
+                let name = flag.trim_start_matches('-').split('=').next().unwrap_or_default();
+                closest_match(name, known.into_iter())
+                    .map(|long| (ContextKind::SuggestedArg, format!("--{long}")))
+            })
+        }
+        _ => None,
+    };
+
+    if let Some((kind, value)) = suggestion {
+        err.insert(kind, ContextValue::String(value));
+    }
+    err.exit()
+}
+
+/// How command results are written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    /// Human-facing formatting, free to change between releases.
+    Human,
+    /// Only the ids of affected todos, one per line.
+    Quiet,
+    /// Stable, versioned, tab-separated records for scripts.
+    Porcelain,
+}
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+#[command(arg_required_else_help = true)]
+struct Cli {
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+
+    /// only print ids of affected todos
+    #[arg(long, short, global = true, conflicts_with = "porcelain")]
+    quiet: bool,
 
-            if let Err(err) = todo_file.write_file().await {
-                failure(err);
+    /// stable, versioned, tab-separated output for scripts
+    #[arg(long, global = true)]
+    porcelain: bool,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+impl Cli {
+    fn output_mode(&self) -> OutputMode {
+        if self.porcelain {
+            OutputMode::Porcelain
+        } else if self.quiet {
+            OutputMode::Quiet
+        } else {
+            OutputMode::Human
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Create a new todo from a template
+    New(NewArgs),
+    /// Create a new todo as a copy of an existing one, open and with
+    /// nothing tracked or blocking it
+    Clone {
+        id: String,
+
+        /// title of the copy instead of the original's
+        #[arg(long)]
+        title: Option<String>,
+
+        /// also copy these links, which are left out by default
+        #[arg(long, value_enum, value_delimiter = ',')]
+        keep: Vec<CloneLink>,
+    },
+    /// List todos, by default only open ones
+    List(ListArgs),
+    /// List the subtasks of a todo, and theirs, in any status
+    Subtasks { id: String },
+    /// Print a single todo with its front matter
+    Show {
+        id: String,
+
+        /// also look in the archive
+        #[arg(long)]
+        include_archived: bool,
+
+        /// list what happened to the todo, including commits touching it
+        #[arg(long)]
+        activity: bool,
+    },
+    /// Print one field of a todo, like front_matter.due_at or
+    /// checklist.completed_count
+    Get {
+        id: String,
+
+        /// dot-separated, with numbers indexing into lists, like
+        /// front_matter.tags.0
+        path: String,
+    },
+    /// Change a todo with a JSON merge patch on the document `get` reads
+    SetJson {
+        id: String,
+
+        /// like '{"front_matter": {"priority": "high", "due_at": null}}'
+        #[arg(long)]
+        patch: String,
+    },
+    /// Add front matter fields a template sets to existing todos lacking them
+    ReapplyTemplate {
+        #[arg(long, default_value = "task")]
+        template: String,
+
+        /// only update todos carrying all of these tags
+        #[arg(long = "tag", short)]
+        tags: Vec<String>,
+
+        /// write the changes instead of only previewing them
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Find todos whose front matter or body contains a text
+    Search {
+        query: String,
+
+        /// treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+
+        /// match regardless of case
+        #[arg(long, short)]
+        ignore_case: bool,
+
+        /// also search archived todos
+        #[arg(long)]
+        include_archived: bool,
+    },
+    /// Reserve ids for todos that will be created later
+    Reserve {
+        /// how many consecutive ids to reserve
+        #[arg(default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=1000))]
+        count: u32,
+    },
+    /// Initialize directory for todo
+    Init,
+    /// Copy a todo to the system clipboard
+    Copy {
+        id: String,
+
+        /// markup to render the todo body in
+        #[arg(long = "as", value_enum, default_value_t = Markup::Markdown)]
+        markup: Markup,
+    },
+    /// Render a todo's due date as an iCalendar event
+    Ics {
+        id: String,
+
+        /// write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// List known titles or tags starting with a prefix, for completion
+    Suggest {
+        /// case-insensitive start of the value, empty to list all
+        #[arg(long, default_value = "")]
+        prefix: String,
+
+        #[arg(long, value_enum, default_value_t = SuggestKind::Tag)]
+        kind: SuggestKind,
+    },
+    /// Record or verify content hashes of the whole collection
+    #[command(group(ArgGroup::new("mode").required(true).args(["write_manifest", "verify"])))]
+    Fsck {
+        /// record the current state of every file in the manifest
+        #[arg(long)]
+        write_manifest: bool,
+
+        /// report files that changed since the manifest was written
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Mirror the todos matching a query into another collection
+    Replicate {
+        /// terms that all have to match, like "tag:public status:open"
+        #[arg(long)]
+        query: String,
+
+        /// data dir of the collection to mirror into
+        #[arg(long)]
+        to: PathBuf,
+    },
+    /// Publish todos as GitHub issues using the gh CLI
+    Github {
+        #[command(subcommand)]
+        command: GithubCommands,
+    },
+    /// Show a year of activity as a grid of days
+    Heatmap {
+        /// year to show, the current one by default
+        #[arg(long, value_parser = clap::value_parser!(i32).range(1..=9999))]
+        year: Option<i32>,
+
+        #[arg(long, value_enum, default_value_t = heatmap::Metric::Completed)]
+        metric: heatmap::Metric,
+    },
+    /// Check todo files for parse errors, tag rule violations and
+    /// duplicate ids
+    Validate {
+        /// only check files staged in git, for use in a pre-commit hook
+        #[arg(long)]
+        staged: bool,
+    },
+    /// Rewrite every todo, archived ones included, with its front matter
+    /// in another style
+    ///
+    /// Every value is kept, but not the layout: key order, comments, inline
+    /// tables and how numbers and strings are written follow the new style.
+    Migrate {
+        /// toml between +++ lines or yaml between --- lines
+        #[arg(long, value_enum)]
+        front_matter: FrontMatterStyle,
+    },
+    /// Merge two versions of a todo file field by field, for git
+    ///
+    /// Register it with `* merge=todo` in tasks/.gitattributes and
+    /// `git config merge.todo.driver "todo git-merge-driver %O %A %B"`.
+    GitMergeDriver {
+        /// common ancestor version (%O)
+        base: PathBuf,
+        /// our version, overwritten with the result (%A)
+        ours: PathBuf,
+        /// their version (%B)
+        theirs: PathBuf,
+    },
+    /// Compare the collection between two git refs, task by task
+    DiffBranches {
+        from: String,
+
+        #[arg(default_value = "HEAD")]
+        to: String,
+    },
+    /// Try out changes on a copy of the collection before applying them
+    Sandbox {
+        #[command(subcommand)]
+        command: SandboxCommands,
+    },
+    /// Open a todo in $VISUAL or $EDITOR and validate it afterwards
+    Edit {
+        id: String,
+
+        /// editor command to use instead of $VISUAL or $EDITOR
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// Move todos to the trash
+    Delete {
+        #[command(flatten)]
+        selection: Selection,
+
+        /// remove the file right away instead of moving it to the trash
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Manage deleted todos
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    /// Add or remove tags of todos, or tidy up tags across all of them
+    #[command(args_conflicts_with_subcommands = true)]
+    Tag {
+        #[command(subcommand)]
+        command: Option<TagCommands>,
+
+        #[command(flatten)]
+        selection: Selection,
+
+        /// tags to add
+        #[arg(long)]
+        add: Vec<String>,
+
+        /// tags to remove
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// List all tags with the number of todos using them
+    Tags,
+    /// Accept one-line JSON captures on a Unix socket
+    Serve {
+        /// socket to listen on instead of $XDG_RUNTIME_DIR/todo.sock
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Show open todos that are overdue, due today or due within a week
+    Today,
+    /// Show open todos whose due date has passed
+    Overdue,
+    /// Show the open todos due on each of the coming days
+    Agenda {
+        /// number of days to show, starting today
+        #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u64).range(1..=366))]
+        days: u64,
+
+        /// show the coming seven days, like --days 7
+        #[arg(long, conflicts_with = "days")]
+        week: bool,
+    },
+    /// Set or clear the due date of a todo
+    #[command(group(ArgGroup::new("due").required(true).args(["date", "clear"])))]
+    Due {
+        id: String,
+
+        /// a date like 2025-03-01, tomorrow or in 3 days
+        date: Option<String>,
+
+        /// remove the due date
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Set or clear the priority of a todo
+    #[command(group(ArgGroup::new("level").required(true).args(["priority", "clear"])))]
+    Priority {
+        id: String,
+
+        /// how important the todo is
+        #[arg(value_enum)]
+        priority: Option<Priority>,
+
+        /// remove the priority
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Make a todo recur, or stop it from recurring
+    #[command(group(ArgGroup::new("recurrence").required(true).args(["rule", "clear"])))]
+    Recur {
+        id: String,
+
+        /// an RRULE like FREQ=WEEKLY;BYDAY=MO or FREQ=MONTHLY;BYMONTHDAY=-1
+        rule: Option<String>,
+
+        /// stop recurring
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Make a todo depend on others that have to be closed first
+    #[command(group(
+        ArgGroup::new("change").required(true).multiple(true).args(["depends_on", "remove"])
+    ))]
+    Link {
+        id: String,
+
+        /// todos to close before this one
+        #[arg(long)]
+        depends_on: Vec<String>,
+
+        /// dependencies to drop
+        #[arg(long)]
+        remove: Vec<String>,
+    },
+    /// Suggest the most urgent open todo whose dependencies are all
+    /// closed, going by due date, priority, age and dependents
+    Next {
+        /// how many todos to suggest
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+    },
+    /// Suggest closing stale todos, merging duplicates and demoting
+    /// overdue priorities, asking for each
+    Groom {
+        /// how long a todo may sit without activity, or overdue, like 6m
+        #[arg(long, default_value = "6m")]
+        older_than: String,
+
+        /// only list the suggestions
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+
+        /// accept every suggestion without asking
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Push the due date of a todo forward, or set it from now if unset
+    Snooze {
+        id: String,
+
+        /// how far to push, like 12h, 3d, 2w or 1m for a month
+        duration: String,
+    },
+    /// Move a todo, or all done or cancelled ones, into archive/YYYY/MM/
+    #[command(group(
+        ArgGroup::new("which").required(true).multiple(true).args(["id", "done", "cancelled"])
+    ))]
+    #[command(group(ArgGroup::new("closed").multiple(true).args(["done", "cancelled"])))]
+    Archive {
+        #[arg(conflicts_with = "closed")]
+        id: Option<String>,
+
+        /// archive every done todo
+        #[arg(long)]
+        done: bool,
+
+        /// archive every cancelled todo
+        #[arg(long)]
+        cancelled: bool,
+
+        /// with --done or --cancelled, only todos closed longer ago than
+        /// this, like 30d
+        #[arg(long, requires = "closed")]
+        older_than: Option<String>,
+    },
+    /// Mark a done or cancelled todo as open again, restoring it from the
+    /// archive if needed
+    Reopen { id: String },
+    /// Start tracking time on a todo, stopping whichever todo is tracked
+    Start { id: String },
+    /// Stop tracking time on the tracked todo
+    Stop,
+    /// Report tracked time
+    Time {
+        #[command(subcommand)]
+        command: TimeCommands,
+    },
+    /// Set the status of a todo, like in-progress or blocked
+    Status {
+        id: String,
+
+        #[arg(value_enum)]
+        status: Status,
+    },
+    /// Record what a todo waits for and mark it blocked
+    Block {
+        id: String,
+
+        /// the id of a todo, or anything else like a URL or "waiting for
+        /// the vendor"
+        on: String,
+    },
+    /// Clear a blocker of a todo, or all of them, unblocking it when none
+    /// are left
+    Unblock {
+        id: String,
+
+        /// number of the blocker as listed by `blocked`, all by default
+        index: Option<usize>,
+
+        /// why the blocker no longer blocks
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// List blocked todos with what they wait for
+    Blocked,
+    /// Mark todos as done
+    Done {
+        #[command(flatten)]
+        selection: Selection,
+    },
+    /// Mark todos as dropped rather than done
+    Cancel {
+        #[command(flatten)]
+        selection: Selection,
+
+        /// why the todo is no longer needed
+        #[arg(long)]
+        reason: String,
+
+        /// the todo that replaces this one
+        #[arg(long)]
+        superseded_by: Option<String>,
+    },
+    /// Manage checklists inside todos
+    Checklist {
+        #[command(subcommand)]
+        command: ChecklistCommands,
+    },
+    /// Work with the collection's templates
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+    /// Replace templates/managed with the configured shared template source
+    SyncTemplates,
+    /// Render todos as a print-ready HTML page
+    Print {
+        #[arg(long, value_enum, default_value_t = print::Layout::Checklist)]
+        layout: print::Layout,
+
+        /// only print todos carrying all of these tags
+        #[arg(long = "tag", short)]
+        tags: Vec<String>,
+
+        /// write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Import todos from another tool's export file
+    Import {
+        /// format of the export file
+        #[arg(long, value_enum)]
+        from: ExchangeFormat,
+
+        path: PathBuf,
+    },
+    /// Fill a collection with synthetic todos, for benchmarks and demos
+    Generate {
+        /// how many todos to create
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+
+        /// the same seed creates the same todos, a random one by default
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// data dir to fill, initialized if needed; a new one in the
+        /// system's temp dir by default
+        #[arg(long)]
+        into: Option<PathBuf>,
+    },
+    /// Export the collection for another tool
+    Export {
+        #[arg(long, value_enum)]
+        format: ExchangeFormat,
+
+        /// write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GithubCommands {
+    /// Create an issue for every matching todo that has none yet
+    Push {
+        /// terms that all have to match, like "tag:public status:open"
+        #[arg(long)]
+        query: String,
+
+        /// repository as owner/name
+        #[arg(long)]
+        repo: String,
+
+        /// issues to create between checks of the rate limit
+        #[arg(long, default_value_t = 20)]
+        batch: usize,
+
+        /// seconds to pause between two issues, as GitHub asks of bulk
+        /// creation
+        #[arg(long, default_value_t = 1)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum SandboxCommands {
+    /// Copy the collection into a sandbox in the temp dir
+    Open,
+    /// Compare the todos in the sandbox with the collection
+    Diff,
+    /// Replace the collection's todos with the sandbox's and close it
+    Apply,
+    /// Close the sandbox, dropping its changes
+    Discard,
+}
+
+#[derive(Subcommand)]
+enum TimeCommands {
+    /// Sum up tracked time per todo and per tag
+    Report {
+        /// only count time since Monday of the current week
+        #[arg(long)]
+        week: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChecklistCommands {
+    /// Inject a named checklist from templates/checklists into a todo
+    Add {
+        id: String,
+
+        /// checklist name, read from templates/checklists/<name>.md
+        #[arg(long)]
+        from: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommands {
+    /// Show which tags occur together, likely synonyms and tags used once
+    Graph {
+        /// how many of the most frequent tag pairs to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Rename a tag on every todo carrying it
+    Rename { from: String, to: String },
+    /// Replace tags on every todo by another one
+    Merge {
+        #[arg(required = true)]
+        tags: Vec<String>,
+
+        /// the tag to keep
+        #[arg(long)]
+        into: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommands {
+    /// Permanently remove every todo in the trash
+    Empty,
+}
+
+#[derive(Subcommand)]
+enum TemplateCommands {
+    /// Render every fixture in templates/tests and compare to its golden output
+    Test {
+        /// record the current output as golden instead of comparing
+        #[arg(long)]
+        update: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SuggestKind {
+    /// titles of existing todos
+    Title,
+    /// tags in use, most used first
+    Tag,
+}
+
+impl SuggestKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SuggestKind::Title => "title",
+            SuggestKind::Tag => "tag",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExchangeFormat {
+    /// Trello board JSON export
+    Trello,
+}
+
+/// What `new` creates a todo from.
+#[derive(clap::Args)]
+struct NewArgs {
+    /// the whole todo in one line, like "Call the dentist tomorrow
+    /// #health !p2"; #words are tags, !p1 to !p3 the priority and a date
+    /// phrase the due date
+    #[arg(conflicts_with = "title")]
+    text: Option<String>,
+
+    /// template to use
+    #[arg(long)]
+    template: Option<String>,
+
+    /// title
+    #[arg(long)]
+    title: Option<String>,
+
+    /// tags
+    #[arg(long = "tag", short)]
+    tags: Vec<String>,
+
+    /// use an id handed out by `reserve`
+    #[arg(long)]
+    id: Option<String>,
+
+    /// due date, like 2025-03-01, tomorrow or in 3 days
+    #[arg(long)]
+    due: Option<String>,
+
+    /// how important the todo is
+    #[arg(long, value_enum)]
+    priority: Option<Priority>,
+
+    /// make it a subtask of this todo
+    #[arg(long)]
+    parent: Option<String>,
+
+    /// ask for title, tags, due date and priority, offering what the
+    /// template and the other flags set and a suggested due date
+    #[arg(long, short)]
+    interactive: bool,
+
+    /// without a due date, use the one the [due] rules of the config
+    /// suggest
+    #[arg(long)]
+    suggest_due: bool,
+}
+
+/// Which todos `list` shows, and how.
+#[derive(clap::Args)]
+struct ListArgs {
+    /// only list todos carrying all of these tags
+    #[arg(long = "tag", short)]
+    tags: Vec<String>,
+
+    /// only list open todos whose due date has passed
+    #[arg(long)]
+    overdue: bool,
+
+    /// only list todos with one of these statuses
+    #[arg(long, value_enum, value_delimiter = ',')]
+    status: Vec<Status>,
+
+    /// only list todos with this priority
+    #[arg(long, value_enum)]
+    priority: Option<Priority>,
+
+    /// add a column with each todo's summary or first line of body
+    #[arg(long)]
+    excerpt: bool,
+
+    /// show subtasks indented below their parent
+    #[arg(long)]
+    tree: bool,
+
+    /// order by these keys, later ones breaking ties of earlier ones
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "id")]
+    sort: Vec<SortKey>,
+
+    /// reverse the order
+    #[arg(long)]
+    reverse: bool,
+
+    /// show at most this many todos
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// also list archived todos
+    #[arg(long)]
+    include_archived: bool,
+}
+
+/// The todos a bulk command works on, see `selection`.
+#[derive(clap::Args)]
+struct Selection {
+    /// ids and ranges like 3 7 9-14
+    ids: Vec<String>,
+
+    /// also every todo matching terms like "tag=backend status=open"
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// only list the todos that would change
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// Which todos `list` shows, and in which order.
+struct ListFilter {
+    tags: Vec<String>,
+    overdue: bool,
+    /// Any open status when empty.
+    status: Vec<Status>,
+    priority: Option<Priority>,
+    /// With the default statuses, also todos completed since then.
+    done_since: Option<chrono::DateTime<Utc>>,
+    sort: Vec<SortKey>,
+    reverse: bool,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SortKey {
+    Id,
+    /// soonest first, todos without a due date last
+    Due,
+    /// oldest first
+    Created,
+    /// highest first, todos without a priority last
+    Priority,
+}
+
+impl SortKey {
+    fn compare(&self, a: &FrontMatter, b: &FrontMatter) -> std::cmp::Ordering {
+        match self {
+            SortKey::Id => a.id.cmp(&b.id),
+            SortKey::Due => (a.due_at.is_none(), a.due_at).cmp(&(b.due_at.is_none(), b.due_at)),
+            SortKey::Created => a.created_at.cmp(&b.created_at),
+            SortKey::Priority => {
+                (a.priority.is_none(), a.priority).cmp(&(b.priority.is_none(), b.priority))
+            }
+        }
+    }
+}
+
+/// Open todos by the day they are due, see `CommandProcessor::schedule`.
+struct Schedule<'a> {
+    overdue: Vec<&'a TodoFile>,
+    days: BTreeMap<chrono::NaiveDate, Vec<&'a TodoFile>>,
+    unscheduled: Vec<&'a TodoFile>,
+}
+
+/// How soon an open todo is due, as grouped by `today`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Urgency {
+    Overdue,
+    /// Due later on the current local day.
+    Today,
+    /// Due within the seven days after today.
+    ThisWeek,
+}
+
+impl Urgency {
+    fn of(due_at: chrono::DateTime<Utc>, now: chrono::DateTime<Utc>) -> Option<Self> {
+        let days_ahead = (due_at.with_timezone(&Local).date_naive()
+            - now.with_timezone(&Local).date_naive())
+        .num_days();
+        match days_ahead {
+            _ if due_at < now => Some(Urgency::Overdue),
+            0 => Some(Urgency::Today),
+            1..=7 => Some(Urgency::ThisWeek),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Urgency::Overdue => "overdue",
+            Urgency::Today => "today",
+            Urgency::ThisWeek => "week",
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            Urgency::Overdue => "overdue",
+            Urgency::Today => "due today",
+            Urgency::ThisWeek => "due this week",
+        }
+    }
+}
+
+/// An entry of `show --activity`.
+struct Activity {
+    at: chrono::DateTime<Utc>,
+    kind: &'static str,
+    detail: String,
+}
+
+/// A line matched by `search`.
+struct SearchHit {
+    id: DataId,
+    line_number: usize,
+    line: String,
+    /// The match within `line`, in chars.
+    span: (usize, usize),
+}
+
+/// Characters of context kept on either side of a match in snippets.
+const SNIPPET_CONTEXT: usize = 30;
+
+impl SearchHit {
+    /// The matching line cut down to the match and some context, with
+    /// the match in bold red when `highlight` is set.
+    fn snippet(&self, highlight: bool) -> String {
+        let chars: Vec<char> = self.line.chars().collect();
+        let (start, end) = self.span;
+        let from = start.saturating_sub(SNIPPET_CONTEXT);
+        let to = (end + SNIPPET_CONTEXT).min(chars.len());
+        let part = |range: std::ops::Range<usize>| chars[range].iter().collect::<String>();
+
+        let mut snippet = String::new();
+        if from > 0 {
+            snippet.push('…');
+        }
+        snippet.push_str(part(from..start).trim_start());
+        if highlight {
+            snippet.push_str(&format!("\x1b[1;31m{}\x1b[0m", part(start..end)));
+        } else {
+            snippet.push_str(&part(start..end));
+        }
+        snippet.push_str(&part(end..to));
+        if to < chars.len() {
+            snippet.push('…');
+        }
+        snippet
+    }
+}
+
+/// Directory below the data dir that deleted todos are moved into.
+const TRASH_DIR: &str = ".trash";
+
+struct CommandProcessor {
+    data_dir: PathBuf,
+    tasks_dir: PathBuf,
+    templates_dir: PathBuf,
+    hbs: Arc<Handlebars<'static>>,
+    collection: Collection,
+    config: Config,
+}
+
+impl CommandProcessor {
+    pub fn new(
+        hbs: Handlebars<'static>,
+        collection: Collection,
+        config: Config,
+        data_dir: PathBuf,
+    ) -> CommandProcessor {
+        let tasks_dir = data_dir.join("tasks");
+        let templates_dir = data_dir.join("templates");
+        Self {
+            hbs: Arc::new(hbs),
+            collection,
+            config,
+            data_dir,
+            tasks_dir,
+            templates_dir,
+        }
+    }
+}
+impl CommandProcessor {
+    /// Parses an id as given on the command line.
+    pub fn resolve_id(&self, input: &str) -> anyhow::Result<DataId> {
+        self.config.ids.parse(input)
+    }
+
+    pub fn get(&self, id: DataId) -> anyhow::Result<&TodoFile> {
+        self.collection
+            .get(&id)
+            .ok_or_else(|| anyhow!("todo {} does not exist", self.config.ids.display(id)))
+    }
+
+    pub fn get_mut(&mut self, id: DataId) -> anyhow::Result<&mut TodoFile> {
+        let display = self.config.ids.display(id);
+        self.collection
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("todo {display} does not exist"))
+    }
+
+    /// The todos matching `filter` in its order, ties broken by id.
+    pub fn list(&self, filter: &ListFilter, now: chrono::DateTime<Utc>) -> Vec<&TodoFile> {
+        let mut todos: Vec<_> = self
+            .collection
+            .values()
+            .filter(|todo| {
+                let front_matter = &todo.data.front_matter;
+                let recently_done = match (front_matter.completed_at, filter.done_since) {
+                    (Some(at), Some(since)) => front_matter.status == Status::Done && at >= since,
+                    _ => false,
+                };
+                let status_matches = match filter.status.as_slice() {
+                    [] => front_matter.status.is_open() || recently_done,
+                    statuses => statuses.contains(&front_matter.status),
+                };
+                let is_overdue = front_matter.status.is_open()
+                    && front_matter.due_at.is_some_and(|due| due < now);
+
+                status_matches
+                    && (!filter.overdue || is_overdue)
+                    && filter.priority.is_none_or(|level| front_matter.priority == Some(level))
+                    && filter.tags.iter().all(|tag| front_matter.tags.contains(tag))
+            })
+            .collect();
+
+        todos.sort_by(|a, b| {
+            let (a, b) = (&a.data.front_matter, &b.data.front_matter);
+            filter
+                .sort
+                .iter()
+                .map(|key| key.compare(a, b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if filter.reverse {
+            todos.reverse();
+        }
+        if let Some(limit) = filter.limit {
+            todos.truncate(limit);
+        }
+        todos
+    }
+
+    /// Since when completed todos stay visible in `list` and `today`, if
+    /// they do at all.
+    pub fn done_visible_since(
+        &self,
+        now: chrono::DateTime<Utc>,
+    ) -> Option<chrono::DateTime<Utc>> {
+        match self.config.done.keep_visible_days {
+            0 => None,
+            days => now.checked_sub_days(chrono::Days::new(days)),
+        }
+    }
+
+    /// Todos completed since `since`, the most recent first.
+    pub fn done_since(&self, since: chrono::DateTime<Utc>) -> Vec<&TodoFile> {
+        let mut todos: Vec<_> = self
+            .collection
+            .values()
+            .filter(|todo| {
+                let front_matter = &todo.data.front_matter;
+                front_matter.status == Status::Done
+                    && front_matter.completed_at.is_some_and(|at| at >= since)
+            })
+            .collect();
+        todos.sort_by_key(|todo| std::cmp::Reverse(todo.data.front_matter.completed_at));
+        todos
+    }
+
+    /// Open todos due within a week or earlier, grouped by urgency and
+    /// sorted by due date. Groups without todos are left out.
+    pub fn agenda(&self, now: chrono::DateTime<Utc>) -> BTreeMap<Urgency, Vec<&TodoFile>> {
+        let mut agenda: BTreeMap<Urgency, Vec<&TodoFile>> = BTreeMap::new();
+        for todo in self.collection.values() {
+            let front_matter = &todo.data.front_matter;
+            let Some(due_at) = front_matter.due_at.filter(|_| front_matter.status.is_open()) else {
+                continue;
+            };
+            if let Some(urgency) = Urgency::of(due_at, now) {
+                agenda.entry(urgency).or_default().push(todo);
+            }
+        }
+        for todos in agenda.values_mut() {
+            todos.sort_by_key(|todo| (todo.data.front_matter.due_at, todo.data.front_matter.id));
+        }
+        agenda
+    }
+
+    /// Buckets the open todos by the local day they are due on, for `days`
+    /// days starting today. Every day gets a bucket, even an empty one.
+    pub fn schedule(&self, now: chrono::DateTime<Utc>, days: u64) -> Schedule<'_> {
+        let today = now.with_timezone(&Local).date_naive();
+        let mut schedule = Schedule {
+            overdue: vec![],
+            days: (0..days)
+                .filter_map(|offset| today.checked_add_days(chrono::Days::new(offset)))
+                .map(|day| (day, vec![]))
+                .collect(),
+            unscheduled: vec![],
+        };
+
+        for todo in self.collection.values() {
+            let front_matter = &todo.data.front_matter;
+            if !front_matter.status.is_open() {
+                continue;
+            }
+            match front_matter.due_at {
+                None => schedule.unscheduled.push(todo),
+                Some(due_at) if due_at < now => schedule.overdue.push(todo),
+                Some(due_at) => {
+                    let day = due_at.with_timezone(&Local).date_naive();
+                    if let Some(todos) = schedule.days.get_mut(&day) {
+                        todos.push(todo);
+                    }
+                }
+            }
+        }
+        let by_due = |todo: &&TodoFile| (todo.data.front_matter.due_at, todo.data.front_matter.id);
+        schedule.overdue.sort_by_key(by_due);
+        for todos in schedule.days.values_mut() {
+            todos.sort_by_key(by_due);
+        }
+        schedule
+    }
+
+    /// Every line of every todo file matching `pattern`, by id and line as
+    /// the file is on disk. In the front matter only values are searched,
+    /// not keys or table headers.
+    pub async fn search(&self, pattern: &pattern::Pattern) -> anyhow::Result<Vec<SearchHit>> {
+        let mut hits = vec![];
+        for todo in self.collection.values() {
+            let bytes = tokio::fs::read(todo.path.as_path()).await?;
+            let text = String::from_utf8_lossy(&bytes);
+            let style = todo.data.style;
+            let (separator, skipped) = match style {
+                FrontMatterStyle::Toml => ('=', &['[', '#'][..]),
+                FrontMatterStyle::Yaml => (':', &['#'][..]),
+            };
+            let mut fences = 0;
+            for (index, line) in text.lines().enumerate() {
+                if fences < 2 && line.trim_end() == style.fence() {
+                    fences += 1;
+                    continue;
+                }
+                let searched = match fences {
+                    1 if line.trim_start().starts_with(skipped) => continue,
+                    1 => line.split_once(separator).map_or(line, |(_, value)| value),
+                    _ => line,
+                };
+                // the searched part is always the end of the line
+                let offset = line.chars().count() - searched.chars().count();
+                if let Some((start, end)) = pattern.find(searched) {
+                    hits.push(SearchHit {
+                        id: todo.data.front_matter.id,
+                        line_number: index + 1,
+                        line: line.to_string(),
+                        span: (start + offset, end + offset),
+                    });
+                }
+            }
+        }
+        Ok(hits)
+    }
+
+    /// The front matter of a todo as named, readable values.
+    pub fn fields(&self, todo: &TodoFile) -> Vec<(&'static str, String)> {
+        let front_matter = &todo.data.front_matter;
+        let mut fields = vec![
+            ("id", front_matter.id.to_string()),
+            ("status", front_matter.status.name().to_string()),
+            (
+                "created",
+                front_matter.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            ),
+            ("due", format_due(front_matter.due_at)),
+            ("tags", front_matter.tags.join(", ")),
+        ];
+        if let Some(priority) = front_matter.priority {
+            fields.push(("priority", priority.name().to_string()));
+        }
+        if let Some(recurrence) = &front_matter.recurrence {
+            fields.push(("recurs", recurrence.clone()));
+        }
+        if let Some(parent) = front_matter.parent {
+            fields.push(("parent", self.config.ids.display(parent)));
+        }
+        if !front_matter.depends_on.is_empty() {
+            let ids: Vec<String> =
+                front_matter.depends_on.iter().map(|id| self.config.ids.display(*id)).collect();
+            fields.push(("depends on", ids.join(", ")));
+        }
+        let blockers: Vec<String> = front_matter
+            .blocked_by
+            .iter()
+            .filter(|blocker| blocker.is_active())
+            .map(|blocker| self.describe_blocker(&blocker.on))
+            .collect();
+        if !blockers.is_empty() {
+            fields.push(("blocked by", blockers.join("; ")));
+        }
+        if !front_matter.time_entries.is_empty() {
+            let tracked = front_matter
+                .time_entries
+                .iter()
+                .map(|entry| timesheet::tracked(entry, None, Utc::now()))
+                .fold(chrono::TimeDelta::zero(), |sum, tracked| sum + tracked);
+            fields.push(("tracked", timesheet::format(tracked)));
+        }
+        if let Some(summary) = &front_matter.summary {
+            fields.push(("summary", summary.clone()));
+        }
+        let (_, checklists) = checklist::split(todo.data.body());
+        let (criteria, others): (Vec<_>, Vec<_>) = checklists
+            .iter()
+            .partition(|checklist| checklist.is_acceptance_criteria());
+        if !criteria.is_empty() {
+            let (met, total) = checklist::progress(criteria);
+            fields.push(("acceptance", format!("{met}/{total} met")));
+        }
+        if !others.is_empty() {
+            let (done, total) = checklist::progress(others);
+            fields.push(("checklists", format!("{done}/{total} done")));
+        }
+        if let Some(completed_at) = front_matter.completed_at {
+            fields.push(("completed", completed_at.format("%Y-%m-%d %H:%M").to_string()));
+        }
+        if let Some(cancellation) = &front_matter.cancellation {
+            fields.push(("reason", cancellation.reason.clone()));
+            if let Some(other) = cancellation.superseded_by {
+                fields.push(("superseded by", self.config.ids.display(other)));
+            }
+        }
+        fields
+    }
+
+    /// What happened to the todo, oldest first: its status changes from
+    /// the front matter and, when the data dir is a git repository, the
+    /// commits touching its file.
+    pub async fn activity(&self, todo: &TodoFile) -> Vec<Activity> {
+        let front_matter = &todo.data.front_matter;
+        let mut events = vec![Activity {
+            at: front_matter.created_at,
+            kind: "created",
+            detail: String::new(),
+        }];
+        if let Some(completed_at) = front_matter.completed_at {
+            events.push(Activity {
+                at: completed_at,
+                kind: "completed",
+                detail: String::new(),
+            });
+        }
+        if let Some(cancellation) = &front_matter.cancellation {
+            events.push(Activity {
+                at: cancellation.at,
+                kind: "cancelled",
+                detail: cancellation.reason.clone(),
+            });
+        }
+
+        // collections outside of git simply have no commits to show
+        let path = todo.path.strip_prefix(&self.data_dir).unwrap_or(&todo.path);
+        if let Ok(commits) = git::file_log(&self.data_dir, path).await {
+            let ignored = &self.config.diff.ignore_fields;
+            let mut previous: Option<TodoData> = None;
+            for commit in commits {
+                if !ignored.is_empty() {
+                    let version = git::load_file_at(&self.data_dir, &commit.hash, path).await.ok();
+                    let noise = previous
+                        .as_ref()
+                        .zip(version.as_ref())
+                        .is_some_and(|(old, new)| !git::differs(old, new, ignored));
+                    if version.is_some() {
+                        previous = version;
+                    }
+                    if noise {
+                        continue;
+                    }
+                }
+                events.push(Activity {
+                    at: commit.at,
+                    kind: "commit",
+                    detail: commit.subject,
+                });
+            }
+        }
+        events.sort_by_key(|event| event.at);
+        events
+    }
+
+    pub async fn add_checklist(&mut self, id: DataId, name: &str) -> anyhow::Result<()> {
+        let body = self.get(id)?.data.body();
+        let checklist = checklist::load_template(self.templates_dir.as_path(), name).await?;
+
+        let (_, existing) = checklist::split(body);
+        if existing.iter().any(|known| known.name == checklist.name) {
+            return Err(anyhow!(
+                "todo {} already has a checklist '{}'",
+                self.config.ids.display(id),
+                checklist.name
+            ));
+        }
+
+        let todo_file = self.get_mut(id)?;
+        todo_file.data.content = checklist::inject(todo_file.data.content.as_str(), &checklist);
+        todo_file.write_file().await
+    }
+
+    /// Opens the todo in `editor` and keeps the result only if it still
+    /// parses and keeps its id; otherwise the file is restored.
+    pub async fn edit(&self, id: DataId, editor: &str) -> anyhow::Result<()> {
+        let path = self.get(id)?.path.as_path();
+        let original = tokio::fs::read(path).await?;
+
+        let mut command = editor.split_whitespace();
+        let program = command.next().ok_or_else(|| anyhow!("no editor configured"))?;
+        let status = tokio::process::Command::new(program)
+            .args(command)
+            .arg(path)
+            .status()
+            .await
+            .map_err(|err| anyhow!("cannot run editor '{program}': {err}"))?;
+        if !status.success() {
+            tokio::fs::write(path, original).await?;
+            return Err(anyhow!("editor exited with {status}, discarded the changes"));
+        }
+
+        let edited = tokio::fs::read_to_string(path).await?;
+        let problem = match TodoData::from_str(edited.as_str()) {
+            Ok(data) if data.front_matter.id == id => return Ok(()),
+            Ok(data) => format!(
+                "the id changed to {}, ids can't be edited",
+                self.config.ids.display(data.front_matter.id)
+            ),
+            Err(err) => err.to_string(),
+        };
+        tokio::fs::write(path, original).await?;
+        Err(anyhow!(
+            "edited todo {} is invalid ({problem}), restored the previous version",
+            self.config.ids.display(id)
+        ))
+    }
+
+    /// Moves the todo's file into the trash, or removes it for good with
+    /// `purge`. Returns where the file went, if anywhere.
+    pub async fn delete(&mut self, id: DataId, purge: bool) -> anyhow::Result<Option<PathBuf>> {
+        let todo_file = self.get(id)?;
+        if purge {
+            tokio::fs::remove_file(todo_file.path.as_path()).await?;
+            self.collection.remove(&id);
+            return Ok(None);
+        }
+
+        let trash_dir = self.data_dir.join(TRASH_DIR);
+        tokio::fs::create_dir_all(trash_dir.as_path()).await?;
+        let target = trash_dir.join(format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            todo_file.path.file_name().unwrap().to_string_lossy()
+        ));
+        tokio::fs::rename(todo_file.path.as_path(), target.as_path()).await?;
+        self.collection.remove(&id);
+        Ok(Some(target))
+    }
+
+    /// Rewrites every todo, archived ones included, with its front matter
+    /// in `style`. All are converted and read back before any is written,
+    /// so one that would lose anything leaves every file as it is. Returns
+    /// the todos rewritten and how many files were skipped as they don't
+    /// parse.
+    pub async fn migrate(
+        &self,
+        style: FrontMatterStyle,
+    ) -> anyhow::Result<(Vec<DataId>, usize)> {
+        let archived = archive::load(self.data_dir.as_path()).await?;
+        let on_disk = validate::worktree(&self.data_dir).await?.len()
+            + archive::files(&self.data_dir).await?.len();
+        let skipped = on_disk.saturating_sub(self.collection.len() + archived.len());
+        let mut converted = vec![];
+        for todo_file in self.collection.values().chain(archived.values()) {
+            if todo_file.data.style == style {
+                continue;
+            }
+            let mut data = todo_file.data.clone();
+            data.style = style;
+            let bytes = data.to_bytes();
+            let read_back = TodoData::from_str(String::from_utf8_lossy(&bytes).as_ref())?;
+            let before = front_matter_table(&todo_file.data.front_matter)?;
+            if front_matter_table(&read_back.front_matter)? != before
+                || read_back.content != data.content
+            {
+                return Err(anyhow!(
+                    "{} can't be converted without losing something, no todo was changed",
+                    todo_file.path.strip_prefix(&self.data_dir).unwrap_or(&todo_file.path).display()
+                ));
+            }
+            converted.push((todo_file, bytes));
+        }
+
+        let mut migrated = vec![];
+        for (todo_file, bytes) in converted {
+            tokio::fs::write(todo_file.path.as_path(), bytes).await?;
+            migrated.push(todo_file.data.front_matter.id);
+        }
+        Ok((migrated, skipped))
+    }
+
+    /// Moves the todo's file into the archive, by the month it was closed
+    /// in or the current one for open todos. Returns the new path.
+    pub async fn archive(&mut self, id: DataId) -> anyhow::Result<PathBuf> {
+        let todo_file = self.get(id)?;
+        let closed_at = todo_file.data.front_matter.closed_at().unwrap_or_else(Utc::now);
+        let dir = archive::dir_for(&self.data_dir, closed_at);
+        tokio::fs::create_dir_all(dir.as_path()).await?;
+        let target = dir.join(todo_file.path.file_name().unwrap());
+        if tokio::fs::try_exists(target.as_path()).await? {
+            return Err(anyhow!("{} is already archived", target.display()));
+        }
+        tokio::fs::rename(todo_file.path.as_path(), target.as_path()).await?;
+        self.collection.remove(&id);
+        Ok(target)
+    }
+
+    /// Every todo below `id`, depth first, with its depth starting at 0
+    /// for direct subtasks.
+    pub fn subtasks(&self, id: DataId) -> anyhow::Result<Vec<(usize, &TodoFile)>> {
+        let children = |parent: DataId| {
+            self.collection.values().rev().filter(move |todo| {
+                let front_matter = &todo.data.front_matter;
+                // a todo made a subtask of its own subtask would come around again
+                front_matter.parent == Some(parent) && front_matter.id != id
+            })
+        };
+        self.get(id)?;
+        let mut subtasks = vec![];
+        let mut pending: Vec<_> = children(id).map(|todo| (0, todo)).collect();
+        while let Some((depth, todo)) = pending.pop() {
+            subtasks.push((depth, todo));
+            pending.extend(children(todo.data.front_matter.id).map(|child| (depth + 1, child)));
+        }
+        Ok(subtasks)
+    }
+
+    /// The todo with a time entry still running.
+    pub fn tracked(&self) -> Option<DataId> {
+        self.collection
+            .values()
+            .map(|todo| &todo.data.front_matter)
+            .find(|front_matter| {
+                front_matter.time_entries.last().is_some_and(|entry| entry.end.is_none())
+            })
+            .map(|front_matter| front_matter.id)
+    }
+
+    /// Starts a time entry on an open todo, marking it in progress. A todo
+    /// tracked so far is stopped first and returned with the time of its
+    /// last entry.
+    pub async fn start(
+        &mut self,
+        id: DataId,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Option<(DataId, chrono::TimeDelta)>> {
+        let display = self.config.ids.display(id);
+        let status = self.get(id)?.data.front_matter.status;
+        if !status.is_open() {
+            return Err(anyhow!("todo {display} is {}", status.name()));
+        }
+        let stopped = match self.tracked() {
+            Some(tracked) if tracked == id => {
+                return Err(anyhow!("todo {display} is already tracked"));
+            }
+            Some(_) => Some(self.stop(now).await?),
+            None => None,
+        };
+
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        if front_matter.status == Status::Open {
+            front_matter.status = Status::InProgress;
+        }
+        front_matter.time_entries.push(TimeEntry {
+            start: now,
+            end: None,
+        });
+        todo_file.write_file().await?;
+        Ok(stopped)
+    }
+
+    /// Ends the running time entry and returns its todo and duration.
+    pub async fn stop(
+        &mut self,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<(DataId, chrono::TimeDelta)> {
+        let id = self.tracked().ok_or_else(|| anyhow!("no todo is tracked"))?;
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        front_matter.stop_tracking(now);
+        let tracked = timesheet::tracked(front_matter.time_entries.last().unwrap(), None, now);
+        todo_file.write_file().await?;
+        Ok((id, tracked))
+    }
+
+    /// Marks the todo open again, moving it back into `tasks/` when it was
+    /// archived. Returns whether it was.
+    pub async fn reopen(&mut self, id: DataId) -> anyhow::Result<bool> {
+        let display = self.config.ids.display(id);
+        if let Some(todo_file) = self.collection.get_mut(&id) {
+            let status = todo_file.data.front_matter.status;
+            if status.is_open() {
+                return Err(anyhow!("todo {display} is already {}", status.name()));
+            }
+            todo_file.data.front_matter.reopen();
+            todo_file.write_file().await?;
+            return Ok(false);
+        }
+
+        let mut archived = archive::load(&self.data_dir)
+            .await?
+            .remove(&id)
+            .ok_or_else(|| anyhow!("todo {display} does not exist"))?;
+        let target = TodoFile::gen_filepath(&self.tasks_dir, &self.config.ids, id);
+        if tokio::fs::try_exists(target.as_path()).await? {
+            return Err(anyhow!(
+                "cannot restore todo {display}, {} already exists",
+                target.strip_prefix(&self.data_dir).unwrap_or(&target).display()
+            ));
+        }
+        let archived_path = std::mem::replace(&mut archived.path, target);
+        archived.data.front_matter.reopen();
+        archived.write_file().await?;
+        tokio::fs::remove_file(archived_path).await?;
+        self.collection.insert(id, archived);
+        Ok(true)
+    }
+
+    /// Todos in one of the closed `statuses`, only those closed before
+    /// `cutoff` if given.
+    pub fn closed_before(
+        &self,
+        statuses: &[Status],
+        cutoff: Option<chrono::DateTime<Utc>>,
+    ) -> Vec<DataId> {
+        self.collection
+            .values()
+            .map(|todo| &todo.data.front_matter)
+            .filter(|front_matter| statuses.contains(&front_matter.status))
+            .filter(|front_matter| {
+                cutoff.is_none_or(|cutoff| front_matter.closed_at().is_some_and(|at| at < cutoff))
+            })
+            .map(|front_matter| front_matter.id)
+            .collect()
+    }
+
+    /// Removes everything in the trash, returning how many files that was.
+    pub async fn empty_trash(&self) -> anyhow::Result<usize> {
+        let trash_dir = self.data_dir.join(TRASH_DIR);
+        if !tokio::fs::try_exists(trash_dir.as_path()).await? {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(trash_dir.as_path()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                tokio::fs::remove_dir_all(entry.path()).await?;
+            } else {
+                tokio::fs::remove_file(entry.path()).await?;
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Adds and removes tags, normalizing both, and returns the new set.
+    pub async fn retag(
+        &mut self,
+        id: DataId,
+        add: Vec<String>,
+        remove: Vec<String>,
+    ) -> anyhow::Result<Vec<String>> {
+        let add = self.config.tags.normalize_all(add)?;
+        let remove = remove
+            .iter()
+            .map(|tag| self.config.tags.normalize(tag))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.hint_similar_tags(&add);
+
+        let todo_file = self.get_mut(id)?;
+        let mut tags: Vec<String> = vec![];
+        for tag in todo_file.data.front_matter.tags.drain(..).chain(add) {
+            if !remove.contains(&tag) && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        todo_file.data.front_matter.tags = tags.clone();
+        todo_file.write_file().await?;
+        Ok(tags)
+    }
+
+    /// Replaces `tags` by `into` on every todo carrying any of them, at
+    /// the place of the first, returning the todos changed with their new
+    /// tags. Renaming refuses to replace by a tag already in use.
+    pub async fn replace_tags(
+        &mut self,
+        tags: &[String],
+        into: &str,
+        renaming: bool,
+    ) -> anyhow::Result<Vec<(DataId, Vec<String>)>> {
+        let into = self.config.tags.normalize(into)?;
+        let tags = tags
+            .iter()
+            .map(|tag| self.config.tags.normalize(tag))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let tagged = |tag: &String| {
+            self.collection
+                .values()
+                .any(|todo| todo.data.front_matter.tags.contains(tag))
+        };
+        if let Some(unused) = tags.iter().find(|tag| !tagged(tag)) {
+            return Err(anyhow!("no todo is tagged '{unused}'"));
+        }
+        if renaming && tagged(&into) {
+            return Err(anyhow!(
+                "tag '{into}' is in use, join the tags with `todo tag merge {} --into {into}`",
+                tags.join(" ")
+            ));
+        }
+
+        let mut retagged = vec![];
+        for todo_file in self.collection.values_mut() {
+            let front_matter = &mut todo_file.data.front_matter;
+            if !front_matter.tags.iter().any(|tag| tags.contains(tag)) {
+                continue;
+            }
+            let mut replaced: Vec<String> = vec![];
+            for tag in front_matter.tags.drain(..) {
+                let tag = if tags.contains(&tag) { into.clone() } else { tag };
+                if !replaced.contains(&tag) {
+                    replaced.push(tag);
+                }
+            }
+            front_matter.tags = replaced;
+            let retag = (front_matter.id, front_matter.tags.clone());
+            todo_file.write_file().await?;
+            retagged.push(retag);
+        }
+        Ok(retagged)
+    }
+
+    pub async fn set_due(
+        &mut self,
+        id: DataId,
+        due_at: Option<chrono::DateTime<Utc>>,
+    ) -> anyhow::Result<()> {
+        let todo_file = self.get_mut(id)?;
+        todo_file.data.front_matter.due_at = due_at;
+        todo_file.write_file().await
+    }
+
+    pub async fn set_priority(
+        &mut self,
+        id: DataId,
+        priority: Option<Priority>,
+    ) -> anyhow::Result<()> {
+        let todo_file = self.get_mut(id)?;
+        todo_file.data.front_matter.priority = priority;
+        todo_file.write_file().await
+    }
+
+    /// Adds and removes dependencies of a todo and returns the new set.
+    /// Refuses a dependency that does not exist or already depends on the
+    /// todo, directly or through others.
+    pub async fn link(
+        &mut self,
+        id: DataId,
+        add: Vec<DataId>,
+        remove: Vec<DataId>,
+    ) -> anyhow::Result<Vec<DataId>> {
+        let display = self.config.ids.display(id);
+        self.get(id)?;
+        for &other in &add {
+            self.get(other)?;
+            if let Some(path) = self.dependency_path(other, id) {
+                let path: Vec<String> =
+                    path.iter().map(|id| self.config.ids.display(*id)).collect();
+                return Err(anyhow!(
+                    "todo {display} cannot depend on {}, that would be a cycle ({display} -> {})",
+                    self.config.ids.display(other),
+                    path.join(" -> ")
+                ));
+            }
+        }
+
+        let todo_file = self.get_mut(id)?;
+        let mut dependencies: Vec<DataId> = vec![];
+        for other in todo_file.data.front_matter.depends_on.drain(..).chain(add) {
+            if !remove.contains(&other) && !dependencies.contains(&other) {
+                dependencies.push(other);
+            }
+        }
+        todo_file.data.front_matter.depends_on = dependencies.clone();
+        todo_file.write_file().await?;
+        Ok(dependencies)
+    }
+
+    /// The todos leading from `from` to `to` along their dependencies, both
+    /// included, if `from` depends on `to` at all.
+    fn dependency_path(&self, from: DataId, to: DataId) -> Option<Vec<DataId>> {
+        let mut pending = vec![vec![from]];
+        let mut seen = vec![from];
+        while let Some(path) = pending.pop() {
+            let last = *path.last().unwrap();
+            if last == to {
+                return Some(path);
+            }
+            let Some(todo) = self.collection.get(&last) else {
+                continue;
+            };
+            for &next in todo.data.front_matter.depends_on.iter().rev() {
+                if !seen.contains(&next) {
+                    seen.push(next);
+                    pending.push([path.as_slice(), &[next]].concat());
+                }
+            }
+        }
+        None
+    }
+
+    /// Open todos that are not blocked and whose dependencies are all
+    /// closed. Dependencies no longer in the collection, archived or
+    /// deleted, count as closed.
+    pub fn actionable(&self) -> Vec<&TodoFile> {
+        self.collection
+            .values()
+            .filter(|todo| {
+                let front_matter = &todo.data.front_matter;
+                front_matter.status.is_open()
+                    && front_matter.status != Status::Blocked
+                    && front_matter.depends_on.iter().all(|other| {
+                        self.collection
+                            .get(other)
+                            .is_none_or(|other| !other.data.front_matter.status.is_open())
+                    })
+            })
+            .collect()
+    }
+
+    /// The due date the `[due]` rules suggest for a new todo from
+    /// `template` with `tags`, given how many open todos are due each day.
+    pub fn suggest_due(
+        &self,
+        template: &str,
+        tags: &[String],
+    ) -> anyhow::Result<Option<(chrono::DateTime<Utc>, String)>> {
+        self.config.due.suggest(template, tags, Local::now(), |day| {
+            self.collection
+                .values()
+                .map(|todo| &todo.data.front_matter)
+                .filter(|front_matter| front_matter.status.is_open())
+                .filter_map(|front_matter| front_matter.due_at)
+                .filter(|due_at| due_at.with_timezone(&Local).date_naive() == day)
+                .count()
+        })
+    }
+
+    /// Applies a JSON merge patch to the document of a todo, see `fields`.
+    pub async fn patch(&mut self, id: DataId, patch: &serde_json::Value) -> anyhow::Result<()> {
+        let todo_file = self.get_mut(id)?;
+        fields::patch(&mut todo_file.data, patch)?;
+        todo_file.write_file().await
+    }
+
+    pub async fn set_recurrence(
+        &mut self,
+        id: DataId,
+        recurrence: Option<String>,
+    ) -> anyhow::Result<()> {
+        if let Some(rule) = &recurrence {
+            recurrence::Rule::from_str(rule)?;
+        }
+        let todo_file = self.get_mut(id)?;
+        todo_file.data.front_matter.recurrence = recurrence;
+        todo_file.write_file().await
+    }
+
+    /// The actionable todos with their scores, the most urgent first and
+    /// ties broken by id.
+    pub fn ranked(&self, now: chrono::DateTime<Utc>) -> Vec<(f64, &TodoFile)> {
+        let mut ranked: Vec<_> = self
+            .actionable()
+            .into_iter()
+            .map(|todo| {
+                let id = todo.data.front_matter.id;
+                let dependents = self
+                    .collection
+                    .values()
+                    .map(|other| &other.data.front_matter)
+                    .filter(|other| other.status.is_open() && other.depends_on.contains(&id))
+                    .count();
+                let score = score::score(&todo.data, dependents, now, &self.config.next);
+                (score, todo)
+            })
+            .collect();
+        // a stable sort keeps the id order of equal scores
+        ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        ranked
+    }
+
+    /// Adds `duration` to the due date, counting from now when there is
+    /// none, and returns the new one.
+    pub async fn snooze(
+        &mut self,
+        id: DataId,
+        duration: &str,
+    ) -> anyhow::Result<chrono::DateTime<Utc>> {
+        let todo_file = self.get_mut(id)?;
+        let from = todo_file.data.front_matter.due_at.unwrap_or_else(Utc::now);
+        let due_at = dates::shift(from, duration)?;
+        todo_file.data.front_matter.due_at = Some(due_at);
+        todo_file.write_file().await?;
+        Ok(due_at)
+    }
+
+    /// What `on` names as a blocker: an existing todo when it is the id of
+    /// one, anything else otherwise.
+    pub fn blocked_on(&self, on: &str) -> BlockedOn {
+        match self.resolve_id(on) {
+            Ok(other) if self.collection.contains_key(&other) => BlockedOn::Todo(other),
+            _ => BlockedOn::External(on.trim().to_string()),
+        }
+    }
+
+    /// A blocker for reports, with the title and status of a todo.
+    pub fn describe_blocker(&self, on: &BlockedOn) -> String {
+        match on {
+            BlockedOn::Todo(other) => match self.collection.get(other) {
+                Some(todo) => format!(
+                    "todo {} {} ({})",
+                    self.config.ids.display(*other),
+                    todo.data.title().unwrap_or("-"),
+                    todo.data.front_matter.status.name()
+                ),
+                None => format!("todo {}", self.config.ids.display(*other)),
+            },
+            BlockedOn::External(what) => what.clone(),
+        }
+    }
+
+    /// Adds a blocker to an open todo and marks it blocked. Returns the
+    /// blocker's number, counting from 1.
+    pub async fn block(
+        &mut self,
+        id: DataId,
+        on: BlockedOn,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<usize> {
+        let display = self.config.ids.display(id);
+        match &on {
+            BlockedOn::Todo(other) if *other == id => {
+                return Err(anyhow!("a todo cannot block itself"));
+            }
+            BlockedOn::External(what) if what.is_empty() => {
+                return Err(anyhow!("a blocker needs a description"));
+            }
+            _ => {}
+        }
+        let description = self.describe_blocker(&on);
+
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        if !front_matter.status.is_open() {
+            return Err(anyhow!("todo {display} is {}", front_matter.status.name()));
+        }
+        let blockers = &mut front_matter.blocked_by;
+        if blockers.iter().any(|blocker| blocker.is_active() && blocker.on == on) {
+            return Err(anyhow!("todo {display} is already blocked by {description}"));
+        }
+        blockers.push(Blocker {
+            on,
+            since: now,
+            cleared: None,
+        });
+        let index = blockers.len();
+        front_matter.status = Status::Blocked;
+        todo_file.write_file().await?;
+        Ok(index)
+    }
+
+    /// Clears the blocker numbered `index`, or all active ones, recording
+    /// when and why. A blocked todo without active blockers left is open
+    /// again. Returns the numbers of the cleared blockers.
+    pub async fn unblock(
+        &mut self,
+        id: DataId,
+        index: Option<usize>,
+        reason: Option<String>,
+        now: chrono::DateTime<Utc>,
+    ) -> anyhow::Result<Vec<usize>> {
+        let display = self.config.ids.display(id);
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        let active: Vec<usize> = front_matter
+            .blocked_by
+            .iter()
+            .enumerate()
+            .filter(|(_, blocker)| blocker.is_active())
+            .map(|(i, _)| i + 1)
+            .collect();
+        let cleared = match index {
+            None if active.is_empty() => {
+                return Err(anyhow!("todo {display} has no active blockers"));
+            }
+            None => active,
+            Some(index) if active.contains(&index) => vec![index],
+            Some(index) if (1..=front_matter.blocked_by.len()).contains(&index) => {
+                return Err(anyhow!("blocker #{index} of todo {display} is already cleared"));
+            }
+            Some(index) => return Err(anyhow!("todo {display} has no blocker #{index}")),
+        };
+
+        for index in &cleared {
+            front_matter.blocked_by[index - 1].cleared = Some(Clearance {
+                at: now,
+                reason: reason.clone(),
+            });
+        }
+        let still_blocked = front_matter.blocked_by.iter().any(Blocker::is_active);
+        if !still_blocked && front_matter.status == Status::Blocked {
+            front_matter.status = Status::Open;
+        }
+        todo_file.write_file().await?;
+        Ok(cleared)
+    }
+
+    /// Open todos that are blocked or have active blockers, with those
+    /// blockers by number.
+    pub fn blocked(&self) -> Vec<(&TodoFile, Vec<(usize, &Blocker)>)> {
+        self.collection
+            .values()
+            .filter(|todo| todo.data.front_matter.status.is_open())
+            .filter_map(|todo| {
+                let front_matter = &todo.data.front_matter;
+                let blockers: Vec<_> = front_matter
+                    .blocked_by
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, blocker)| blocker.is_active())
+                    .map(|(i, blocker)| (i + 1, blocker))
+                    .collect();
+                let blocked = front_matter.status == Status::Blocked || !blockers.is_empty();
+                blocked.then_some((todo, blockers))
+            })
+            .collect()
+    }
+
+    /// Moves a todo to `status`. Completing goes through the checks of
+    /// `complete` and returns the next occurrence like it; cancelling needs
+    /// a reason and is left to `cancel`.
+    pub async fn set_status(
+        &mut self,
+        id: DataId,
+        status: Status,
+    ) -> anyhow::Result<Option<DataId>> {
+        match status {
+            Status::Done => return self.complete(id).await,
+            Status::Cancelled => {
+                return Err(anyhow!("cancelling needs a reason, use `todo cancel --reason`"))
+            }
+            _ => {}
+        }
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        if !front_matter.status.is_open() {
+            front_matter.reopen();
+        }
+        front_matter.status = status;
+        todo_file.write_file().await?;
+        Ok(None)
+    }
+
+    /// Marks the todo done. A recurring todo gets its next occurrence
+    /// created, whose id is returned.
+    pub async fn complete(&mut self, id: DataId) -> anyhow::Result<Option<DataId>> {
+        let display = self.config.ids.display(id);
+        let require_criteria = self.config.done.require_acceptance_criteria;
+        let todo_file = self.get(id)?;
+        let (_, checklists) = checklist::split(todo_file.data.body());
+        let front_matter = &todo_file.data.front_matter;
+        if !front_matter.status.is_open() {
+            return Err(anyhow!("todo {display} is already {}", front_matter.status.name()));
+        }
+        let rule = front_matter
+            .recurrence
+            .as_deref()
+            .map(recurrence::Rule::from_str)
+            .transpose()
+            .map_err(|err| anyhow!("todo {display} has an invalid recurrence: {err}"))?;
+        let unmet: Vec<&str> = checklists
+            .iter()
+            .filter(|checklist| checklist.is_acceptance_criteria())
+            .flat_map(|checklist| checklist.unchecked())
+            .map(|item| item.text.as_str())
+            .collect();
+        if require_criteria && !unmet.is_empty() {
+            return Err(anyhow!(
+                "todo {display} has unmet acceptance criteria: {}",
+                unmet.join("; ")
+            ));
+        }
+
+        // the next occurrence is written first, so that a failing template
+        // or rule leaves the todo open instead of silently ending the series
+        let next = match rule {
+            Some(rule) => Some(self.next_occurrence(id, &rule).await.map_err(|err| {
+                anyhow!("todo {display} stays open, its next occurrence can't be created: {err}")
+            })?),
+            None => None,
+        };
+        if let Some(next) = &next {
+            next.write_file().await?;
+        }
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        front_matter.status = Status::Done;
+        front_matter.completed_at = Some(Utc::now());
+        front_matter.stop_tracking(Utc::now());
+        if let Err(err) = todo_file.write_file().await {
+            if let Some(next) = &next {
+                tokio::fs::remove_file(next.path.as_path()).await?;
+            }
+            return Err(err);
+        }
+
+        let open: Vec<String> = self
+            .collection
+            .values()
+            .map(|todo| &todo.data.front_matter)
+            .filter(|child| child.parent == Some(id) && child.status.is_open())
+            .map(|child| self.config.ids.display(child.id))
+            .collect();
+        if !open.is_empty() {
+            eprintln!("warning: todo {display} still has open subtasks: {}", open.join(", "));
+        }
+        Ok(next.map(|next| {
+            let next_id = next.data.front_matter.id;
+            self.collection.insert(next_id, next);
+            next_id
+        }))
+    }
+
+    /// The occurrence of a recurring todo following its due date, or today
+    /// when it has none or is overdue, from the `task` template with the
+    /// title and tags carried over. It is not written yet.
+    async fn next_occurrence(
+        &self,
+        id: DataId,
+        rule: &recurrence::Rule,
+    ) -> anyhow::Result<TodoFile> {
+        let reservations = Reservations::load(&self.data_dir).await?;
+        let archived = archive::max_id(&self.data_dir).await?;
+        let next_id = self.next_data_id(&reservations, archived);
+
+        let todo = &self.get(id)?.data;
+        let front_matter = &todo.front_matter;
+        let now = Local::now();
+        let due = front_matter.due_at.map(|due| due.with_timezone(&Local));
+        let anchor = due.unwrap_or(now);
+        let after = due.filter(|due| *due > now).unwrap_or(now).date_naive();
+        let day = rule
+            .next_after(anchor.date_naive(), after)
+            .ok_or_else(|| {
+                let display = self.config.ids.display(id);
+                anyhow!("the recurrence of todo {display} has no further occurrence")
+            })?;
+        let due_at = match due {
+            Some(due) => day
+                .and_time(due.time())
+                .and_local_timezone(Local)
+                .latest()
+                .map(|at| at.with_timezone(&Utc))
+                .ok_or_else(|| anyhow!("{day} has no {} in the local time zone", due.time()))?,
+            None => dates::end_of_day(day)?,
+        };
+
+        let mut template_vars = TemplateVars::new(next_id);
+        template_vars.title = todo.title().map(str::to_string);
+        template_vars.tags = front_matter.tags.clone();
+        let mut next = self.new_todo_from_template("task", template_vars).await?;
+        next.data.front_matter.due_at = Some(due_at);
+        next.data.front_matter.priority = front_matter.priority;
+        next.data.front_matter.recurrence = front_matter.recurrence.clone();
+        next.data.front_matter.parent = front_matter.parent;
+        Ok(next)
+    }
+
+    /// Copies todo `id` under the next free id as a new open todo, titled
+    /// `title` if given. Content, tags, due date, priority, summary and
+    /// unknown fields are copied; tracked time and blockers are not, and
+    /// parent, recurrence and dependencies only when in `keep`.
+    pub async fn clone_todo(
+        &mut self,
+        id: DataId,
+        title: Option<&str>,
+        keep: &[CloneLink],
+    ) -> anyhow::Result<DataId> {
+        let reservations = Reservations::load(&self.data_dir).await?;
+        let archived = archive::max_id(&self.data_dir).await?;
+        let clone_id = self.next_data_id(&reservations, archived);
+
+        let mut data = self.get(id)?.data.clone();
+        data.front_matter.id = clone_id;
+        data.front_matter.created_at = Utc::now();
+        data.front_matter.reopen();
+        data.front_matter.time_entries.clear();
+        data.front_matter.blocked_by.clear();
+        if !keep.contains(&CloneLink::Parent) {
+            data.front_matter.parent = None;
+        }
+        if !keep.contains(&CloneLink::Recurrence) {
+            data.front_matter.recurrence = None;
+        }
+        if !keep.contains(&CloneLink::DependsOn) {
+            data.front_matter.depends_on.clear();
+        }
+        if let Some(title) = title {
+            let title = self
+                .config
+                .title
+                .normalize(title)?
+                .ok_or_else(|| anyhow!("the title of the copy cannot be empty"))?;
+            data.set_title(title.as_str());
+        }
+
+        let clone = TodoFile::new_from_data(self.tasks_dir.as_path(), &self.config.ids, data);
+        clone.write_file().await?;
+        self.collection.insert(clone_id, clone);
+        Ok(clone_id)
+    }
+
+    pub async fn cancel(
+        &mut self,
+        id: DataId,
+        reason: String,
+        superseded_by: Option<DataId>,
+    ) -> anyhow::Result<()> {
+        if let Some(other) = superseded_by {
+            if other == id {
+                return Err(anyhow!("a todo cannot supersede itself"));
+            }
+            self.get(other)?;
+        }
+
+        let display = self.config.ids.display(id);
+        let todo_file = self.get_mut(id)?;
+        let front_matter = &mut todo_file.data.front_matter;
+        if !front_matter.status.is_open() {
+            return Err(anyhow!("todo {display} is already {}", front_matter.status.name()));
+        }
+        front_matter.status = Status::Cancelled;
+        front_matter.stop_tracking(Utc::now());
+        front_matter.cancellation = Some(Cancellation {
+            at: Utc::now(),
+            reason,
+            superseded_by,
+        });
+        todo_file.write_file().await
+    }
+
+    /// Tags are free-form, so a typo silently creates a new one; point
+    /// out new tags that look like an existing one.
+    fn hint_similar_tags(&self, tags: &[String]) {
+        let mut known: Vec<&str> = self
+            .collection
+            .values()
+            .flat_map(|todo| todo.data.front_matter.tags.iter().map(String::as_str))
+            .collect();
+        known.sort_unstable();
+        known.dedup();
+
+        for tag in tags {
+            if known.contains(&tag.as_str()) {
+                continue;
+            }
+            if let Some(suggestion) = closest_match(tag, known.iter().copied()) {
+                eprintln!("note: '{tag}' is a new tag, did you mean '{suggestion}'?");
+            }
+        }
+    }
+
+    /// The id after the highest one in use, archived or reserved.
+    pub fn next_data_id(&self, reservations: &Reservations, archived: Option<DataId>) -> DataId {
+        self.collection
+            .keys()
+            .copied()
+            .chain(reservations.max())
+            .chain(archived)
+            .max()
+            .map_or_else(|| 1, |last| last + 1)
+    }
+
+    /// Parses `input` and takes it out of `reservations`, failing unless
+    /// it names a reserved id that no todo uses yet.
+    pub fn claim_reservation(
+        &self,
+        reservations: &mut Reservations,
+        input: &str,
+    ) -> anyhow::Result<DataId> {
+        let id = self.resolve_id(input)?;
+        let display = self.config.ids.display(id);
+        if self.collection.contains_key(&id) {
+            return Err(anyhow!("todo {display} already exists"));
+        }
+        if !reservations.consume(id) {
+            return Err(anyhow!(
+                "id {display} is not reserved, run `todo reserve` to hand one out"
+            ));
+        }
+        Ok(id)
+    }
+
+    pub async fn new_todo_from_template(
+        &self,
+        template: &str,
+        template_vars: TemplateVars,
+    ) -> anyhow::Result<TodoFile> {
+        Ok(TodoFile::new_from_data(
+            self.tasks_dir.as_path(),
+            &self.config.ids,
+            self.create_todo_data_from_template(template, template_vars)
+                .await?,
+        ))
+    }
+
+    fn ensure_template(&self, template: &str) -> anyhow::Result<()> {
+        if self.hbs.get_template(template).is_some() {
+            return Ok(());
+        }
+        let known = self.hbs.get_templates().keys().map(String::as_str);
+        Err(match closest_match(template, known) {
+            Some(suggestion) => {
+                anyhow!("template '{template}' does not exist, did you mean '{suggestion}'?")
+            }
+            None => anyhow!("template '{template}' does not exist"),
+        })
+    }
+
+    /// Renders `template` for each of `ids` and adds the front matter
+    /// fields it sets that the todo lacks; fields the todo already has and
+    /// the body are left alone. Only writes with `apply`. Returns the
+    /// fields added per todo, skipping todos that gain nothing.
+    pub async fn reapply_template(
+        &mut self,
+        template: &str,
+        ids: &[DataId],
+        apply: bool,
+    ) -> anyhow::Result<Vec<(DataId, toml::Table)>> {
+        self.ensure_template(template)?;
+
+        let mut changes = vec![];
+        for &id in ids {
+            let todo_file = self.get(id)?;
+            let template_vars = TemplateVars {
+                id,
+                created_at: todo_file.data.front_matter.created_at,
+                tags: todo_file.data.front_matter.tags.clone(),
+                title: todo_file.data.title().map(str::to_string),
+            };
+            let rendered = render_sandboxed(self.hbs.clone(), template, template_vars).await?;
+            let (style, defaults, _) = split_front_matter(rendered.as_str())?;
+            let defaults = style
+                .parse(defaults)
+                .map_err(|err| anyhow!("invalid template '{template}': {err}"))?;
+
+            let current = front_matter_table(&todo_file.data.front_matter)?;
+            let mut merged = current.clone();
+            for (key, value) in defaults {
+                merged.entry(key).or_insert(value);
+            }
+            let front_matter: FrontMatter = toml::Value::Table(merged).try_into()?;
+
+            // compare what would be written, so defaults that serialize
+            // away (like an open status) don't count as changes
+            let added: toml::Table = front_matter_table(&front_matter)?
+                .into_iter()
+                .filter(|(key, _)| !current.contains_key(key))
+                .collect();
+            if added.is_empty() {
+                continue;
+            }
+
+            if apply {
+                let todo_file = self.get_mut(id)?;
+                todo_file.data.front_matter = front_matter;
+                todo_file.write_file().await?;
+            }
+            changes.push((id, added));
+        }
+        Ok(changes)
+    }
+
+    async fn create_todo_data_from_template(
+        &self,
+        template: &str,
+        mut template_vars: TemplateVars,
+    ) -> anyhow::Result<TodoData> {
+        self.ensure_template(template)?;
+
+        if let Some(title) = template_vars.title.take() {
+            template_vars.title = self.config.title.normalize(title.as_str())?;
+        }
+        template_vars.tags = self.config.tags.normalize_all(template_vars.tags)?;
+        self.hint_similar_tags(&template_vars.tags);
+
+        let rendered = render_sandboxed(self.hbs.clone(), template, template_vars).await?;
+        let mut data = TodoData::from_str(rendered.as_str())
+            .map_err(|err| anyhow!("invalid template '{template}': {err:?}"))?;
+        // templates may use either style, new todos follow the setting
+        data.style = self.config.files.front_matter;
+        Ok(data)
+    }
+
+    /// Writes `count` synthetic todos with consecutive ids.
+    pub async fn generate(&self, count: usize, seed: u64) -> anyhow::Result<()> {
+        let reservations = Reservations::load(self.data_dir.as_path()).await?;
+        let archived = archive::max_id(self.data_dir.as_path()).await?;
+        let mut rng = generate::Rng::new(seed);
+        let today = Utc::now().date_naive();
+
+        let first = self.next_data_id(&reservations, archived);
+        for id in (first..).take(count) {
+            let mut data = generate::todo(&mut rng, id, today);
+            data.style = self.config.files.front_matter;
+            data.front_matter.tags = self.config.tags.normalize_all(data.front_matter.tags)?;
+            TodoFile::new_from_data(self.tasks_dir.as_path(), &self.config.ids, data)
+                .write_file()
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Writes one todo per draft, assigning consecutive ids.
+    pub async fn import_drafts(&self, drafts: Vec<trello::Draft>) -> anyhow::Result<Vec<TodoFile>> {
+        let reservations = Reservations::load(self.data_dir.as_path()).await?;
+        let archived = archive::max_id(self.data_dir.as_path()).await?;
+        let mut imported = vec![];
+
+        for (id, draft) in (self.next_data_id(&reservations, archived)..).zip(drafts) {
+            let title = self
+                .config
+                .title
+                .normalize(draft.title.as_str())?
+                .unwrap_or(draft.title);
+            let mut content = format!("\n# {title}\n\n");
+            if !draft.body.is_empty() {
+                content.push_str(draft.body.as_str());
+                content.push('\n');
+            }
+
+            let todo_file = TodoFile::new_from_data(
+                self.tasks_dir.as_path(),
+                &self.config.ids,
+                TodoData {
+                    front_matter: FrontMatter {
+                        id,
+                        created_at: Utc::now(),
+                        due_at: draft.due_at,
+                        tags: self.config.tags.normalize_all(draft.tags)?,
+                        priority: None,
+                        recurrence: None,
+                        parent: None,
+                        depends_on: vec![],
+                        blocked_by: vec![],
+                        summary: None,
+                        status: Status::Open,
+                        completed_at: None,
+                        cancellation: None,
+                        time_entries: vec![],
+                        extra: toml::Table::new(),
+                    },
+                    content,
+                    style: self.config.files.front_matter,
+                },
+            );
+            todo_file.write_file().await?;
+            imported.push(todo_file);
+        }
+
+        Ok(imported)
+    }
+
+    pub async fn is_initialized(&self) -> anyhow::Result<bool> {
+        if !tokio::fs::try_exists(self.tasks_dir.as_path()).await? {
+            return Ok(false);
+        }
+        if !tokio::fs::try_exists(self.templates_dir.as_path()).await? {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    pub async fn init(&self) -> anyhow::Result<()> {
+        if self.is_initialized().await? {
+            return Err(anyhow!("directories tasks and/or templates already exists"));
+        }
+
+        tokio::fs::create_dir_all(self.tasks_dir.as_path()).await?;
+        tokio::fs::create_dir_all(self.templates_dir.as_path()).await?;
+        tokio::fs::write(
+            self.templates_dir.join("task.md.hbs").as_path(),
+            TASK_TEMPLATE,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct TodoFile {
+    path: PathBuf,
+    data: TodoData,
+}
+
+impl TodoFile {
+    pub async fn load_file(path: &Path) -> anyhow::Result<Self> {
+        let content = tokio::fs::read_to_string(path).await?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            data: TodoData::from_str(content.as_str())?,
+        })
+    }
+
+    pub async fn write_file(&self) -> anyhow::Result<()> {
+        tokio::fs::write(self.path.as_path(), self.data.to_bytes()).await?;
+        Ok(())
+    }
+
+    fn gen_filepath(tasks_dir: &Path, ids: &IdFormat, id: DataId) -> PathBuf {
+        tasks_dir.join(format!("{}.todo.md", ids.file_stem(id)))
+    }
+
+    pub fn new_from_data(tasks_dir: &Path, ids: &IdFormat, todo_data: TodoData) -> Self {
+        Self {
+            path: Self::gen_filepath(tasks_dir, ids, todo_data.front_matter.id),
+            data: todo_data,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TodoData {
+    front_matter: FrontMatter,
+    content: String,
+    /// How the file fences its front matter, kept when it is rewritten.
+    style: FrontMatterStyle,
+}
+
+impl TodoData {
+    /// The first top-level heading of the content.
+    pub fn title(&self) -> Option<&str> {
+        self.content
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(str::trim)
+    }
+
+    /// Replaces the first top-level heading, or adds one before the
+    /// content.
+    pub fn set_title(&mut self, title: &str) {
+        let mut offset = 0;
+        for line in self.content.split_inclusive('\n') {
+            if line.starts_with("# ") {
+                let heading = line.trim_end_matches(['\r', '\n']).len();
+                self.content
+                    .replace_range(offset..offset + heading, format!("# {title}").as_str());
+                return;
+            }
+            offset += line.len();
+        }
+        self.content = format!("\n# {title}\n{}", self.content);
+    }
+
+    /// The content without a leading title heading.
+    pub fn body(&self) -> &str {
+        let mut offset = 0;
+        for line in self.content.split_inclusive('\n') {
+            if line.starts_with("# ") {
+                return self.content[offset + line.len()..].trim_start_matches('\n');
+            }
+            if !line.trim().is_empty() {
+                break;
+            }
+            offset += line.len();
+        }
+        self.content.as_str()
+    }
+
+    /// The `summary` field, or else the first line of body text.
+    pub fn excerpt(&self) -> Option<&str> {
+        if let Some(summary) = &self.front_matter.summary {
+            return Some(summary.trim());
+        }
+        self.body()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("<!--"))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = BufWriter::new(Vec::new());
+
+        let front_matter = match self.style {
+            FrontMatterStyle::Toml => toml::to_string(&self.front_matter).unwrap(),
+            FrontMatterStyle::Yaml => {
+                yaml::to_string(&front_matter_table(&self.front_matter).unwrap())
+            }
+        };
+        writeln!(&mut buf, "{}", self.style.fence()).unwrap();
+        writeln!(&mut buf, "{front_matter}").unwrap();
+        writeln!(&mut buf, "{}", self.style.fence()).unwrap();
+        write!(&mut buf, "{}", self.content).unwrap();
+
+        buf.into_inner().unwrap()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct FrontMatter {
+    id: DataId,
+    created_at: chrono::DateTime<chrono::Utc>,
+    due_at: Option<chrono::DateTime<chrono::Utc>>,
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Priority>,
+    /// An RRULE like `FREQ=WEEKLY;BYDAY=MO`; completing the todo creates
+    /// its next occurrence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    recurrence: Option<String>,
+    /// The todo this one is a subtask of.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<DataId>,
+    /// Todos that have to be closed before work on this one can start.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<DataId>,
+    /// What the todo waits for, cleared blockers included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocked_by: Vec<Blocker>,
+    /// One-line description shown instead of the body's first line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Status::is_default")]
+    status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cancellation: Option<Cancellation>,
+    /// Time tracked with `start` and `stop`, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    time_entries: Vec<TimeEntry>,
+    /// Fields this version doesn't know, kept so rewriting a file
+    /// doesn't drop them.
+    #[serde(flatten)]
+    extra: toml::Table,
+}
+
+/// How todo files fence and write their front matter.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum FrontMatterStyle {
+    /// TOML between `+++` lines.
+    #[default]
+    Toml,
+    /// YAML between `---` lines.
+    Yaml,
+}
+
+impl FrontMatterStyle {
+    fn name(self) -> &'static str {
+        match self {
+            FrontMatterStyle::Toml => "toml",
+            FrontMatterStyle::Yaml => "yaml",
+        }
+    }
+
+    fn fence(self) -> &'static str {
+        match self {
+            FrontMatterStyle::Toml => "+++",
+            FrontMatterStyle::Yaml => "---",
+        }
+    }
+
+    /// The fields of front matter written in this style.
+    fn parse(self, front_matter: &str) -> anyhow::Result<toml::Table> {
+        Ok(match self {
+            FrontMatterStyle::Toml => toml::from_str(front_matter)?,
+            FrontMatterStyle::Yaml => yaml::from_str(front_matter)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+enum Status {
+    #[default]
+    Open,
+    /// Being worked on.
+    InProgress,
+    /// Waiting on something before work can go on.
+    Blocked,
+    Done,
+    /// Dropped without being done; not counted as completed.
+    Cancelled,
+}
+
+impl FrontMatter {
+    fn reopen(&mut self) {
+        self.status = Status::Open;
+        self.completed_at = None;
+        self.cancellation = None;
+    }
+
+    /// Ends a running time entry, if any.
+    fn stop_tracking(&mut self, now: chrono::DateTime<Utc>) {
+        if let Some(entry) = self.time_entries.last_mut().filter(|entry| entry.end.is_none()) {
+            entry.end = Some(now);
+        }
+    }
+
+    /// When the todo was completed or cancelled.
+    fn closed_at(&self) -> Option<chrono::DateTime<Utc>> {
+        match self.status {
+            Status::Open | Status::InProgress | Status::Blocked => None,
+            Status::Done => self.completed_at,
+            Status::Cancelled => self.cancellation.as_ref().map(|cancellation| cancellation.at),
+        }
+    }
+}
+
+impl Status {
+    /// Not done or cancelled yet, whether or not work has started.
+    fn is_open(&self) -> bool {
+        !matches!(self, Status::Done | Status::Cancelled)
+    }
+
+    fn is_default(&self) -> bool {
+        *self == Status::Open
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Status::Open => "open",
+            Status::InProgress => "in-progress",
+            Status::Blocked => "blocked",
+            Status::Done => "done",
+            Status::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Links `clone` leaves out unless asked to keep them, as they would make
+/// the copy a subtask, make it recur or put it into a dependency chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CloneLink {
+    Parent,
+    Recurrence,
+    DependsOn,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    /// The next lower priority, if there is one.
+    fn lower(&self) -> Option<Priority> {
+        match self {
+            Priority::High => Some(Priority::Medium),
+            Priority::Medium => Some(Priority::Low),
+            Priority::Low => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// A stretch of time spent on a todo, still running without an end.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct TimeEntry {
+    start: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Something a todo waits for, from when on and, once it no longer
+/// blocks, until when.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Blocker {
+    on: BlockedOn,
+    since: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cleared: Option<Clearance>,
+}
+
+/// A todo of the collection, or anything else: a URL, a person, "waiting
+/// for the vendor".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+enum BlockedOn {
+    Todo(DataId),
+    External(String),
+}
+
+/// When and why a blocker was cleared.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Clearance {
+    at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+impl Blocker {
+    fn is_active(&self) -> bool {
+        self.cleared.is_none()
+    }
+}
+
+/// Why and when a todo was cancelled.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Cancellation {
+    at: chrono::DateTime<chrono::Utc>,
+    reason: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    superseded_by: Option<DataId>,
+}
+
+type DataId = u32;
+/// Todos by id, iterated in id order.
+type Collection = BTreeMap<DataId, TodoFile>;
+
+async fn load_collection(data_dir: &Path) -> anyhow::Result<Collection> {
+    let mut cur_dir = tokio::fs::read_dir(data_dir.join("tasks")).await?;
+    let mut connection = Collection::new();
+
+    while let Some(entry) = cur_dir.next_entry().await? {
+        if !entry.file_type().await?.is_file() {
+            continue;
+        }
+
+        if !entry.path().extension().is_some_and(|ext| ext.eq("md")) {
+            continue;
+        }
+
+        if let Ok(file) = TodoFile::load_file(entry.path().as_path()).await {
+            if connection.insert(file.data.front_matter.id, file).is_some() {
+                return Err(anyhow!("duplicate content id"));
             }
-
-            println!(
-                "{} {}",
-                todo_file
-                    .path
-                    .strip_prefix(proc.data_dir)
-                    .unwrap()
-                    .to_str()
-                    .unwrap(),
-                todo_file.path.file_name().unwrap().to_str().unwrap()
-            )
         }
+    }
 
-        Some(Commands::Init) => {
-            let proc = CommandProcessor::new(
-                Handlebars::new(),
-                Collection::new(),
-                current_dir,
-            );
+    Ok(connection)
+}
 
-            if let Err(err) = proc.init().await {
-                failure(err);
+/// `load_collection`, plus the archived todos with `include_archived`.
+async fn load_collection_with_archive(
+    data_dir: &Path,
+    include_archived: bool,
+) -> anyhow::Result<Collection> {
+    let mut collection = load_collection(data_dir).await?;
+    if include_archived {
+        for (id, file) in archive::load(data_dir).await? {
+            if collection.insert(id, file).is_some() {
+                return Err(anyhow!("duplicate content id"));
             }
         }
     }
+    Ok(collection)
 }
 
-fn failure(err: impl Display) -> ! {
-    eprintln!("Error: {err}");
-    std::process::exit(1);
+impl FromStr for TodoData {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (style, front_matter, content) = split_front_matter(s)?;
+        let front_matter = match style {
+            FrontMatterStyle::Toml => toml::from_str(front_matter)?,
+            // the message alone, as positions point into the TOML in between
+            FrontMatterStyle::Yaml => front_matter_from_table(&yaml::from_str(front_matter)?)
+                .map_err(|err| anyhow!(err.message().to_string()))?,
+        };
+        let data = TodoData {
+            front_matter,
+            content: content.to_string(),
+            style,
+        };
+
+        Ok(data)
+    }
 }
 
-#[derive(Parser)]
-#[command(version, about, long_about = None)]
-#[command(arg_required_else_help = true)]
-struct Cli {
-    #[arg(long)]
-    data_dir: Option<PathBuf>,
+fn front_matter_table(front_matter: &FrontMatter) -> anyhow::Result<toml::Table> {
+    // through TOML text, as datetimes in `extra` turn into tables when
+    // serialized to a value through the flattening
+    Ok(toml::from_str(toml::to_string(front_matter)?.as_str())?)
+}
 
-    #[command(subcommand)]
-    command: Option<Commands>,
+/// The fields holding the todo's own dates, stepping into tables and
+/// into every item of a list.
+const DATE_FIELDS: [&[&str]; 8] = [
+    &["created_at"],
+    &["due_at"],
+    &["completed_at"],
+    &["cancellation", "at"],
+    &["blocked_by", "since"],
+    &["blocked_by", "cleared", "at"],
+    &["time_entries", "start"],
+    &["time_entries", "end"],
+];
+
+/// Whether the field at `path` is a plain datetime rather than the
+/// RFC 3339 string the todo's own dates are.
+fn unquoted_date(table: &toml::Table, path: &[&str]) -> bool {
+    let Some((field, rest)) = path.split_first() else {
+        return false;
+    };
+    match (table.get(*field), rest) {
+        (Some(toml::Value::Datetime(_)), []) => true,
+        (Some(toml::Value::Table(table)), _) => unquoted_date(table, rest),
+        (Some(toml::Value::Array(items)), _) => items
+            .iter()
+            .any(|item| matches!(item, toml::Value::Table(item) if unquoted_date(item, rest))),
+        _ => false,
+    }
 }
 
-#[derive(Subcommand)]
-enum Commands {
-    /// does testing things
-    New {
-        /// template to use
-        #[arg(long)]
-        template: Option<String>,
+/// Front matter from its fields, through TOML text for the same reason.
+fn front_matter_from_table(table: &toml::Table) -> Result<FrontMatter, toml::de::Error> {
+    if let Some(path) = DATE_FIELDS.iter().find(|path| unquoted_date(table, path)) {
+        return Err(serde::de::Error::custom(format!(
+            "{} must be a quoted string, like \"2025-01-31T09:00:00Z\"",
+            path.join(".")
+        )));
+    }
+    let text = toml::to_string(table).map_err(serde::de::Error::custom)?;
+    toml::from_str(text.as_str())
+}
 
-        /// title
-        #[arg(long)]
-        title: Option<String>,
+/// Splits a todo file into its front matter, in the style its first line
+/// fences it with, and markdown content.
+fn split_front_matter(s: &str) -> anyhow::Result<(FrontMatterStyle, &str, &str)> {
+    let style = match s.starts_with("---\n") {
+        true => FrontMatterStyle::Yaml,
+        false => FrontMatterStyle::Toml,
+    };
+    let fence = format!("{}\n", style.fence());
+    let parts: Vec<_> = s.splitn(3, fence.as_str()).collect();
+    match parts[..] {
+        [_, front_matter, content] => Ok((style, front_matter, content)),
+        _ => Err(anyhow!("missing +++ or --- front matter fences")),
+    }
+}
 
-        /// tags
-        #[arg(long = "tag", short)]
-        tags: Vec<String>,
-    },
-    /// Initialize directory for todo
-    Init,
+/// Returns the known name closest to `name` by edit distance, counting
+/// transpositions as one edit, if it is close enough to be a typo.
+fn closest_match<'a>(name: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let threshold = (name.chars().count() / 3).max(1);
+    known
+        .map(|candidate| (strsim::osa_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min()
+        .map(|(_, candidate)| candidate)
 }
 
-struct CommandProcessor<'a> {
-    data_dir: PathBuf,
-    tasks_dir: PathBuf,
-    templates_dir: PathBuf,
-    hbs: Handlebars<'a>,
-    collection: Collection,
+/// Values of the given kind starting with `prefix`, ignoring case. Tags
+/// are ordered by how many todos use them, titles alphabetically.
+fn suggestions<'a>(collection: &'a Collection, kind: SuggestKind, prefix: &str) -> Vec<&'a str> {
+    let prefix = prefix.to_lowercase();
+    let matches = |value: &&str| value.to_lowercase().starts_with(prefix.as_str());
+
+    match kind {
+        SuggestKind::Title => {
+            let mut titles: Vec<_> = collection
+                .values()
+                .filter_map(|todo| todo.data.title())
+                .filter(matches)
+                .collect();
+            titles.sort_unstable();
+            titles.dedup();
+            titles
+        }
+        SuggestKind::Tag => tag_counts(collection)
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .filter(matches)
+            .collect(),
+    }
 }
 
-impl<'a> CommandProcessor<'a> {
-    pub fn new(
-        hbs: Handlebars<'a>,
-        collection: Collection,
-        data_dir: PathBuf,
-    ) -> CommandProcessor<'a> {
-        let tasks_dir = data_dir.join("tasks");
-        let templates_dir = data_dir.join("templates");
-        Self {
-            hbs,
-            collection,
-            data_dir,
-            tasks_dir,
-            templates_dir,
+/// Every tag in use with the number of todos carrying it, most used
+/// first.
+fn tag_counts(collection: &Collection) -> Vec<(&str, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tag in collection
+        .values()
+        .flat_map(|todo| todo.data.front_matter.tags.iter().map(String::as_str))
+    {
+        *counts.entry(tag).or_default() += 1;
+    }
+    let mut tags: Vec<_> = counts.into_iter().collect();
+    tags.sort_unstable_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    tags
+}
+
+/// Longest excerpt `list --excerpt` shows, in characters.
+const MAX_EXCERPT_LENGTH: usize = 50;
+
+/// Dims and strikes through the lines of a rendered table whose rows are
+/// `faded`, for todos that are done but still shown.
+fn fade_rows(table: &str, faded: &[bool]) -> String {
+    let mut out = String::new();
+    for (i, line) in table.lines().enumerate() {
+        // the first line holds the headers
+        match i.checked_sub(1).is_some_and(|row| faded.get(row) == Some(&true)) {
+            true => out.push_str(&format!("\x1b[2;9m{line}\x1b[0m\n")),
+            false => out.push_str(&format!("{line}\n")),
         }
     }
+    out
 }
-impl CommandProcessor<'_> {
-    pub fn next_data_id(&self) -> DataId {
-        self.collection
-            .keys()
-            .max()
-            .map_or_else(|| 1, |last| last + 1)
+
+/// Cuts `text` to at most `max` characters, marking the cut with `…`.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
     }
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
 
-    pub fn new_todo_from_template(
-        &self,
-        template: &str,
-        template_vars: TemplateVars,
-    ) -> anyhow::Result<TodoFile> {
-        Ok(TodoFile::new_from_data(
-            self.create_todo_data_from_template(template, template_vars)?,
-        ))
+/// Asks on stderr whether to go ahead with `action`; anything but an
+/// answer starting with `y` declines, and so does closed input.
+fn confirm(action: &str) -> bool {
+    eprint!("{action}? [y/N] ");
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            eprintln!();
+            false
+        }
+        Ok(_) => answer.trim_start().to_lowercase().starts_with('y'),
     }
+}
 
-    fn create_todo_data_from_template(
-        &self,
-        template: &str,
-        template_vars: TemplateVars,
-    ) -> anyhow::Result<TodoData> {
-        let foo = self.hbs.render(template, &template_vars)?;
-        Ok(TodoData::from_str(foo.as_str())
-            .map_err(|err| anyhow!("invalid template '{template}': {err:?}"))?)
+/// Asks on stderr for `field`, offering `default`; an empty answer and
+/// closed input keep the default.
+fn ask(field: &str, default: &str) -> String {
+    eprint!("{field} [{default}]: ");
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => {
+            eprintln!();
+            default.to_string()
+        }
+        Ok(_) if answer.trim().is_empty() => default.to_string(),
+        Ok(_) => answer.trim().to_string(),
     }
+}
 
-    pub async fn is_initialized(&self) -> anyhow::Result<bool> {
-        if !tokio::fs::try_exists(self.tasks_dir.as_path()).await? {
-            return Ok(false);
+/// The comma-separated tags of an answer to `new -i`, normalized like
+/// the tags of every other todo.
+fn parse_tags(answer: &str, rules: &config::TagRules) -> anyhow::Result<Vec<String>> {
+    let tags = match answer {
+        "none" => vec![],
+        tags => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+    };
+    rules.normalize_all(tags)
+}
+
+/// What `new -i` asked for.
+struct NewFields {
+    title: String,
+    tags: Vec<String>,
+    due_at: Option<chrono::DateTime<Utc>>,
+    priority: Option<Priority>,
+}
+
+/// Asks for the fields of a new todo, offering those of `todo` as
+/// defaults. `none` clears tags, due date and priority; answers that
+/// don't parse or break the tag policy are asked again.
+fn ask_new_fields(todo: &TodoData, rules: &config::TagRules) -> NewFields {
+    let front_matter = &todo.front_matter;
+    let title = ask("title", todo.title().unwrap_or("Title"));
+
+    let tags = match front_matter.tags.is_empty() {
+        true => "none".to_string(),
+        false => front_matter.tags.join(", "),
+    };
+    let tags = loop {
+        match parse_tags(ask("tags", tags.as_str()).as_str(), rules) {
+            Ok(tags) => break tags,
+            Err(err) => eprintln!("Error: {err}"),
         }
-        if !tokio::fs::try_exists(self.templates_dir.as_path()).await? {
-            return Ok(false);
+    };
+
+    let due = format_due(front_matter.due_at);
+    let due_at = loop {
+        match ask("due", due.as_str()).as_str() {
+            "none" => break None,
+            answer if answer == due => break front_matter.due_at,
+            answer => match dates::parse(answer, Local::now()) {
+                Ok(due_at) => break Some(due_at),
+                Err(err) => eprintln!("Error: {err}"),
+            },
         }
-        Ok(true)
+    };
+
+    let priority = front_matter
+        .priority
+        .and_then(|priority| priority.to_possible_value())
+        .map_or("none".to_string(), |value| value.get_name().to_string());
+    let priority = loop {
+        match ask("priority, high, medium or low", priority.as_str()).as_str() {
+            "none" => break None,
+            answer => match Priority::from_str(answer, true) {
+                Ok(priority) => break Some(priority),
+                Err(err) => eprintln!("Error: {err}"),
+            },
+        }
+    };
+
+    NewFields {
+        title,
+        tags,
+        due_at,
+        priority,
     }
+}
 
-    pub async fn init(&self) -> anyhow::Result<()> {
-        if self.is_initialized().await? {
-            return Err(anyhow!("directories tasks and/or templates already exists"));
+/// Orders todos so subtasks follow their parent, each with its depth.
+/// Todos whose parent is not among them are roots; the order among
+/// siblings is kept.
+fn subtask_tree(todos: Vec<&TodoFile>) -> Vec<(usize, &TodoFile)> {
+    let listed = |id: DataId| todos.iter().any(|todo| todo.data.front_matter.id == id);
+    let mut tree = vec![];
+    let mut pending: Vec<(usize, &TodoFile)> = todos
+        .iter()
+        .rev()
+        .filter(|todo| !todo.data.front_matter.parent.is_some_and(listed))
+        .map(|todo| (0, *todo))
+        .collect();
+    while let Some((depth, todo)) = pending.pop() {
+        let id = todo.data.front_matter.id;
+        tree.push((depth, todo));
+        for child in todos.iter().rev() {
+            if child.data.front_matter.parent == Some(id) {
+                pending.push((depth + 1, *child));
+            }
         }
+    }
+    // subtasks in a cycle have no root to hang below
+    for todo in &todos {
+        if !tree.iter().any(|(_, known)| known.data.front_matter.id == todo.data.front_matter.id) {
+            tree.push((0, *todo));
+        }
+    }
+    tree
+}
 
-        tokio::fs::create_dir_all(self.tasks_dir.as_path()).await?;
-        tokio::fs::create_dir_all(self.templates_dir.as_path()).await?;
-        tokio::fs::write(
-            self.templates_dir.join("task.md.hbs").as_path(),
-            TASK_TEMPLATE,
-        )
-        .await?;
+/// Reports the occurrence `next` created by completing `id`.
+fn print_next_occurrence(proc: &CommandProcessor, id: DataId, next: DataId, output: OutputMode) {
+    let due_at = proc.get(next).ok().and_then(|todo| todo.data.front_matter.due_at);
+    match output {
+        OutputMode::Human => println!(
+            "created todo {} as the next occurrence, due {}",
+            proc.config.ids.display(next),
+            format_due(due_at)
+        ),
+        OutputMode::Quiet => {}
+        OutputMode::Porcelain => println!("{}", porcelain::Record::Recurred { id, next, due_at }),
+    }
+}
 
-        Ok(())
+/// Prints task-level changes, as found by `git::diff`, one per line.
+fn print_changes(changes: &[git::Change], ids: &IdFormat, output: OutputMode) {
+    for change in changes {
+        let todo = change.todo();
+        let id = todo.front_matter.id;
+        match output {
+            OutputMode::Human => {
+                let mut line = format!(
+                    "{:<11} {:>4} {}",
+                    change.kind(),
+                    ids.display(id),
+                    todo.title().unwrap_or("-")
+                );
+                match change {
+                    git::Change::Rescheduled(before, after) => line.push_str(&format!(
+                        " ({} -> {})",
+                        format_due(before.front_matter.due_at),
+                        format_due(after.front_matter.due_at)
+                    )),
+                    git::Change::Edited(before, after) => {
+                        let parts = git::edited_parts(before, after);
+                        if !parts.is_empty() {
+                            line.push_str(&format!(" ({})", parts.join(", ")));
+                        }
+                    }
+                    _ => {}
+                }
+                println!("{line}");
+            }
+            OutputMode::Quiet => println!("{id}"),
+            OutputMode::Porcelain => {
+                println!("{}", porcelain::line(&[change.kind(), &id.to_string()]))
+            }
+        }
     }
 }
 
-#[derive(Debug)]
-struct TodoFile {
-    path: PathBuf,
-    data: TodoData,
+/// Due dates are shown as the local day, matching how `dates::parse`
+/// reads plain dates.
+fn format_due(due_at: Option<chrono::DateTime<Utc>>) -> String {
+    due_at.map_or("none".to_string(), |due| {
+        due.with_timezone(&Local).format("%Y-%m-%d").to_string()
+    })
 }
 
-impl TodoFile {
-    pub async fn load_file(path: &Path) -> anyhow::Result<Self> {
-        let content = tokio::fs::read_to_string(path).await?;
+/// Clipboard tools tried in order; the first one installed wins.
+const CLIPBOARD_COMMANDS: &[&[&str]] = &[
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip.exe"],
+];
 
-        Ok(Self {
-            path: path.to_path_buf(),
-            data: TodoData::from_str(content.as_str())?,
-        })
+async fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    for command in CLIPBOARD_COMMANDS {
+        let child = tokio::process::Command::new(command[0])
+            .args(&command[1..])
+            .stdin(std::process::Stdio::piped())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut stdin = child.stdin.take().unwrap();
+        stdin.write_all(text.as_bytes()).await?;
+        drop(stdin);
+
+        let status = child.wait().await?;
+        if !status.success() {
+            return Err(anyhow!("{} exited with {status}", command[0]));
+        }
+        return Ok(());
     }
 
-    pub async fn write_file(&self) -> anyhow::Result<()> {
-        tokio::fs::write(self.path.as_path(), self.data.to_bytes()).await?;
-        Ok(())
+    Err(anyhow!(
+        "no clipboard tool found, install one of pbcopy, wl-copy, xclip or xsel"
+    ))
+}
+
+fn init_hbs(data_dir: &Path) -> anyhow::Result<Handlebars<'static>> {
+    let mut options = DirectorySourceOptions::default();
+    options.tpl_extension = ".md.hbs".to_string();
+    options.temporary = false;
+
+    let templates_dir = data_dir.join("templates");
+    reject_foreign_templates(&templates_dir)?;
+
+    let mut hbs = Handlebars::new();
+    // templates produce TOML front matter and markdown, not HTML
+    hbs.register_escape_fn(escape_toml);
+    hbs.register_templates_directory(&templates_dir, options)?;
+
+    // synced templates answer to their bare name unless a local one exists
+    let prefix = format!("{}/", sync::MANAGED_DIR);
+    let managed: Vec<_> = hbs
+        .get_templates()
+        .iter()
+        .filter_map(|(name, template)| Some((name.strip_prefix(prefix.as_str())?, template)))
+        .filter(|(name, _)| hbs.get_template(name).is_none())
+        .map(|(name, template)| (name.to_string(), template.clone()))
+        .collect();
+    for (name, template) in managed {
+        hbs.register_template(name.as_str(), template);
     }
 
-    fn gen_filepath(id: DataId) -> PathBuf {
-        Path::new(std::env::current_dir().unwrap().as_path())
-            .join("tasks")
-            .join(format!("{:010}.todo.md", id))
+    // in name order, so a broken template set always reports the same error
+    let templates: BTreeMap<_, _> = hbs.get_templates().iter().collect();
+    for (name, template) in templates {
+        check_helpers(name, template)?;
+        partial_depth(&hbs, name, &mut vec![])?;
     }
 
-    pub fn new_from_data(todo_data: TodoData) -> Self {
-        Self {
-            path: Self::gen_filepath(todo_data.front_matter.id),
-            data: todo_data,
+    Ok(hbs)
+}
+
+/// Escapes `{{ }}` output for a TOML basic string, so values can't end
+/// the string or add keys to the front matter. Markdown uses `{{{ }}}`,
+/// which leaves text as it is.
+fn escape_toml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if ch.is_control() => escaped.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => escaped.push(ch),
         }
     }
+    escaped
 }
 
-#[derive(Debug)]
-struct TodoData {
-    front_matter: FrontMatter,
-    content: String,
-}
+/// Upper bound for the size of a rendered template, in bytes.
+const MAX_RENDER_SIZE: usize = 64 * 1024;
 
-impl TodoData {
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = BufWriter::new(Vec::new());
+/// Upper bound for how deeply partials may include each other.
+const MAX_PARTIAL_DEPTH: usize = 8;
 
-        write!(&mut buf, "+++\n").unwrap();
-        write!(
-            &mut buf,
-            "{}\n",
-            toml::to_string(&self.front_matter).unwrap()
-        )
-        .unwrap();
-        write!(&mut buf, "+++\n").unwrap();
-        write!(&mut buf, "{}", self.content).unwrap();
+/// How long a single template may take to render.
+const RENDER_TIMEOUT: Duration = Duration::from_secs(2);
 
-        buf.into_inner().unwrap()
+/// The helpers handlebars brings along, the only ones templates may call.
+/// None of them reads files or runs code.
+const TEMPLATE_HELPERS: [&str; 17] = [
+    "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+    "and", "or", "not", "len",
+];
+
+/// Refuses to register templates that are symlinks resolving outside the
+/// templates directory, so a shared template set can't pull arbitrary
+/// files from disk into a todo.
+fn reject_foreign_templates(templates_dir: &Path) -> anyhow::Result<()> {
+    let Ok(root) = templates_dir.canonicalize() else {
+        return Ok(());
+    };
+
+    let mut pending = vec![root.clone()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let resolved = path.canonicalize()?;
+            if !resolved.starts_with(&root) {
+                return Err(anyhow!(
+                    "template '{}' points outside of the templates directory",
+                    path.display()
+                ));
+            }
+            if path.is_dir() && !path.is_symlink() {
+                pending.push(path);
+            }
+        }
     }
-}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct FrontMatter {
-    id: DataId,
-    created_at: chrono::DateTime<chrono::Utc>,
-    due_at: Option<chrono::DateTime<chrono::Utc>>,
-    tags: Vec<String>,
+    Ok(())
 }
 
-type DataId = u32;
-type Collection = HashMap<DataId, TodoFile>;
+/// Returns how deeply the named template nests partials, failing on
+/// cycles, dynamic partial names, and nesting beyond `MAX_PARTIAL_DEPTH`.
+fn partial_depth(hbs: &Handlebars, name: &str, stack: &mut Vec<String>) -> anyhow::Result<usize> {
+    if stack.iter().any(|visited| visited == name) {
+        return Err(anyhow!(
+            "template '{}' includes itself via {} -> {name}",
+            stack[0],
+            stack.join(" -> ")
+        ));
+    }
+    if stack.len() >= MAX_PARTIAL_DEPTH {
+        return Err(anyhow!(
+            "template '{}' nests partials deeper than {MAX_PARTIAL_DEPTH} levels",
+            stack[0]
+        ));
+    }
+    let Some(template) = hbs.get_template(name) else {
+        // inline partials and `@partial-block` aren't registered templates
+        return Ok(0);
+    };
 
-async fn load_collection() -> anyhow::Result<Collection> {
-    let mut cur_dir = tokio::fs::read_dir(std::env::current_dir()?.join("tasks")).await?;
-    let mut connection = Collection::new();
+    let mut partials = vec![];
+    collect_partials(template, &mut partials)?;
 
-    while let Some(entry) = cur_dir.next_entry().await? {
-        if !entry.file_type().await?.is_file() {
-            continue;
+    stack.push(name.to_string());
+    let mut depth = 0;
+    for partial in partials {
+        depth = depth.max(1 + partial_depth(hbs, partial.as_str(), stack)?);
+    }
+    stack.pop();
+
+    Ok(depth)
+}
+
+fn collect_partials(template: &Template, partials: &mut Vec<String>) -> anyhow::Result<()> {
+    for element in &template.elements {
+        match element {
+            TemplateElement::PartialExpression(decorator)
+            | TemplateElement::PartialBlock(decorator) => {
+                let name = decorator.name.as_name().ok_or_else(|| {
+                    anyhow!(
+                        "template '{}' uses a dynamic partial name",
+                        template.name.as_deref().unwrap_or_default()
+                    )
+                })?;
+                partials.push(name.to_string());
+                if let Some(inner) = &decorator.template {
+                    collect_partials(inner, partials)?;
+                }
+            }
+            TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(inner) = &decorator.template {
+                    collect_partials(inner, partials)?;
+                }
+            }
+            TemplateElement::HelperBlock(helper)
+            | TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper) => {
+                for inner in [&helper.template, &helper.inverse].into_iter().flatten() {
+                    collect_partials(inner, partials)?;
+                }
+            }
+            _ => {}
         }
+    }
+    Ok(())
+}
 
-        if !entry.path().extension().is_some_and(|ext| ext.eq("md")) {
-            continue;
+/// Fails on calls to helpers other than `TEMPLATE_HELPERS` in the named
+/// template, which handlebars would only report once a todo is rendered.
+fn check_helpers(name: &str, template: &Template) -> anyhow::Result<()> {
+    for element in &template.elements {
+        match element {
+            TemplateElement::HelperBlock(helper)
+            | TemplateElement::Expression(helper)
+            | TemplateElement::HtmlExpression(helper) => check_helper_call(name, helper)?,
+            TemplateElement::PartialExpression(decorator)
+            | TemplateElement::PartialBlock(decorator)
+            | TemplateElement::DecoratorExpression(decorator)
+            | TemplateElement::DecoratorBlock(decorator) => {
+                if let Some(inner) = &decorator.template {
+                    check_helpers(name, inner)?;
+                }
+            }
+            _ => {}
         }
+    }
+    Ok(())
+}
 
-        if let Ok(file) = TodoFile::load_file(entry.path().as_path()).await {
-            if connection.insert(file.data.front_matter.id, file).is_some() {
-                return Err(anyhow!("duplicate content id"));
+fn check_helper_call(name: &str, helper: &HelperTemplate) -> anyhow::Result<()> {
+    let is_call = helper.block || !helper.params.is_empty() || !helper.hash.is_empty();
+    if let Some(called) = helper.name.as_name().filter(|_| is_call) {
+        if !TEMPLATE_HELPERS.contains(&called) {
+            return Err(anyhow!(
+                "template '{name}' calls the helper '{called}', only {} are available",
+                TEMPLATE_HELPERS.join(", ")
+            ));
+        }
+    }
+    for parameter in helper.params.iter().chain(helper.hash.values()) {
+        if let Parameter::Subexpression(subexpression) = parameter {
+            if let TemplateElement::Expression(inner) = subexpression.as_element() {
+                check_helper_call(name, inner)?;
             }
         }
     }
+    for inner in [&helper.template, &helper.inverse].into_iter().flatten() {
+        check_helpers(name, inner)?;
+    }
+    Ok(())
+}
 
-    Ok(connection)
+/// Renders a template off the async runtime, bounded by
+/// `RENDER_TIMEOUT` and `MAX_RENDER_SIZE`.
+async fn render_sandboxed(
+    hbs: Arc<Handlebars<'static>>,
+    template: &str,
+    template_vars: impl Serialize + Send + 'static,
+) -> anyhow::Result<String> {
+    let name = template.to_string();
+    tokio::task::spawn_blocking(move || {
+        let budget = RenderBudget::new(MAX_RENDER_SIZE, RENDER_TIMEOUT);
+        render_within(&hbs, name.as_str(), &template_vars, budget)
+    })
+    .await?
 }
 
-impl FromStr for TodoData {
-    type Err = anyhow::Error;
+/// Renders into `budget`, which stops the render from the inside once it
+/// runs out, so no thread is left rendering after an error.
+fn render_within(
+    hbs: &Handlebars,
+    template: &str,
+    template_vars: &impl Serialize,
+    mut budget: RenderBudget,
+) -> anyhow::Result<String> {
+    hbs.render_to_write(template, template_vars, &mut budget)?;
+    Ok(String::from_utf8(budget.buf)?)
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mat = s.splitn(3, "+++\n");
+/// Writer that fails once more than `limit` bytes have been written or
+/// once written to after its time is up. Handlebars writes every piece of
+/// text and every expression as it goes, so loops are cut short too.
+struct RenderBudget {
+    buf: Vec<u8>,
+    limit: usize,
+    timeout: Duration,
+    deadline: std::time::Instant,
+}
 
-        let parts: Vec<_> = mat.collect();
-        if parts.is_empty() {
-            return Err(anyhow::anyhow!("invalid content"));
+impl RenderBudget {
+    fn new(limit: usize, timeout: Duration) -> Self {
+        let deadline = std::time::Instant::now() + timeout;
+        Self {
+            buf: vec![],
+            limit,
+            timeout,
+            deadline,
         }
-
-        let data = TodoData {
-            front_matter: toml::from_str(parts.get(1).unwrap())?,
-            content: parts.get(2).unwrap().to_string(),
-        };
-
-        Ok(data)
     }
 }
 
-fn init_hbs() -> anyhow::Result<Handlebars<'static>> {
-    let mut options = DirectorySourceOptions::default();
-    options.tpl_extension = ".md.hbs".to_string();
-    options.temporary = false;
-
-    let mut hbs = Handlebars::new();
-    hbs.register_templates_directory(std::env::current_dir()?.join("templates"), options)?;
+impl Write for RenderBudget {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if self.buf.len() + data.len() > self.limit {
+            return Err(std::io::Error::other(format!(
+                "rendered output exceeds {} bytes",
+                self.limit
+            )));
+        }
+        if std::time::Instant::now() > self.deadline {
+            return Err(std::io::Error::other(format!(
+                "rendering takes longer than {:?}",
+                self.timeout
+            )));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
 
-    Ok(hbs)
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Serialize)]
@@ -340,9 +3819,100 @@ impl TemplateVars {
 const TASK_TEMPLATE: &str = r#"+++
 id = {{ id }}
 created_at = "{{ created_at }}"
-tags = [ {{#each tags}}{{#if @index}}, {{/if}}"{{@index}} {{this}}"{{/each}} ]
+tags = [ {{#each tags}}{{#if @index}}, {{/if}}"{{this}}"{{/each}} ]
 +++
 
-# {{#if title}}{{title}}{{else}}Title{{/if}}
+# {{#if title}}{{{title}}}{{else}}Title{{/if}}
 
 "#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(template: &str) -> Handlebars<'static> {
+        let mut hbs = Handlebars::new();
+        hbs.register_template_string("t", template).unwrap();
+        hbs
+    }
+
+    fn render(template: &str, budget: RenderBudget) -> anyhow::Result<String> {
+        let vars = serde_json::json!({"items": (0..100).collect::<Vec<_>>()});
+        render_within(&registry(template), "t", &vars, budget)
+    }
+
+    #[test]
+    fn renders_within_budget() {
+        let budget = RenderBudget::new(MAX_RENDER_SIZE, RENDER_TIMEOUT);
+        let template = "{{#each items}}{{#if @first}}{{len ../items}}{{/if}}{{/each}}";
+        assert_eq!(render(template, budget).unwrap(), "100");
+    }
+
+    #[test]
+    fn stops_rendering_when_time_is_up() {
+        let budget = RenderBudget::new(MAX_RENDER_SIZE, Duration::ZERO);
+        let err = render("{{#each items}}.{{/each}}", budget).unwrap_err();
+        assert!(err.to_string().ends_with("rendering takes longer than 0ns"), "{err}");
+    }
+
+    #[test]
+    fn stops_rendering_past_the_size_limit() {
+        let budget = RenderBudget::new(100, RENDER_TIMEOUT);
+        let err = render("{{#each items}}{{this}}{{/each}}", budget).unwrap_err();
+        assert!(err.to_string().ends_with("rendered output exceeds 100 bytes"), "{err}");
+        let fits = render("{{#each items}}.{{/each}}", RenderBudget::new(100, RENDER_TIMEOUT));
+        assert_eq!(fits.unwrap(), ".".repeat(100));
+    }
+
+    #[test]
+    fn only_builtin_helpers() {
+        let check = |template: &str| check_helpers("t", &Template::compile(template).unwrap());
+        assert!(check("{{title}} {{#each tags}}{{#if @index}}, {{/if}}{{this}}{{/each}}").is_ok());
+        assert!(check("{{#if (eq a (len b))}}x{{else if c}}y{{/if}}").is_ok());
+        // unknown names without arguments are variables
+        assert!(check("{{include}}").is_ok());
+        for template in [
+            "{{include \"x\"}}",
+            "{{include path=\"x\"}}",
+            "{{#include}}{{/include}}",
+            "{{#if a}}{{else}}{{include \"x\"}}{{/if}}",
+            "{{#if (not (include \"x\"))}}{{/if}}",
+            "{{#with a}}{{{include \"x\"}}}{{/with}}",
+            "{{#> nothing}}{{include \"x\"}}{{/nothing}}",
+        ] {
+            let err = check(template).unwrap_err().to_string();
+            let expected = "template 't' calls the helper 'include'";
+            assert!(err.starts_with(expected), "{template}: {err}");
+        }
+    }
+
+    #[test]
+    fn answered_tags_follow_the_tag_policy() {
+        let rules = config::TagRules::default();
+        assert_eq!(
+            parse_tags("Backend, Work Stuff,, backend", &rules).unwrap(),
+            ["backend", "work-stuff"]
+        );
+        assert!(parse_tags("none", &rules).unwrap().is_empty());
+        let err = parse_tags("ok, bad!", &rules).unwrap_err().to_string();
+        assert!(err.starts_with("tag 'bad!' contains '!'"), "{err}");
+    }
+
+    #[test]
+    fn yaml_dates_of_the_todo_are_quoted() {
+        let todo = |fields: &str| {
+            let text = format!("---\nid: 3\ntags: []\n{fields}---\n\n# Plan\n");
+            TodoData::from_str(text.as_str()).map_err(|err| err.to_string())
+        };
+        let quoted = "created_at: \"2025-01-01T09:00:00Z\"\n";
+        assert!(todo(quoted).is_ok());
+        let err = todo("created_at: 2025-01-01T09:00:00Z\n").unwrap_err();
+        assert_eq!(err, "created_at must be a quoted string, like \"2025-01-31T09:00:00Z\"");
+        let entry = "time_entries:\n- start: \"2025-01-01T10:00:00Z\"\n  \
+                     end: 2025-01-01T11:00:00Z\n";
+        let err = todo(&format!("{quoted}{entry}")).unwrap_err();
+        assert!(err.starts_with("time_entries.end must be a quoted string"), "{err}");
+        // other fields may hold dates of any kind
+        assert!(todo(&format!("{quoted}review: 2025-02-01\n")).is_ok());
+    }
+}
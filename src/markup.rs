@@ -0,0 +1,240 @@
+//! Line-based conversion of todo markdown bodies into other markups.
+//!
+//! This deliberately understands only the subset of markdown templates
+//! produce: ATX headings, bullet and checkbox lists, fenced code blocks,
+//! inline code, bold and italic emphasis.
+
+use clap::ValueEnum;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Markup {
+    Markdown,
+    Plain,
+    Jira,
+    Html,
+}
+
+pub fn render(markdown: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => markdown.trim().to_string(),
+        Markup::Plain => render_lines(markdown, &Plain),
+        Markup::Jira => render_lines(markdown, &Jira),
+        Markup::Html => render_lines(markdown, &Html),
+    }
+}
+
+enum Line<'a> {
+    Heading(usize, &'a str),
+    Bullet(&'a str),
+    Checkbox(bool, &'a str),
+    Fence,
+    Code(&'a str),
+    Text(&'a str),
+    Blank,
+}
+
+trait Target {
+    fn line(&self, out: &mut String, line: &Line, in_list: bool);
+    fn open_code(&self, out: &mut String);
+    fn close_code(&self, out: &mut String);
+    fn close_list(&self, _out: &mut String) {}
+}
+
+fn render_lines(markdown: &str, target: &dyn Target) -> String {
+    let mut out = String::new();
+    let mut in_code = false;
+    let mut in_list = false;
+
+    for line in markdown.trim().lines() {
+        let line = classify(line, in_code);
+        let is_list_item = matches!(line, Line::Bullet(_) | Line::Checkbox(..));
+        if in_list && !is_list_item {
+            target.close_list(&mut out);
+        }
+
+        match line {
+            Line::Fence if in_code => target.close_code(&mut out),
+            Line::Fence => target.open_code(&mut out),
+            ref line => target.line(&mut out, line, in_list),
+        }
+
+        if matches!(line, Line::Fence) {
+            in_code = !in_code;
+        }
+        in_list = is_list_item;
+    }
+    if in_list {
+        target.close_list(&mut out);
+    }
+
+    out.trim_end().to_string()
+}
+
+fn classify(line: &str, in_code: bool) -> Line<'_> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") {
+        return Line::Fence;
+    }
+    if in_code {
+        return Line::Code(line);
+    }
+    if trimmed.is_empty() {
+        return Line::Blank;
+    }
+
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+        return Line::Heading(level, trimmed[level..].trim());
+    }
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = trimmed.strip_prefix(bullet) {
+            if let Some(text) = item.strip_prefix("[ ] ") {
+                return Line::Checkbox(false, text);
+            }
+            if let Some(text) = item
+                .strip_prefix("[x] ")
+                .or_else(|| item.strip_prefix("[X] "))
+            {
+                return Line::Checkbox(true, text);
+            }
+            return Line::Bullet(item);
+        }
+    }
+
+    Line::Text(line)
+}
+
+/// Rewrites inline code, bold, and italic spans with the given wrappers.
+fn inline(text: &str, code: (&str, &str), bold: (&str, &str), italic: (&str, &str)) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let span = [("`", code), ("**", bold), ("__", bold), ("*", italic), ("_", italic)]
+            .into_iter()
+            .find_map(|(marker, wrap)| {
+                let inner = rest.strip_prefix(marker)?;
+                let end = inner.find(marker).filter(|end| *end > 0)?;
+                Some((marker.len() * 2 + end, &inner[..end], wrap))
+            });
+
+        match span {
+            Some((len, inner, (open, close))) => {
+                out.push_str(open);
+                out.push_str(inner);
+                out.push_str(close);
+                rest = &rest[len..];
+            }
+            None => {
+                let ch = rest.chars().next().unwrap();
+                out.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+
+    out
+}
+
+struct Plain;
+
+impl Target for Plain {
+    fn line(&self, out: &mut String, line: &Line, _in_list: bool) {
+        let text = |text: &str| inline(text, ("", ""), ("", ""), ("", ""));
+        match line {
+            Line::Heading(_, heading) => writeln!(out, "{}", text(heading)),
+            Line::Bullet(item) => writeln!(out, "- {}", text(item)),
+            Line::Checkbox(true, item) => writeln!(out, "[x] {}", text(item)),
+            Line::Checkbox(false, item) => writeln!(out, "[ ] {}", text(item)),
+            Line::Code(code) => writeln!(out, "    {code}"),
+            Line::Text(line) => writeln!(out, "{}", text(line)),
+            Line::Blank | Line::Fence => writeln!(out),
+        }
+        .unwrap();
+    }
+
+    fn open_code(&self, _out: &mut String) {}
+
+    fn close_code(&self, _out: &mut String) {}
+}
+
+struct Jira;
+
+impl Target for Jira {
+    fn line(&self, out: &mut String, line: &Line, _in_list: bool) {
+        let text = |text: &str| inline(text, ("{{", "}}"), ("*", "*"), ("_", "_"));
+        match line {
+            Line::Heading(level, heading) => writeln!(out, "h{level}. {}", text(heading)),
+            Line::Bullet(item) => writeln!(out, "* {}", text(item)),
+            Line::Checkbox(true, item) => writeln!(out, "* (/) {}", text(item)),
+            Line::Checkbox(false, item) => writeln!(out, "* [ ] {}", text(item)),
+            Line::Code(code) => writeln!(out, "{code}"),
+            Line::Text(line) => writeln!(out, "{}", text(line)),
+            Line::Blank | Line::Fence => writeln!(out),
+        }
+        .unwrap();
+    }
+
+    fn open_code(&self, out: &mut String) {
+        out.push_str("{code}\n");
+    }
+
+    fn close_code(&self, out: &mut String) {
+        out.push_str("{code}\n");
+    }
+}
+
+struct Html;
+
+impl Target for Html {
+    fn line(&self, out: &mut String, line: &Line, in_list: bool) {
+        let text = |text: &str| {
+            inline(
+                &escape_html(text),
+                ("<code>", "</code>"),
+                ("<strong>", "</strong>"),
+                ("<em>", "</em>"),
+            )
+        };
+        if !in_list && matches!(line, Line::Bullet(_) | Line::Checkbox(..)) {
+            out.push_str("<ul>\n");
+        }
+        match line {
+            Line::Heading(level, heading) => {
+                writeln!(out, "<h{level}>{}</h{level}>", text(heading))
+            }
+            Line::Bullet(item) => writeln!(out, "<li>{}</li>", text(item)),
+            Line::Checkbox(done, item) => writeln!(
+                out,
+                "<li><input type=\"checkbox\" disabled{}> {}</li>",
+                if *done { " checked" } else { "" },
+                text(item)
+            ),
+            Line::Code(code) => writeln!(out, "{}", escape_html(code)),
+            Line::Text(line) => writeln!(out, "<p>{}</p>", text(line)),
+            Line::Blank | Line::Fence => Ok(()),
+        }
+        .unwrap();
+    }
+
+    fn open_code(&self, out: &mut String) {
+        out.push_str("<pre><code>");
+    }
+
+    fn close_code(&self, out: &mut String) {
+        out.push_str("</code></pre>\n");
+    }
+
+    fn close_list(&self, out: &mut String) {
+        out.push_str("</ul>\n");
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
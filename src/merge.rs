@@ -0,0 +1,267 @@
+//! Three-way merge of todo files, for use as a git merge driver.
+//!
+//! Front matter is merged field by field: a field changed on one side
+//! only takes that side's value, tags are merged as sets, and when both
+//! sides changed the status the one that changed it last wins. Anything
+//! else both sides changed differently keeps our value and is reported
+//! as a conflict; conflicting bodies are kept side by side between
+//! conflict markers.
+
+use crate::{front_matter_table, FrontMatter, TodoData};
+
+/// Front matter fields that describe the status and move together.
+const STATUS_FIELDS: &[&str] = &["status", "completed_at", "cancellation"];
+
+#[derive(Debug)]
+pub struct Merged {
+    pub data: TodoData,
+    /// Fields merged by keeping our side, and `body` when the bodies
+    /// conflict.
+    pub conflicts: Vec<String>,
+}
+
+pub fn merge(base: &TodoData, ours: &TodoData, theirs: &TodoData) -> anyhow::Result<Merged> {
+    let base_table = front_matter_table(&base.front_matter)?;
+    let ours_table = front_matter_table(&ours.front_matter)?;
+    let theirs_table = front_matter_table(&theirs.front_matter)?;
+
+    let status_side = match status_winner(base, ours, theirs) {
+        Side::Ours => &ours_table,
+        Side::Theirs => &theirs_table,
+    };
+
+    let mut keys: Vec<&String> = ours_table.keys().collect();
+    keys.extend(theirs_table.keys().filter(|key| !ours_table.contains_key(*key)));
+
+    let mut merged = toml::Table::new();
+    let mut conflicts = vec![];
+    for key in keys {
+        if key == "tags" {
+            continue;
+        }
+        let value = if STATUS_FIELDS.contains(&key.as_str()) {
+            status_side.get(key)
+        } else {
+            match three_way(base_table.get(key), ours_table.get(key), theirs_table.get(key)) {
+                Ok(value) => value,
+                Err(ours) => {
+                    conflicts.push(key.clone());
+                    ours
+                }
+            }
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    merged.insert(
+        "tags".to_string(),
+        merge_tags(
+            &base.front_matter.tags,
+            &ours.front_matter.tags,
+            &theirs.front_matter.tags,
+        )
+        .into(),
+    );
+    let front_matter: FrontMatter = toml::Value::Table(merged).try_into()?;
+
+    let content = match three_way(Some(&base.content), Some(&ours.content), Some(&theirs.content)) {
+        Ok(content) => content.unwrap().clone(),
+        Err(_) => {
+            conflicts.push("body".to_string());
+            format!(
+                "<<<<<<< ours\n{}\n=======\n{}\n>>>>>>> theirs\n",
+                ours.content.trim_end(),
+                theirs.content.trim_end()
+            )
+        }
+    };
+
+    Ok(Merged {
+        data: TodoData {
+            front_matter,
+            content,
+            style: ours.style,
+        },
+        conflicts,
+    })
+}
+
+/// Picks the side that changed relative to `base`. When both changed it
+/// differently, the error carries our value.
+fn three_way<'a, T: PartialEq>(
+    base: Option<&'a T>,
+    ours: Option<&'a T>,
+    theirs: Option<&'a T>,
+) -> Result<Option<&'a T>, Option<&'a T>> {
+    if ours == theirs || theirs == base {
+        Ok(ours)
+    } else if ours == base {
+        Ok(theirs)
+    } else {
+        Err(ours)
+    }
+}
+
+/// Keeps tags both sides kept and tags either side added.
+fn merge_tags(base: &[String], ours: &[String], theirs: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = vec![];
+    for tag in ours.iter().chain(theirs) {
+        let kept = ours.contains(tag) && theirs.contains(tag);
+        let added = !base.contains(tag);
+        if (kept || added) && !merged.contains(tag) {
+            merged.push(tag.clone());
+        }
+    }
+    merged
+}
+
+enum Side {
+    Ours,
+    Theirs,
+}
+
+fn status_winner(base: &TodoData, ours: &TodoData, theirs: &TodoData) -> Side {
+    let status = |data: &TodoData| {
+        let front_matter = &data.front_matter;
+        (
+            front_matter.status,
+            front_matter.completed_at,
+            front_matter.cancellation.as_ref().map(|c| c.at),
+        )
+    };
+    if status(theirs) == status(base) || status(theirs) == status(ours) {
+        return Side::Ours;
+    }
+    if status(ours) == status(base) {
+        return Side::Theirs;
+    }
+
+    // reopening carries no timestamp, so a done or cancelled side wins
+    if theirs.front_matter.closed_at() > ours.front_matter.closed_at() {
+        Side::Theirs
+    } else {
+        Side::Ours
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use std::str::FromStr;
+
+    fn todo(front_matter: &str, body: &str) -> TodoData {
+        let text = format!(
+            "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\n{front_matter}+++\n\n# Plan\n{body}"
+        );
+        TodoData::from_str(text.as_str()).unwrap()
+    }
+
+    fn merged(base: &TodoData, ours: &TodoData, theirs: &TodoData) -> Merged {
+        merge(base, ours, theirs).unwrap()
+    }
+
+    #[test]
+    fn one_sided_changes_are_taken() {
+        let base = todo("tags = []\npriority = \"low\"\n", "");
+        let ours = todo("tags = []\npriority = \"low\"\n", "notes\n");
+        let theirs = todo(
+            "due_at = \"2099-01-01T00:00:00Z\"\ntags = []\nowner = \"kim\"\n",
+            "",
+        );
+        let result = merged(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        let front_matter = &result.data.front_matter;
+        assert_eq!(front_matter.due_at.unwrap().to_rfc3339(), "2099-01-01T00:00:00+00:00");
+        // removed on their side
+        assert_eq!(front_matter.priority, None);
+        assert_eq!(front_matter.extra.get("owner").unwrap().as_str(), Some("kim"));
+        assert_eq!(result.data.content, "\n# Plan\nnotes\n");
+    }
+
+    #[test]
+    fn identical_changes_do_not_conflict() {
+        let base = todo("tags = []\n", "");
+        let both = todo("tags = []\npriority = \"high\"\n", "same\n");
+        let result = merged(&base, &both, &both);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.data.content, both.content);
+    }
+
+    #[test]
+    fn conflicting_fields_keep_ours() {
+        let base = todo("tags = []\npriority = \"low\"\nowner = \"kim\"\n", "");
+        let ours = todo("tags = []\npriority = \"high\"\nowner = \"ana\"\n", "");
+        let theirs = todo("tags = []\npriority = \"medium\"\nowner = 7\n", "");
+        let result = merged(&base, &ours, &theirs);
+        let mut conflicts = result.conflicts.clone();
+        conflicts.sort();
+        assert_eq!(conflicts, ["owner", "priority"]);
+        assert_eq!(result.data.front_matter.priority, Some(crate::Priority::High));
+        assert_eq!(result.data.front_matter.extra.get("owner").unwrap().as_str(), Some("ana"));
+    }
+
+    #[test]
+    fn tags_merge_as_sets() {
+        let base = todo("tags = [\"a\", \"b\", \"c\"]\n", "");
+        let ours = todo("tags = [\"a\", \"b\", \"d\"]\n", "");
+        let theirs = todo("tags = [\"b\", \"c\", \"d\", \"e\"]\n", "");
+        let result = merged(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.data.front_matter.tags, ["b", "d", "e"]);
+    }
+
+    #[test]
+    fn later_status_change_wins_with_its_fields() {
+        let base = todo("tags = []\n", "");
+        let ours = todo(
+            "tags = []\nstatus = \"done\"\ncompleted_at = \"2025-01-05T09:00:00Z\"\n",
+            "",
+        );
+        let theirs = todo(
+            "tags = []\nstatus = \"cancelled\"\n\n[cancellation]\n\
+             at = \"2025-01-06T09:00:00Z\"\nreason = \"moot\"\n",
+            "",
+        );
+        let result = merged(&base, &ours, &theirs);
+        assert!(result.conflicts.is_empty());
+        let front_matter = &result.data.front_matter;
+        assert_eq!(front_matter.status, Status::Cancelled);
+        assert_eq!(front_matter.completed_at, None);
+        assert_eq!(front_matter.cancellation.as_ref().unwrap().reason, "moot");
+
+        let result = merged(&base, &theirs, &ours);
+        assert_eq!(result.data.front_matter.status, Status::Cancelled);
+    }
+
+    #[test]
+    fn closing_beats_reopening() {
+        let done = "tags = []\nstatus = \"done\"\ncompleted_at = \"2025-01-05T09:00:00Z\"\n";
+        let base = todo(done, "");
+        let reopened = todo("tags = []\nstatus = \"in-progress\"\n", "");
+        let done_again = todo(
+            "tags = []\nstatus = \"done\"\ncompleted_at = \"2025-01-07T09:00:00Z\"\n",
+            "",
+        );
+        let result = merged(&base, &reopened, &done_again);
+        assert_eq!(result.data.front_matter.status, Status::Done);
+        let result = merged(&base, &done_again, &reopened);
+        assert_eq!(result.data.front_matter.status, Status::Done);
+        assert!(result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicting_bodies_are_kept_between_markers() {
+        let base = todo("tags = []\n", "- [ ] one\n");
+        let ours = todo("tags = []\n", "- [x] one\n");
+        let theirs = todo("tags = []\n", "- [ ] one\n- [ ] two\n");
+        let result = merged(&base, &ours, &theirs);
+        assert_eq!(result.conflicts, ["body"]);
+        assert_eq!(
+            result.data.content,
+            "<<<<<<< ours\n\n# Plan\n- [x] one\n=======\n\n# Plan\n- [ ] one\n- [ ] two\n\
+             >>>>>>> theirs\n"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+//! Text patterns for `search`: plain substrings or a small regex dialect.
+//!
+//! The dialect covers what's useful for searching notes: literals, `.`,
+//! classes like `[a-z]` and `[^0-9]`, the escapes `\d \w \s` and their
+//! negations, `* + ?` and `{n,m}` quantifiers, `^ $` anchors, groups and
+//! `|` alternation. Matching tracks every reachable position instead of
+//! backtracking, so no pattern can take exponential time.
+
+use anyhow::anyhow;
+use std::collections::BTreeSet;
+
+#[derive(Debug)]
+pub struct Pattern {
+    alternatives: Vec<Vec<Piece>>,
+    ignore_case: bool,
+}
+
+#[derive(Debug)]
+struct Piece {
+    atom: Atom,
+    min: usize,
+    max: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Atom {
+    Char(char),
+    Any,
+    Class { items: Vec<ClassItem>, negated: bool },
+    Start,
+    End,
+    Group(Vec<Vec<Piece>>),
+}
+
+#[derive(Debug)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl Pattern {
+    /// Matches `text` literally.
+    pub fn literal(text: &str, ignore_case: bool) -> Self {
+        let pieces = text
+            .chars()
+            .map(|ch| Piece {
+                atom: Atom::Char(ch),
+                min: 1,
+                max: Some(1),
+            })
+            .collect();
+        Self {
+            alternatives: vec![pieces],
+            ignore_case,
+        }
+    }
+
+    pub fn regex(source: &str, ignore_case: bool) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err(anyhow!("invalid pattern '{source}': unmatched ')'"));
+        }
+        Ok(Self {
+            alternatives,
+            ignore_case,
+        })
+    }
+
+    /// The leftmost, longest match in `text` as a char range.
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = text.chars().collect();
+        (0..=chars.len()).find_map(|start| {
+            self.match_alternatives(&self.alternatives, &chars, start)
+                .last()
+                .map(|&end| (start, end))
+        })
+    }
+
+    fn match_alternatives(
+        &self,
+        alternatives: &[Vec<Piece>],
+        text: &[char],
+        pos: usize,
+    ) -> BTreeSet<usize> {
+        alternatives
+            .iter()
+            .flat_map(|pieces| self.match_sequence(pieces, text, pos))
+            .collect()
+    }
+
+    fn match_sequence(&self, pieces: &[Piece], text: &[char], pos: usize) -> BTreeSet<usize> {
+        let mut positions = BTreeSet::from([pos]);
+        for piece in pieces {
+            positions = positions
+                .into_iter()
+                .flat_map(|pos| self.match_piece(piece, text, pos))
+                .collect();
+            if positions.is_empty() {
+                break;
+            }
+        }
+        positions
+    }
+
+    fn match_piece(&self, piece: &Piece, text: &[char], pos: usize) -> BTreeSet<usize> {
+        let mut ends = BTreeSet::new();
+        let mut current = BTreeSet::from([pos]);
+        let mut seen = BTreeSet::new();
+        let mut count = 0;
+
+        loop {
+            if count >= piece.min {
+                ends.extend(current.iter().copied());
+            }
+            if piece.max.is_some_and(|max| count >= max) {
+                break;
+            }
+            // positions already expanded can't lead anywhere new, which
+            // also ends loops over atoms that match the empty string
+            let next: BTreeSet<usize> = current
+                .iter()
+                .flat_map(|&pos| self.match_atom(&piece.atom, text, pos))
+                .filter(|pos| count < piece.min || !seen.contains(pos))
+                .collect();
+            if next.is_empty() {
+                break;
+            }
+            if count >= piece.min {
+                seen.extend(current);
+            }
+            current = next;
+            count += 1;
+        }
+        ends
+    }
+
+    fn match_atom(&self, atom: &Atom, text: &[char], pos: usize) -> BTreeSet<usize> {
+        let next = text.get(pos).copied();
+        let single = |matches: bool| match matches {
+            true => BTreeSet::from([pos + 1]),
+            false => BTreeSet::new(),
+        };
+
+        match atom {
+            Atom::Char(expected) => single(next.is_some_and(|ch| self.same_char(ch, *expected))),
+            Atom::Any => single(next.is_some_and(|ch| ch != '\n')),
+            Atom::Class { items, negated } => single(next.is_some_and(|ch| {
+                items.iter().any(|item| self.class_contains(item, ch)) != *negated
+            })),
+            Atom::Start if pos == 0 => BTreeSet::from([pos]),
+            Atom::End if pos == text.len() => BTreeSet::from([pos]),
+            Atom::Start | Atom::End => BTreeSet::new(),
+            Atom::Group(alternatives) => self.match_alternatives(alternatives, text, pos),
+        }
+    }
+
+    fn same_char(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn class_contains(&self, item: &ClassItem, ch: char) -> bool {
+        match item {
+            ClassItem::Range(from, to) => {
+                let within = |ch: char| *from <= ch && ch <= *to;
+                within(ch)
+                    || (self.ignore_case
+                        && (ch.to_lowercase().any(within) || ch.to_uppercase().any(within)))
+            }
+            ClassItem::Digit(negated) => ch.is_ascii_digit() != *negated,
+            ClassItem::Word(negated) => (ch.is_alphanumeric() || ch == '_') != *negated,
+            ClassItem::Space(negated) => ch.is_whitespace() != *negated,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.peek();
+        self.pos += 1;
+        ch
+    }
+
+    fn error(&self, problem: &str) -> anyhow::Error {
+        let source: String = self.chars.iter().collect();
+        anyhow!("invalid pattern '{source}': {problem}")
+    }
+
+    fn alternatives(&mut self) -> anyhow::Result<Vec<Vec<Piece>>> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> anyhow::Result<Vec<Piece>> {
+        let mut pieces = vec![];
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            let (min, max) = self.quantifier()?;
+            pieces.push(Piece { atom, min, max });
+        }
+        Ok(pieces)
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Atom> {
+        Ok(match self.next().unwrap() {
+            '.' => Atom::Any,
+            '^' => Atom::Start,
+            '$' => Atom::End,
+            '(' => {
+                let alternatives = self.alternatives()?;
+                if self.next() != Some(')') {
+                    return Err(self.error("unclosed '('"));
+                }
+                Atom::Group(alternatives)
+            }
+            '[' => self.class()?,
+            '\\' => match self.escape()? {
+                Escaped::Char(ch) => Atom::Char(ch),
+                Escaped::Class(item) => Atom::Class {
+                    items: vec![item],
+                    negated: false,
+                },
+            },
+            '*' | '+' | '?' | '{' => return Err(self.error("quantifier without anything to repeat")),
+            ch => Atom::Char(ch),
+        })
+    }
+
+    fn class(&mut self) -> anyhow::Result<Atom> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut items = vec![];
+        let mut first = true;
+        loop {
+            let ch = match self.next() {
+                None => return Err(self.error("unclosed '['")),
+                Some(']') if !first => break,
+                Some('\\') => match self.escape()? {
+                    Escaped::Char(ch) => ch,
+                    Escaped::Class(item) => {
+                        items.push(item);
+                        first = false;
+                        continue;
+                    }
+                },
+                Some(ch) => ch,
+            };
+            first = false;
+
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let to = match self.next() {
+                    Some('\\') => match self.escape()? {
+                        Escaped::Char(to) => to,
+                        Escaped::Class(_) => return Err(self.error("class escape ends a range")),
+                    },
+                    Some(to) => to,
+                    None => return Err(self.error("unclosed '['")),
+                };
+                if to < ch {
+                    return Err(self.error("range is out of order"));
+                }
+                items.push(ClassItem::Range(ch, to));
+            } else {
+                items.push(ClassItem::Range(ch, ch));
+            }
+        }
+        Ok(Atom::Class { items, negated })
+    }
+
+    fn escape(&mut self) -> anyhow::Result<Escaped> {
+        let ch = self.next().ok_or_else(|| self.error("trailing '\\'"))?;
+        Ok(match ch {
+            'd' | 'D' => Escaped::Class(ClassItem::Digit(ch == 'D')),
+            'w' | 'W' => Escaped::Class(ClassItem::Word(ch == 'W')),
+            's' | 'S' => Escaped::Class(ClassItem::Space(ch == 'S')),
+            'n' => Escaped::Char('\n'),
+            't' => Escaped::Char('\t'),
+            ch if ch.is_alphanumeric() => return Err(self.error(&format!("unknown escape '\\{ch}'"))),
+            ch => Escaped::Char(ch),
+        })
+    }
+
+    fn quantifier(&mut self) -> anyhow::Result<(usize, Option<usize>)> {
+        let bounds = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => return self.bounds(),
+            _ => return Ok((1, Some(1))),
+        };
+        self.pos += 1;
+        Ok(bounds)
+    }
+
+    /// Parses `{n}`, `{n,}` or `{n,m}`.
+    fn bounds(&mut self) -> anyhow::Result<(usize, Option<usize>)> {
+        let start = self.pos;
+        let close = self.chars[start..]
+            .iter()
+            .position(|&ch| ch == '}')
+            .ok_or_else(|| self.error("unclosed '{'"))?;
+        let inner: String = self.chars[start + 1..start + close].iter().collect();
+        self.pos = start + close + 1;
+
+        let number = |text: &str| {
+            text.trim()
+                .parse::<usize>()
+                .map_err(|_| self.error(&format!("invalid repetition '{{{inner}}}'")))
+        };
+        let (min, max) = match inner.split_once(',') {
+            None => {
+                let count = number(inner.as_str())?;
+                (count, Some(count))
+            }
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error(&format!("invalid repetition '{{{inner}}}'")));
+        }
+        Ok((min, max))
+    }
+}
+
+enum Escaped {
+    Char(char),
+    Class(ClassItem),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(source: &str, text: &str) -> Option<(usize, usize)> {
+        Pattern::regex(source, false).unwrap().find(text)
+    }
+
+    fn error(source: &str) -> String {
+        Pattern::regex(source, false).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn empty_pattern_matches_at_the_start() {
+        assert_eq!(find("", "abc"), Some((0, 0)));
+        assert_eq!(find("", ""), Some((0, 0)));
+        assert_eq!(Pattern::literal("", false).find("abc"), Some((0, 0)));
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(find("^ab", "abab"), Some((0, 2)));
+        assert_eq!(find("ab$", "abab"), Some((2, 4)));
+        assert_eq!(find("^b", "ab"), None);
+        assert_eq!(find("a$", "ab"), None);
+        assert_eq!(find("^$", ""), Some((0, 0)));
+        assert_eq!(find("^$", "x"), None);
+        assert_eq!(find("a|^b", "cb a"), Some((3, 4)));
+    }
+
+    #[test]
+    fn nested_groups_and_alternation() {
+        assert_eq!(find("((a|b)c)+", "xacbcd"), Some((1, 5)));
+        assert_eq!(find("(a(b|c(d|e)))f", "acef"), Some((0, 4)));
+        assert_eq!(find("(a|ab)(c|bcd)", "abcd"), Some((0, 4)));
+        assert_eq!(find("()", "x"), Some((0, 0)));
+    }
+
+    #[test]
+    fn quantifiers_are_greedy() {
+        assert_eq!(find("a*", "aaab"), Some((0, 3)));
+        assert_eq!(find("a+", "baa"), Some((1, 3)));
+        assert_eq!(find("ab?c", "ac abc"), Some((0, 2)));
+        assert_eq!(find("<.*>", "<a> <b>"), Some((0, 7)));
+        assert_eq!(find("a{2}", "aaaa"), Some((0, 2)));
+        assert_eq!(find("a{2,}", "aaaa"), Some((0, 4)));
+        assert_eq!(find("a{1,3}", "aaaa"), Some((0, 3)));
+        assert_eq!(find("a{3}", "aa"), None);
+    }
+
+    #[test]
+    fn nested_empty_repetition_terminates() {
+        assert_eq!(find("(a*)*b", "aaac"), None);
+        assert_eq!(find("(a?)+$", "aaa"), Some((0, 3)));
+        assert_eq!(find("(a|aa)*c", &format!("{}b", "a".repeat(64))), None);
+    }
+
+    #[test]
+    fn lazy_quantifiers_are_rejected() {
+        assert_eq!(
+            error("<.*?>"),
+            "invalid pattern '<.*?>': quantifier without anything to repeat"
+        );
+        assert_eq!(error("a+?"), "invalid pattern 'a+?': quantifier without anything to repeat");
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(find("[a-c]+", "xxbcaz"), Some((2, 5)));
+        assert_eq!(find("[^0-9]", "12a"), Some((2, 3)));
+        assert_eq!(find("[]a]+", "x]a]"), Some((1, 4)));
+        assert_eq!(find("[a-]+", "-a-"), Some((0, 3)));
+        assert_eq!(find(r"[\d.]+", "v1.25"), Some((1, 5)));
+        assert_eq!(find(r"\w+\s\D", "foo_1 x"), Some((0, 7)));
+        assert_eq!(find(r"\S+", "  ab "), Some((2, 4)));
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(error("[abc"), "invalid pattern '[abc': unclosed '['");
+        assert_eq!(error("[a-"), "invalid pattern '[a-': unclosed '['");
+        assert_eq!(error("(ab"), "invalid pattern '(ab': unclosed '('");
+        assert_eq!(error("ab)"), "invalid pattern 'ab)': unmatched ')'");
+        assert_eq!(error("a{2"), "invalid pattern 'a{2': unclosed '{'");
+        assert_eq!(error("a{3,1}"), "invalid pattern 'a{3,1}': invalid repetition '{3,1}'");
+        assert_eq!(error("a{x}"), "invalid pattern 'a{x}': invalid repetition '{x}'");
+        assert_eq!(error("*a"), "invalid pattern '*a': quantifier without anything to repeat");
+        assert_eq!(error("[z-a]"), "invalid pattern '[z-a]': range is out of order");
+        assert_eq!(error(r"a\"), r"invalid pattern 'a\': trailing '\'");
+        assert_eq!(error(r"\q"), r"invalid pattern '\q': unknown escape '\q'");
+        assert_eq!(error(r"[a-\d]"), r"invalid pattern '[a-\d]': class escape ends a range");
+    }
+
+    #[test]
+    fn non_ascii_text_is_matched_by_char() {
+        assert_eq!(find("é+", "caféé!"), Some((3, 5)));
+        assert_eq!(find("caf.$", "café"), Some((0, 4)));
+        assert_eq!(find("[à-ü]", "naïve"), Some((2, 3)));
+        assert_eq!(find(r"\w+", "„Grüße“"), Some((1, 6)));
+        assert_eq!(find("日本", "こんにちは日本"), Some((5, 7)));
+    }
+
+    #[test]
+    fn ignore_case() {
+        let pattern = Pattern::regex("straße [a-c]", true).unwrap();
+        assert_eq!(pattern.find("STRASSE A"), None);
+        assert_eq!(pattern.find("Straße B"), Some((0, 8)));
+        assert_eq!(Pattern::literal("ÉTÉ", true).find("un été"), Some((3, 6)));
+        assert_eq!(Pattern::literal("abc", false).find("ABC"), None);
+    }
+
+    #[test]
+    fn literals_match_special_characters_as_they_are() {
+        assert_eq!(Pattern::literal("a.b*", false).find("axb a.b*"), Some((4, 8)));
+    }
+}
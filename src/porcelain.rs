@@ -0,0 +1,145 @@
+//! The stable, versioned output of `--porcelain`: tab-separated records
+//! starting with the format version, like `v1\tcompleted\t3`.
+//!
+//! Fields never contain a tab or line break: backslashes, tabs, newlines
+//! and carriage returns in them are written as `\\`, `\t`, `\n` and `\r`,
+//! so a title like `a<TAB>b` stays one field.
+//!
+//! Records of `new`, `list`, `done` and the bulk commands are `Record`s,
+//! as scripts rely on those the most. The tests match on every record, so
+//! one can't be added or changed without pinning its format.
+
+use crate::DataId;
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+
+/// Bumped whenever an existing porcelain record changes shape.
+pub const VERSION: u32 = 1;
+
+/// Formats one record: the format version followed by the given fields,
+/// escaped and separated by tabs.
+pub fn line(fields: &[&str]) -> String {
+    let mut line = format!("v{VERSION}");
+    for field in fields {
+        line.push('\t');
+        for ch in field.chars() {
+            match ch {
+                '\\' => line.push_str("\\\\"),
+                '\t' => line.push_str("\\t"),
+                '\n' => line.push_str("\\n"),
+                '\r' => line.push_str("\\r"),
+                ch => line.push(ch),
+            }
+        }
+    }
+    line
+}
+
+pub enum Record<'a> {
+    /// A todo `new` created, at a path relative to the data dir.
+    Created { id: DataId, path: &'a str },
+    /// A todo `list` matched.
+    Todo {
+        id: DataId,
+        due_at: Option<DateTime<Utc>>,
+        tags: &'a [String],
+        title: &'a str,
+    },
+    Completed { id: DataId },
+    /// The next occurrence `done` created for a recurring todo.
+    Recurred {
+        id: DataId,
+        next: DataId,
+        due_at: Option<DateTime<Utc>>,
+    },
+    Cancelled { id: DataId },
+    Deleted { id: DataId },
+    /// The tags a todo has after `tag`.
+    Tagged { id: DataId, tags: &'a [String] },
+    /// A todo a bulk command would `action` with `--dry-run`.
+    Would { action: &'a str, id: DataId },
+}
+
+impl Display for Record<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rfc3339 = |at: &Option<DateTime<Utc>>| at.map(|at| at.to_rfc3339()).unwrap_or_default();
+        let line = match self {
+            Record::Created { id, path } => line(&["created", &id.to_string(), path]),
+            Record::Todo {
+                id,
+                due_at,
+                tags,
+                title,
+            } => line(&["todo", &id.to_string(), &rfc3339(due_at), &tags.join(","), title]),
+            Record::Completed { id } => line(&["completed", &id.to_string()]),
+            Record::Recurred { id, next, due_at } => {
+                line(&["recurred", &id.to_string(), &next.to_string(), &rfc3339(due_at)])
+            }
+            Record::Cancelled { id } => line(&["cancelled", &id.to_string()]),
+            Record::Deleted { id } => line(&["deleted", &id.to_string()]),
+            Record::Tagged { id, tags } => line(&["tagged", &id.to_string(), &tags.join(",")]),
+            Record::Would { action, id } => line(&["would", action, &id.to_string()]),
+        };
+        f.write_str(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    /// The format of each record, matched without a wildcard so that new
+    /// records fail to compile until they are pinned here.
+    fn pinned(record: &Record) -> &'static str {
+        match record {
+            Record::Created { .. } => "v1\tcreated\t3\t0000000003.md",
+            Record::Todo { due_at: Some(_), .. } => {
+                "v1\ttodo\t3\t2025-01-31T23:59:59+00:00\ta,b\tPlan"
+            }
+            Record::Todo { due_at: None, title: "Plan", .. } => "v1\ttodo\t3\t\t\tPlan",
+            Record::Todo { due_at: None, .. } => "v1\ttodo\t3\t\t\\\\t\tPlan\\tnow\\n\\\\r",
+            Record::Completed { .. } => "v1\tcompleted\t3",
+            Record::Recurred { due_at: Some(_), .. } => {
+                "v1\trecurred\t3\t4\t2025-01-31T23:59:59+00:00"
+            }
+            Record::Recurred { due_at: None, .. } => "v1\trecurred\t3\t4\t",
+            Record::Cancelled { .. } => "v1\tcancelled\t3",
+            Record::Deleted { .. } => "v1\tdeleted\t3",
+            Record::Tagged { tags: [], .. } => "v1\ttagged\t3\t",
+            Record::Tagged { .. } => "v1\ttagged\t3\ta,b",
+            Record::Would { .. } => "v1\twould\tcomplete\t3",
+        }
+    }
+
+    #[test]
+    fn records_keep_their_format() {
+        let due_at = Some(Utc.with_ymd_and_hms(2025, 1, 31, 23, 59, 59).unwrap());
+        let tags = ["a".to_string(), "b".to_string()];
+        let escaped = ["\\t".to_string()];
+        let records = [
+            Record::Created { id: 3, path: "0000000003.md" },
+            Record::Todo { id: 3, due_at, tags: &tags, title: "Plan" },
+            Record::Todo { id: 3, due_at: None, tags: &[], title: "Plan" },
+            Record::Todo { id: 3, due_at: None, tags: &escaped, title: "Plan\tnow\n\\r" },
+            Record::Completed { id: 3 },
+            Record::Recurred { id: 3, next: 4, due_at },
+            Record::Recurred { id: 3, next: 4, due_at: None },
+            Record::Cancelled { id: 3 },
+            Record::Deleted { id: 3 },
+            Record::Tagged { id: 3, tags: &tags },
+            Record::Tagged { id: 3, tags: &[] },
+            Record::Would { action: "complete", id: 3 },
+        ];
+        for record in records {
+            assert_eq!(record.to_string(), pinned(&record));
+        }
+    }
+
+    #[test]
+    fn lines_start_with_the_version() {
+        assert_eq!(line(&[]), "v1");
+        assert_eq!(line(&["tag", "", "2"]), "v1\ttag\t\t2");
+        assert_eq!(line(&["a\tb", "c\r\nd", "\\"]), "v1\ta\\tb\tc\\r\\nd\t\\\\");
+    }
+}
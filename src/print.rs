@@ -0,0 +1,88 @@
+//! Print-optimized HTML pages of todos.
+
+use crate::config::IdFormat;
+use crate::markup::{self, escape_html, Markup};
+use crate::TodoFile;
+use clap::ValueEnum;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// One line per todo with a box to tick, for a daily sheet
+    Checklist,
+    /// One card per todo including its body, to cut out for a board
+    Cards,
+}
+
+const STYLE: &str = "
+@page { margin: 1.5cm; }
+body { font-family: sans-serif; font-size: 11pt; color: #000; }
+h1 { font-size: 14pt; }
+.meta { color: #555; font-size: 9pt; }
+ul.checklist { list-style: none; padding: 0; }
+ul.checklist li { padding: 0.4em 0; border-bottom: 1px solid #ccc; }
+ul.checklist li::before { content: \"\\2610\"; margin-right: 0.6em; font-size: 13pt; }
+.done .title { text-decoration: line-through; }
+.cancelled .title { text-decoration: line-through; color: #777; }
+.cards { display: grid; grid-template-columns: repeat(2, 1fr); gap: 0.5cm; }
+.card { border: 1px dashed #000; padding: 0.4cm; height: 8cm; overflow: hidden; break-inside: avoid; }
+.card h2 { font-size: 12pt; margin: 0 0 0.2cm 0; }
+";
+
+pub fn render(title: &str, todos: &[&TodoFile], layout: Layout, ids: &IdFormat) -> String {
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>{}</title>", escape_html(title)).unwrap();
+    writeln!(html, "<style>{STYLE}</style>\n</head>\n<body>").unwrap();
+    writeln!(html, "<h1>{}</h1>", escape_html(title)).unwrap();
+
+    match layout {
+        Layout::Checklist => {
+            html.push_str("<ul class=\"checklist\">\n");
+            for todo in todos {
+                writeln!(
+                    html,
+                    "<li class=\"{}\"><span class=\"title\">{}</span> <span class=\"meta\">{}</span></li>",
+                    todo.data.front_matter.status.name(),
+                    escape_html(todo.data.title().unwrap_or("-")),
+                    meta(todo, ids)
+                )
+                .unwrap();
+            }
+            html.push_str("</ul>\n");
+        }
+        Layout::Cards => {
+            html.push_str("<div class=\"cards\">\n");
+            for todo in todos {
+                writeln!(
+                    html,
+                    "<div class=\"card {}\">\n<h2 class=\"title\">{}</h2>\n<div class=\"meta\">{}</div>\n{}\n</div>",
+                    todo.data.front_matter.status.name(),
+                    escape_html(todo.data.title().unwrap_or("-")),
+                    meta(todo, ids),
+                    markup::render(todo.data.body(), Markup::Html)
+                )
+                .unwrap();
+            }
+            html.push_str("</div>\n");
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn meta(todo: &TodoFile, ids: &IdFormat) -> String {
+    let front_matter = &todo.data.front_matter;
+    let mut meta = escape_html(&ids.display(front_matter.id));
+    if let Some(cancellation) = &front_matter.cancellation {
+        write!(meta, " &middot; cancelled: {}", escape_html(&cancellation.reason)).unwrap();
+    }
+    if let Some(due_at) = front_matter.due_at {
+        write!(meta, " &middot; due {}", due_at.format("%Y-%m-%d")).unwrap();
+    }
+    if !front_matter.tags.is_empty() {
+        write!(meta, " &middot; {}", escape_html(&front_matter.tags.join(", "))).unwrap();
+    }
+    meta
+}
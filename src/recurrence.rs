@@ -0,0 +1,301 @@
+//! Recurrence rules in the RRULE syntax of iCalendar, like
+//! `FREQ=WEEKLY;BYDAY=MO`.
+//!
+//! Understood are `FREQ` (`DAILY`, `WEEKLY`, `MONTHLY`, `YEARLY`),
+//! `INTERVAL`, `BYDAY` with plain weekdays for weekly rules and
+//! `BYMONTHDAY`, negative counting from the end of the month, for monthly
+//! ones. Occurrences are whole days; the time of day stays as it was.
+
+use anyhow::anyhow;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use std::str::FromStr;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    /// Every how many days, weeks, months or years.
+    pub interval: u32,
+    /// Weekdays of a weekly rule, the anchor's when empty.
+    pub weekdays: Vec<Weekday>,
+    /// Days of a monthly rule, the anchor's when empty.
+    pub month_days: Vec<i32>,
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut frequency = None;
+        let mut rule = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            weekdays: vec![],
+            month_days: vec![],
+        };
+
+        for part in text.trim().trim_start_matches("RRULE:").split(';') {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid recurrence part '{part}', expected KEY=VALUE"))?;
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(anyhow!("unsupported FREQ '{value}'")),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| anyhow!("invalid INTERVAL '{value}'"))?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let (_, weekday) = WEEKDAYS
+                            .iter()
+                            .find(|(code, _)| code.eq_ignore_ascii_case(day))
+                            .ok_or_else(|| anyhow!("invalid BYDAY '{day}', expected MO to SU"))?;
+                        rule.weekdays.push(*weekday);
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        let day = day
+                            .parse()
+                            .ok()
+                            .filter(|day: &i32| (1..=31).contains(&day.abs()))
+                            .ok_or_else(|| anyhow!("invalid BYMONTHDAY '{day}'"))?;
+                        rule.month_days.push(day);
+                    }
+                }
+                _ => return Err(anyhow!("unsupported recurrence part '{key}'")),
+            }
+        }
+
+        rule.frequency = frequency.ok_or_else(|| anyhow!("recurrence lacks a FREQ"))?;
+        if !rule.weekdays.is_empty() && rule.frequency != Frequency::Weekly {
+            return Err(anyhow!("BYDAY is only supported with FREQ=WEEKLY"));
+        }
+        if !rule.month_days.is_empty() && rule.frequency != Frequency::Monthly {
+            return Err(anyhow!("BYMONTHDAY is only supported with FREQ=MONTHLY"));
+        }
+        Ok(rule)
+    }
+}
+
+impl Rule {
+    /// The first occurrence after `after`, counting periods from `anchor`,
+    /// the day of a previous occurrence.
+    pub fn next_after(&self, anchor: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        let mut day = anchor;
+        loop {
+            day = self.next(anchor, day)?;
+            if day > after {
+                return Some(day);
+            }
+        }
+    }
+
+    /// The occurrence following `day`.
+    fn next(&self, anchor: NaiveDate, day: NaiveDate) -> Option<NaiveDate> {
+        let interval = self.interval;
+        match self.frequency {
+            Frequency::Daily => day.checked_add_days(Days::new(interval.into())),
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                day.checked_add_days(Days::new(u64::from(interval) * 7))
+            }
+            Frequency::Weekly => {
+                let week = |day: NaiveDate| day.week(Weekday::Mon).first_day();
+                first_day_after(day, 7 * interval, |candidate| {
+                    let weeks = (week(candidate) - week(anchor)).num_weeks();
+                    self.weekdays.contains(&candidate.weekday())
+                        && weeks % i64::from(interval) == 0
+                })
+            }
+            Frequency::Monthly if self.month_days.is_empty() => {
+                let months = months_between(anchor, day) + interval;
+                anchor.checked_add_months(Months::new(months))
+            }
+            Frequency::Monthly => first_day_after(day, 31 * interval + 31, |candidate| {
+                let last = days_in_month(candidate);
+                let matches = self.month_days.iter().any(|&month_day| {
+                    let month_day = if month_day < 0 { last + month_day + 1 } else { month_day };
+                    month_day == candidate.day() as i32
+                });
+                matches && months_between(anchor, candidate).is_multiple_of(interval)
+            }),
+            Frequency::Yearly => {
+                let months = months_between(anchor, day) + 12 * interval;
+                anchor.checked_add_months(Months::new(months))
+            }
+        }
+    }
+}
+
+/// The first day within `days` after `day` that `matches`.
+fn first_day_after(
+    day: NaiveDate,
+    days: u32,
+    matches: impl Fn(NaiveDate) -> bool,
+) -> Option<NaiveDate> {
+    day.iter_days().skip(1).take(days as usize).find(|candidate| matches(*candidate))
+}
+
+/// Whole calendar months from `from` to `to`, ignoring the day.
+fn months_between(from: NaiveDate, to: NaiveDate) -> u32 {
+    let months = (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32;
+    months.max(0) as u32
+}
+
+fn days_in_month(day: NaiveDate) -> i32 {
+    let first = day.with_day(1).unwrap();
+    let next = first.checked_add_months(Months::new(1)).unwrap_or(first);
+    (next - first).num_days() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn next(rule: &str, anchor: &str, after: &str) -> Option<NaiveDate> {
+        rule.parse::<Rule>().unwrap().next_after(day(anchor), day(after))
+    }
+
+    fn error(rule: &str) -> String {
+        rule.parse::<Rule>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule: Rule = "RRULE:freq=weekly;interval=2;byday=mo,FR".parse().unwrap();
+        assert_eq!(
+            rule,
+            Rule {
+                frequency: Frequency::Weekly,
+                interval: 2,
+                weekdays: vec![Weekday::Mon, Weekday::Fri],
+                month_days: vec![],
+            }
+        );
+        let rule: Rule = "FREQ=MONTHLY;BYMONTHDAY=1,-1".parse().unwrap();
+        assert_eq!(rule.month_days, vec![1, -1]);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let cases = [
+            ("", "invalid recurrence part '', expected KEY=VALUE"),
+            ("INTERVAL=2", "recurrence lacks a FREQ"),
+            ("FREQ=HOURLY", "unsupported FREQ 'HOURLY'"),
+            ("FREQ=DAILY;INTERVAL=0", "invalid INTERVAL '0'"),
+            ("FREQ=DAILY;INTERVAL=-1", "invalid INTERVAL '-1'"),
+            ("FREQ=WEEKLY;BYDAY=XX", "invalid BYDAY 'XX', expected MO to SU"),
+            ("FREQ=WEEKLY;BYDAY=1MO", "invalid BYDAY '1MO', expected MO to SU"),
+            ("FREQ=MONTHLY;BYMONTHDAY=0", "invalid BYMONTHDAY '0'"),
+            ("FREQ=MONTHLY;BYMONTHDAY=32", "invalid BYMONTHDAY '32'"),
+            ("FREQ=DAILY;BYDAY=MO", "BYDAY is only supported with FREQ=WEEKLY"),
+            ("FREQ=YEARLY;BYMONTHDAY=1", "BYMONTHDAY is only supported with FREQ=MONTHLY"),
+            ("FREQ=DAILY;COUNT=3", "unsupported recurrence part 'COUNT'"),
+            ("FREQ=DAILY;", "invalid recurrence part '', expected KEY=VALUE"),
+        ];
+        for (rule, expected) in cases {
+            assert_eq!(error(rule), expected, "{rule}");
+        }
+    }
+
+    #[test]
+    fn daily_and_weekly() {
+        assert_eq!(next("FREQ=DAILY", "2025-01-01", "2025-01-01"), Some(day("2025-01-02")));
+        let every = "FREQ=DAILY;INTERVAL=3";
+        assert_eq!(next(every, "2025-01-01", "2025-01-05"), Some(day("2025-01-07")));
+        assert_eq!(next("FREQ=DAILY", "2025-12-31", "2025-12-31"), Some(day("2026-01-01")));
+        assert_eq!(next("FREQ=WEEKLY", "2025-01-06", "2025-01-06"), Some(day("2025-01-13")));
+        // a monday anchor, on mondays and thursdays
+        let rule = "FREQ=WEEKLY;BYDAY=MO,TH";
+        assert_eq!(next(rule, "2025-01-06", "2025-01-06"), Some(day("2025-01-09")));
+        assert_eq!(next(rule, "2025-01-06", "2025-01-09"), Some(day("2025-01-13")));
+        // every other week counts weeks from the anchor's
+        let rule = "FREQ=WEEKLY;INTERVAL=2;BYDAY=FR";
+        assert_eq!(next(rule, "2025-01-06", "2025-01-06"), Some(day("2025-01-10")));
+        assert_eq!(next(rule, "2025-01-06", "2025-01-10"), Some(day("2025-01-24")));
+    }
+
+    #[test]
+    fn monthly_rolls_over_month_ends() {
+        let rule = "FREQ=MONTHLY";
+        assert_eq!(next(rule, "2025-01-31", "2025-01-31"), Some(day("2025-02-28")));
+        // the anchor's day comes back once months are long enough again
+        assert_eq!(next(rule, "2025-01-31", "2025-02-28"), Some(day("2025-03-31")));
+        assert_eq!(next(rule, "2025-01-31", "2025-03-31"), Some(day("2025-04-30")));
+        assert_eq!(next(rule, "2025-12-15", "2025-12-15"), Some(day("2026-01-15")));
+        let every = "FREQ=MONTHLY;INTERVAL=3";
+        assert_eq!(next(every, "2025-11-30", "2025-11-30"), Some(day("2026-02-28")));
+    }
+
+    #[test]
+    fn monthly_by_day_of_month() {
+        let last = "FREQ=MONTHLY;BYMONTHDAY=-1";
+        assert_eq!(next(last, "2025-01-31", "2025-01-31"), Some(day("2025-02-28")));
+        assert_eq!(next(last, "2024-01-31", "2024-01-31"), Some(day("2024-02-29")));
+        assert_eq!(next(last, "2025-04-30", "2025-04-30"), Some(day("2025-05-31")));
+        let both = "FREQ=MONTHLY;BYMONTHDAY=1,15";
+        assert_eq!(next(both, "2025-01-01", "2025-01-01"), Some(day("2025-01-15")));
+        assert_eq!(next(both, "2025-01-01", "2025-01-15"), Some(day("2025-02-01")));
+        // months without a 31st are skipped
+        let day_31 = "FREQ=MONTHLY;BYMONTHDAY=31";
+        assert_eq!(next(day_31, "2025-01-31", "2025-01-31"), Some(day("2025-03-31")));
+        let every_other = "FREQ=MONTHLY;INTERVAL=2;BYMONTHDAY=10";
+        assert_eq!(next(every_other, "2025-01-10", "2025-01-10"), Some(day("2025-03-10")));
+    }
+
+    #[test]
+    fn yearly_and_leap_days() {
+        let rule = "FREQ=YEARLY";
+        assert_eq!(next(rule, "2024-02-29", "2024-02-29"), Some(day("2025-02-28")));
+        assert_eq!(next(rule, "2024-02-29", "2025-02-28"), Some(day("2026-02-28")));
+        assert_eq!(next(rule, "2024-02-29", "2027-02-28"), Some(day("2028-02-29")));
+        let every = "FREQ=YEARLY;INTERVAL=4";
+        assert_eq!(next(every, "2024-02-29", "2024-02-29"), Some(day("2028-02-29")));
+        assert_eq!(next("FREQ=DAILY", "2024-02-28", "2024-02-28"), Some(day("2024-02-29")));
+    }
+
+    #[test]
+    fn catches_up_from_an_old_anchor() {
+        assert_eq!(next("FREQ=WEEKLY", "2024-01-01", "2025-01-08"), Some(day("2025-01-13")));
+        assert_eq!(next("FREQ=MONTHLY", "2024-01-31", "2025-01-08"), Some(day("2025-01-31")));
+    }
+
+    #[test]
+    fn no_occurrence_past_the_end_of_time() {
+        let last = NaiveDate::MAX.to_string();
+        let before = NaiveDate::MAX.pred_opt().unwrap().to_string();
+        assert_eq!(next("FREQ=DAILY", &before, &last), None);
+    }
+}
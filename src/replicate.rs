@@ -0,0 +1,257 @@
+//! Mirroring the todos that match a query into another collection.
+//!
+//! Replicas get ids of the target collection. Which source id became
+//! which replica is recorded in `replicas.toml` of the source data dir, so
+//! nothing about the source leaks into the mirror. Each run creates
+//! replicas for new matches, rewrites changed ones and removes those that
+//! no longer match.
+
+use crate::archive;
+use crate::config::Config;
+use crate::reservations::Reservations;
+use crate::{load_collection, BlockedOn, Collection, DataId, Status, TodoData, TodoFile};
+use anyhow::anyhow;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const REPLICAS_FILE: &str = "replicas.toml";
+
+/// Space-separated terms that all have to match: `tag:<tag>` and
+/// `status:<status>`, or with `=` instead of `:`. Without a status term
+/// todos of any status match.
+#[derive(Debug, Default)]
+pub struct Query {
+    tags: Vec<String>,
+    status: Option<Status>,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> anyhow::Result<Self> {
+        let mut parsed = Self::default();
+        for term in query.split_whitespace() {
+            match term.split_once([':', '=']) {
+                Some(("tag", tag)) if !tag.is_empty() => parsed.tags.push(tag.to_string()),
+                Some(("status", status)) => {
+                    parsed.status = Some(
+                        Status::from_str(status, true)
+                            .map_err(|_| anyhow!("unknown status '{status}' in query"))?,
+                    )
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "invalid query term '{term}', expected tag:<tag> or status:<status>"
+                    ))
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn matches(&self, todo: &TodoData) -> bool {
+        let front_matter = &todo.front_matter;
+        self.status.is_none_or(|status| front_matter.status == status)
+            && self.tags.iter().all(|tag| front_matter.tags.contains(tag))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct Replicas {
+    #[serde(default)]
+    targets: Vec<Target>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Target {
+    path: String,
+    #[serde(default)]
+    ids: Vec<Replica>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct Replica {
+    source: DataId,
+    replica: DataId,
+}
+
+impl Replicas {
+    async fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(REPLICAS_FILE);
+        if !tokio::fs::try_exists(path.as_path()).await? {
+            return Ok(Self::default());
+        }
+
+        let content = tokio::fs::read_to_string(path.as_path()).await?;
+        toml::from_str(content.as_str()).map_err(|err| anyhow!("invalid {REPLICAS_FILE}: {err}"))
+    }
+
+    async fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(data_dir.join(REPLICAS_FILE), toml::to_string(self)?).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Created,
+    Updated,
+    Unchanged,
+    Removed,
+}
+
+impl Outcome {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Outcome::Created => "created",
+            Outcome::Updated => "updated",
+            Outcome::Unchanged => "unchanged",
+            Outcome::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Replicated {
+    pub source: DataId,
+    pub replica: DataId,
+    pub outcome: Outcome,
+}
+
+/// Brings the replicas in the collection at `target_dir` in line with the
+/// todos of `collection` matching `query`.
+pub async fn run(
+    data_dir: &Path,
+    collection: &Collection,
+    query: &Query,
+    target_dir: &Path,
+) -> anyhow::Result<Vec<Replicated>> {
+    let target_dir = target_dir
+        .canonicalize()
+        .map_err(|err| anyhow!("cannot replicate to {}: {err}", target_dir.display()))?;
+    if target_dir == data_dir.canonicalize()? {
+        return Err(anyhow!("cannot replicate a collection into itself"));
+    }
+    let target_tasks = target_dir.join("tasks");
+    if !tokio::fs::try_exists(target_tasks.as_path()).await? {
+        return Err(anyhow!(
+            "{} is not a todo collection, run `todo init` there first",
+            target_dir.display()
+        ));
+    }
+    let target_config = Config::load(&target_dir).await?;
+    let target_collection = load_collection(&target_dir).await?;
+    let target_reservations = Reservations::load(&target_dir).await?;
+    let target_archived = archive::max_id(&target_dir).await?;
+
+    let mut replicas = Replicas::load(data_dir).await?;
+    let key = target_dir.to_string_lossy().into_owned();
+    let previous = replicas
+        .targets
+        .iter()
+        .position(|target| target.path == key)
+        .map(|index| replicas.targets.remove(index).ids)
+        .unwrap_or_default();
+    let replica_of = |source: DataId| {
+        previous
+            .iter()
+            .find(|replica| replica.source == source)
+            .map(|replica| replica.replica)
+    };
+
+    // assign every id first, so references between replicas can be mapped
+    let mut next_id = target_collection
+        .keys()
+        .copied()
+        .chain(target_reservations.max())
+        .chain(target_archived)
+        .chain(previous.iter().map(|replica| replica.replica))
+        .max()
+        .map_or(1, |last| last + 1);
+    let mut ids = vec![];
+    for todo in collection.values().filter(|todo| query.matches(&todo.data)) {
+        let source = todo.data.front_matter.id;
+        let replica = replica_of(source).unwrap_or_else(|| {
+            next_id += 1;
+            next_id - 1
+        });
+        ids.push(Replica { source, replica });
+    }
+
+    let mut replicated = vec![];
+    for &Replica { source, replica } in &ids {
+        let data = &collection[&source].data;
+        let mut front_matter = data.front_matter.clone();
+        front_matter.id = replica;
+        front_matter.parent = front_matter
+            .parent
+            .and_then(|other| ids.iter().find(|ids| ids.source == other).map(|ids| ids.replica));
+        front_matter.depends_on = front_matter
+            .depends_on
+            .iter()
+            .filter_map(|other| ids.iter().find(|ids| ids.source == *other).map(|ids| ids.replica))
+            .collect();
+        front_matter.blocked_by.retain_mut(|blocker| match &mut blocker.on {
+            BlockedOn::Todo(other) => {
+                let replica = ids.iter().find(|ids| ids.source == *other);
+                replica.inspect(|ids| *other = ids.replica).is_some()
+            }
+            BlockedOn::External(_) => true,
+        });
+        if let Some(cancellation) = front_matter.cancellation.as_mut() {
+            cancellation.superseded_by = cancellation.superseded_by.and_then(|other| {
+                ids.iter().find(|ids| ids.source == other).map(|ids| ids.replica)
+            });
+        }
+        let todo_file = TodoFile::new_from_data(
+            target_tasks.as_path(),
+            &target_config.ids,
+            TodoData {
+                front_matter,
+                content: data.content.clone(),
+                style: target_config.files.front_matter,
+            },
+        );
+
+        let existing = target_collection.get(&replica);
+        let outcome = match existing {
+            None => Outcome::Created,
+            Some(existing) if existing.data.to_bytes() == todo_file.data.to_bytes() => {
+                Outcome::Unchanged
+            }
+            Some(_) => Outcome::Updated,
+        };
+        // the file name changes with the target's id format
+        let moved = existing.filter(|existing| existing.path != todo_file.path);
+        if let Some(existing) = moved {
+            tokio::fs::remove_file(existing.path.as_path()).await?;
+        }
+        if outcome != Outcome::Unchanged || moved.is_some() {
+            todo_file.write_file().await?;
+        }
+        replicated.push(Replicated {
+            source,
+            replica,
+            outcome,
+        });
+    }
+
+    for &Replica { source, replica } in &previous {
+        if ids.iter().any(|ids| ids.source == source) {
+            continue;
+        }
+        if let Some(existing) = target_collection.get(&replica) {
+            tokio::fs::remove_file(existing.path.as_path()).await?;
+            replicated.push(Replicated {
+                source,
+                replica,
+                outcome: Outcome::Removed,
+            });
+        }
+    }
+
+    if !ids.is_empty() {
+        replicas.targets.push(Target { path: key, ids });
+    }
+    replicas.write(data_dir).await?;
+    Ok(replicated)
+}
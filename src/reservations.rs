@@ -0,0 +1,60 @@
+//! Ids handed out ahead of time, so documents can reference a task number
+//! before the task exists. Reserved ids are skipped when allocating new
+//! ones until `new --id` consumes them.
+
+use crate::DataId;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const RESERVATIONS_FILE: &str = "reservations.toml";
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Reservations {
+    #[serde(default)]
+    pub reserved: Vec<Reservation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Reservation {
+    pub id: DataId,
+    pub reserved_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Reservations {
+    pub async fn load(data_dir: &Path) -> anyhow::Result<Self> {
+        let path = data_dir.join(RESERVATIONS_FILE);
+        if !tokio::fs::try_exists(path.as_path()).await? {
+            return Ok(Self::default());
+        }
+
+        let content = tokio::fs::read_to_string(path.as_path()).await?;
+        toml::from_str(content.as_str())
+            .map_err(|err| anyhow!("invalid {RESERVATIONS_FILE}: {err}"))
+    }
+
+    pub async fn write(&self, data_dir: &Path) -> anyhow::Result<()> {
+        tokio::fs::write(data_dir.join(RESERVATIONS_FILE), toml::to_string(self)?).await?;
+        Ok(())
+    }
+
+    pub fn max(&self) -> Option<DataId> {
+        self.reserved.iter().map(|reservation| reservation.id).max()
+    }
+
+    /// Reserves `count` consecutive ids starting at `first`.
+    pub fn reserve(&mut self, first: DataId, count: u32) -> Vec<DataId> {
+        let reserved_at = chrono::Utc::now();
+        let ids: Vec<_> = (first..first + count).collect();
+        self.reserved
+            .extend(ids.iter().map(|&id| Reservation { id, reserved_at }));
+        ids
+    }
+
+    /// Removes the reservation for `id`, returning whether there was one.
+    pub fn consume(&mut self, id: DataId) -> bool {
+        let before = self.reserved.len();
+        self.reserved.retain(|reservation| reservation.id != id);
+        self.reserved.len() != before
+    }
+}
@@ -0,0 +1,153 @@
+//! A throwaway copy of a collection for trying out changes.
+//!
+//! `sandbox open` copies the collection into the system's temp dir, where
+//! every command works as usual through `--data-dir`. `sandbox apply`
+//! moves its todos, archive and reservations back in place of the real
+//! ones, `sandbox discard` drops them. Templates and config changes stay
+//! in the sandbox.
+
+use crate::fsck::{self, Manifest};
+use crate::reservations::RESERVATIONS_FILE;
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Written into the sandbox to tell whether the collection changed since.
+const ORIGIN_FILE: &str = "sandbox.toml";
+/// What `apply` replaces in the real collection.
+const APPLIED: &[&str] = &["tasks", crate::archive::ARCHIVE_DIR, RESERVATIONS_FILE];
+/// Where `apply` stages the sandbox's files before swapping them in.
+const STAGING_DIR: &str = ".sandbox-apply";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Origin {
+    /// The collection as it was when the sandbox was opened.
+    base: Manifest,
+}
+
+/// The sandbox of the collection at `data_dir`, one per collection.
+pub fn dir_for(data_dir: &Path) -> PathBuf {
+    let digest = format!("{:x}", Sha256::digest(data_dir.as_os_str().as_encoded_bytes()));
+    std::env::temp_dir().join(format!("todo-sandbox-{}", &digest[..16]))
+}
+
+/// Copies the collection into a new sandbox and returns its data dir.
+pub async fn open(data_dir: &Path) -> anyhow::Result<PathBuf> {
+    let sandbox = dir_for(data_dir);
+    if tokio::fs::try_exists(sandbox.as_path()).await? {
+        return Err(anyhow!(
+            "a sandbox is already open at {}, apply or discard it first",
+            sandbox.display()
+        ));
+    }
+
+    let mut files = fsck::tracked_files(data_dir).await?;
+    if tokio::fs::try_exists(data_dir.join(RESERVATIONS_FILE)).await? {
+        files.push(PathBuf::from(RESERVATIONS_FILE));
+    }
+    for file in files {
+        copy(&data_dir.join(file.as_path()), &sandbox.join(file.as_path())).await?;
+    }
+    // an empty collection still needs its tasks dir to be one
+    tokio::fs::create_dir_all(sandbox.join("tasks")).await?;
+
+    let origin = Origin {
+        base: Manifest::build(data_dir).await?,
+    };
+    tokio::fs::write(sandbox.join(ORIGIN_FILE), toml::to_string(&origin)?).await?;
+    Ok(sandbox)
+}
+
+/// The data dir of the open sandbox.
+pub async fn find(data_dir: &Path) -> anyhow::Result<PathBuf> {
+    let sandbox = dir_for(data_dir);
+    if !tokio::fs::try_exists(sandbox.join(ORIGIN_FILE)).await? {
+        return Err(anyhow!("no sandbox is open, run `todo sandbox open` first"));
+    }
+    Ok(sandbox)
+}
+
+/// Replaces the todos, archive and reservations of the collection with the
+/// sandbox's and removes the sandbox. Refuses when the collection changed
+/// since the sandbox was opened. Everything is copied next to the real
+/// files first and then swapped in by renames, so a failed copy leaves the
+/// collection untouched.
+pub async fn apply(data_dir: &Path) -> anyhow::Result<()> {
+    let sandbox = find(data_dir).await?;
+    let content = tokio::fs::read_to_string(sandbox.join(ORIGIN_FILE)).await?;
+    let origin: Origin =
+        toml::from_str(content.as_str()).map_err(|err| anyhow!("invalid {ORIGIN_FILE}: {err}"))?;
+    let changed = origin.base.diff(&Manifest::build(data_dir).await?);
+    if let Some(first) = changed.first() {
+        return Err(anyhow!(
+            "the collection changed since the sandbox was opened ({} {}), \
+             discard the sandbox and open a new one",
+            first.kind(),
+            first.path()
+        ));
+    }
+
+    let staging = data_dir.join(STAGING_DIR);
+    if tokio::fs::try_exists(staging.as_path()).await? {
+        tokio::fs::remove_dir_all(staging.as_path()).await?;
+    }
+    let (new, old) = (staging.join("new"), staging.join("old"));
+    for part in APPLIED {
+        copy_tree(&sandbox.join(part), &new.join(part)).await?;
+    }
+    tokio::fs::create_dir_all(old.as_path()).await?;
+    for part in APPLIED {
+        let real = data_dir.join(part);
+        if tokio::fs::try_exists(real.as_path()).await? {
+            tokio::fs::rename(real.as_path(), old.join(part)).await?;
+        }
+        if tokio::fs::try_exists(new.join(part)).await? {
+            tokio::fs::rename(new.join(part), real.as_path()).await?;
+        }
+    }
+
+    tokio::fs::remove_dir_all(staging.as_path()).await?;
+    tokio::fs::remove_dir_all(sandbox.as_path()).await?;
+    Ok(())
+}
+
+/// Removes the sandbox without touching the collection.
+pub async fn discard(data_dir: &Path) -> anyhow::Result<()> {
+    let sandbox = find(data_dir).await?;
+    tokio::fs::remove_dir_all(sandbox.as_path()).await?;
+    Ok(())
+}
+
+/// Copies a file or a directory with everything below it, if it exists.
+async fn copy_tree(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if tokio::fs::metadata(from).await.is_ok_and(|metadata| metadata.is_file()) {
+        return copy(from, to).await;
+    }
+    let mut pending = vec![PathBuf::new()];
+    while let Some(path) = pending.pop() {
+        let source = from.join(path.as_path());
+        let Ok(metadata) = tokio::fs::metadata(source.as_path()).await else {
+            continue;
+        };
+        if metadata.is_file() {
+            copy(&source, &to.join(path.as_path())).await?;
+            continue;
+        }
+
+        tokio::fs::create_dir_all(to.join(path.as_path())).await?;
+        let mut dir = tokio::fs::read_dir(source.as_path()).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            pending.push(path.join(entry.file_name()));
+        }
+    }
+    Ok(())
+}
+
+async fn copy(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::copy(from, to).await?;
+    Ok(())
+}
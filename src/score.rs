@@ -0,0 +1,39 @@
+//! How urgent an actionable todo is, for `next` to pick one.
+//!
+//! Each factor is scaled to 0..=1 and weighted by the `[next]` section of
+//! the config:
+//!
+//! - due: 1 once due or overdue, halving with every day further out
+//! - priority: 1 for high, 0.6 for medium, 0.3 for low, 0 for none
+//! - age: 1 for todos a month old or older, less for newer ones
+//! - dependents: 1 for todos three or more open todos depend on
+
+use crate::config::NextWeights;
+use crate::{Priority, TodoData};
+use chrono::{DateTime, Utc};
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+/// The weighted sum of all factors. `dependents` is the number of open
+/// todos depending on this one.
+pub fn score(todo: &TodoData, dependents: usize, now: DateTime<Utc>, weights: &NextWeights) -> f64 {
+    let front_matter = &todo.front_matter;
+    let due = front_matter.due_at.map_or(0.0, |due_at| {
+        let days_left = (due_at - now).num_seconds() as f64 / SECONDS_PER_DAY;
+        0.5_f64.powf(days_left.max(0.0))
+    });
+    let priority = match front_matter.priority {
+        Some(Priority::High) => 1.0,
+        Some(Priority::Medium) => 0.6,
+        Some(Priority::Low) => 0.3,
+        None => 0.0,
+    };
+    let age_days = (now - front_matter.created_at).num_seconds() as f64 / SECONDS_PER_DAY;
+    let age = (age_days / 30.0).clamp(0.0, 1.0);
+    let dependents = (dependents as f64 / 3.0).min(1.0);
+
+    weights.due * due
+        + weights.priority * priority
+        + weights.age * age
+        + weights.dependents * dependents
+}
@@ -0,0 +1,154 @@
+//! Picking the todos a bulk command works on: ids and ranges like
+//! `3 7 9-14` or `3,7`, todos matching a filter like `tag=backend`, or both.
+
+use crate::config::IdFormat;
+use crate::replicate::Query;
+use crate::{Collection, DataId};
+use anyhow::anyhow;
+
+/// Ranges larger than this are most likely a typo.
+const MAX_RANGE: DataId = 10_000;
+
+/// Parses ids and inclusive ranges, keeping their order and dropping
+/// repeats.
+pub fn parse_ids(inputs: &[String], format: &IdFormat) -> anyhow::Result<Vec<DataId>> {
+    let mut ids = vec![];
+    for part in inputs.iter().flat_map(|input| input.split(',')) {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        let (first, last) = match format.parse(part) {
+            Ok(id) => (id, id),
+            Err(err) => parse_range(part, format).ok_or(err)?,
+        };
+        if first > last {
+            return Err(anyhow!("range '{part}' runs backwards"));
+        }
+        if last - first >= MAX_RANGE {
+            return Err(anyhow!("range '{part}' spans more than {MAX_RANGE} ids"));
+        }
+        for id in first..=last {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Splits `start-end` at whichever dash leaves an id on both sides, as the
+/// configured prefix may contain dashes itself.
+fn parse_range(part: &str, format: &IdFormat) -> Option<(DataId, DataId)> {
+    part.match_indices('-').find_map(|(at, _)| {
+        let first = format.parse(&part[..at]).ok()?;
+        let last = format.parse(&part[at + 1..]).ok()?;
+        Some((first, last))
+    })
+}
+
+/// The listed ids followed by the todos matching `filter` that aren't
+/// listed, in id order.
+pub fn select(
+    collection: &Collection,
+    mut ids: Vec<DataId>,
+    filter: Option<&Query>,
+) -> Vec<DataId> {
+    if let Some(filter) = filter {
+        for todo in collection.values().filter(|todo| filter.matches(&todo.data)) {
+            if !ids.contains(&todo.data.front_matter.id) {
+                ids.push(todo.data.front_matter.id);
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TodoData, TodoFile};
+    use std::str::FromStr;
+
+    fn ids(inputs: &[&str], format: &IdFormat) -> anyhow::Result<Vec<DataId>> {
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        parse_ids(&inputs, format)
+    }
+
+    fn error(inputs: &[&str]) -> String {
+        ids(inputs, &IdFormat::default()).unwrap_err().to_string()
+    }
+
+    fn prefixed() -> IdFormat {
+        IdFormat { prefix: "T-".to_string(), ..IdFormat::default() }
+    }
+
+    #[test]
+    fn parses_ids_and_ranges() {
+        let plain = IdFormat::default();
+        assert_eq!(ids(&["3", "7", "9-11"], &plain).unwrap(), [3, 7, 9, 10, 11]);
+        assert_eq!(ids(&["3,7", " 9 , 1"], &plain).unwrap(), [3, 7, 9, 1]);
+        assert_eq!(ids(&["#4", "0x10"], &plain).unwrap(), [4, 16]);
+        assert_eq!(ids(&["5-5"], &plain).unwrap(), [5]);
+        assert_eq!(ids(&["3,,", ","], &plain).unwrap(), [3]);
+        assert!(ids(&[], &plain).unwrap().is_empty());
+    }
+
+    #[test]
+    fn drops_repeats_keeping_the_first() {
+        let plain = IdFormat::default();
+        assert_eq!(ids(&["4", "2-5", "3", "1"], &plain).unwrap(), [4, 2, 3, 5, 1]);
+    }
+
+    #[test]
+    fn ranges_with_dashes_in_the_prefix() {
+        let format = prefixed();
+        assert_eq!(ids(&["T-3"], &format).unwrap(), [3]);
+        assert_eq!(ids(&["T-3-T-5"], &format).unwrap(), [3, 4, 5]);
+        assert_eq!(ids(&["T-3-5", "t-7"], &format).unwrap(), [3, 4, 5, 7]);
+        assert_eq!(
+            ids(&["T-3-T-"], &format).unwrap_err().to_string(),
+            "'T-3-T-' is not a valid id, expected e.g. T-42"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(error(&["5-3"]), "range '5-3' runs backwards");
+        assert_eq!(error(&["1-10001"]), "range '1-10001' spans more than 10000 ids");
+        assert_eq!(ids(&["1-10000"], &IdFormat::default()).unwrap().len(), 10_000);
+        let invalid = |input: &str| format!("'{input}' is not a valid id, expected e.g. 42");
+        assert_eq!(error(&["abc"]), invalid("abc"));
+        assert_eq!(error(&["3", "-3"]), invalid("-3"));
+        assert_eq!(error(&["3-"]), invalid("3-"));
+        assert_eq!(error(&["1-2-3"]), invalid("1-2-3"));
+        assert_eq!(error(&["2,x"]), invalid("x"));
+    }
+
+    fn collection() -> Collection {
+        [(1, "backend"), (2, "frontend"), (3, "backend")]
+            .into_iter()
+            .map(|(id, tag)| {
+                let text = format!(
+                    "+++\nid = {id}\ncreated_at = \"2025-01-01T09:00:00Z\"\n\
+                     tags = [\"{tag}\"]\n+++\n\n# Todo {id}\n"
+                );
+                let data = TodoData::from_str(&text).unwrap();
+                (id, TodoFile { path: format!("{id}.md").into(), data })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn selects_listed_then_matching_todos() {
+        let collection = collection();
+        let backend = Query::parse("tag=backend").unwrap();
+        assert_eq!(select(&collection, vec![2], None), [2]);
+        assert_eq!(select(&collection, vec![], Some(&backend)), [1, 3]);
+        assert_eq!(select(&collection, vec![3, 2], Some(&backend)), [3, 2, 1]);
+        // listed ids are kept even when they are not in the collection
+        assert_eq!(select(&collection, vec![9], Some(&backend)), [9, 1, 3]);
+        let nothing = Query::parse("tag=nothing").unwrap();
+        assert!(select(&collection, vec![], Some(&nothing)).is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+//! Capturing a todo in one line, like
+//! `Call the dentist tomorrow #health !p2`.
+//!
+//! Words starting with `#` are tags, `!p1` to `!p3` set a high, medium or
+//! low priority and the last phrase `dates::parse` understands, like
+//! `tomorrow`, `next fri` or `in 3 days`, is the due date. What is left is
+//! the title.
+
+use crate::{dates, Priority};
+use chrono::{DateTime, Local, Utc};
+
+/// The longest date phrase looked for, as in `in 3 days`.
+const LONGEST_PHRASE: usize = 3;
+
+#[derive(Debug, Default)]
+pub struct Shorthand {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due_at: Option<DateTime<Utc>>,
+    pub priority: Option<Priority>,
+}
+
+pub fn parse(text: &str, now: DateTime<Local>) -> Shorthand {
+    let mut shorthand = Shorthand::default();
+    let mut words = vec![];
+    for word in text.split_whitespace() {
+        if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            if !shorthand.tags.iter().any(|known| known == tag) {
+                shorthand.tags.push(tag.to_string());
+            }
+            continue;
+        }
+        let priority = match word.to_lowercase().as_str() {
+            "!p1" => Some(Priority::High),
+            "!p2" => Some(Priority::Medium),
+            "!p3" => Some(Priority::Low),
+            _ => None,
+        };
+        match priority {
+            Some(_) => shorthand.priority = priority,
+            None => words.push(word),
+        }
+    }
+
+    if let Some((start, len, due_at)) = last_date(&words, now) {
+        shorthand.due_at = Some(due_at);
+        words.drain(start..start + len);
+    }
+    shorthand.title = words.join(" ");
+    shorthand
+}
+
+/// Where the last date phrase in `words` starts, how many words it takes
+/// and its date, preferring longer phrases ending on the same word.
+fn last_date(words: &[&str], now: DateTime<Local>) -> Option<(usize, usize, DateTime<Utc>)> {
+    (1..=words.len()).rev().find_map(|end| {
+        (1..=LONGEST_PHRASE.min(end)).rev().find_map(|len| {
+            let phrase = words[end - len..end].join(" ");
+            let due_at = dates::parse(phrase.as_str(), now).ok()?;
+            Some((end - len, len, due_at))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    /// A friday.
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 1, 3, 12, 0, 0).unwrap()
+    }
+
+    fn due(day: &str) -> Option<DateTime<Utc>> {
+        let day = NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap();
+        Some(dates::end_of_day(day).unwrap())
+    }
+
+    /// Text, then the title, tags, due day and priority parsed from it.
+    type Case<'a> = (&'a str, &'a str, &'a [&'a str], Option<&'a str>, Option<Priority>);
+
+    #[test]
+    fn parses_captures() {
+        let cases: [Case; 18] = [
+            (
+                "Call the dentist tomorrow #health !p2",
+                "Call the dentist",
+                &["health"],
+                Some("2025-01-04"),
+                Some(Priority::Medium),
+            ),
+            ("Plain title", "Plain title", &[], None, None),
+            ("", "", &[], None, None),
+            // `#` inside a word or on its own is text
+            ("Learn C# and F#", "Learn C# and F#", &[], None, None),
+            ("Reply to issue#12 #", "Reply to issue#12 #", &[], None, None),
+            ("#a #b #a Tidy up #b", "Tidy up", &["a", "b"], None, None),
+            ("#Work #work", "", &["Work", "work"], None, None),
+            // `!` is only a priority as a whole !p1 to !p3
+            ("Ship it!", "Ship it!", &[], None, None),
+            ("Wow!p2 !p4 !p !", "Wow!p2 !p4 !p !", &[], None, None),
+            ("Deploy !P1", "Deploy", &[], None, Some(Priority::High)),
+            ("Decide !p1 !p3", "Decide", &[], None, Some(Priority::Low)),
+            // date phrases without their value are text
+            ("Done in 3", "Done in 3", &[], None, None),
+            ("Read in days", "Read in days", &[], None, None),
+            ("Plan next", "Plan next", &[], None, None),
+            ("Pay rent in 3 days", "Pay rent", &[], Some("2025-01-06"), None),
+            // the last date phrase wins, earlier ones stay in the title
+            (
+                "Move friday meeting to next mon",
+                "Move friday meeting to",
+                &[],
+                Some("2025-01-06"),
+                None,
+            ),
+            ("Call tomorrow about taxes", "Call about taxes", &[], Some("2025-01-04"), None),
+            (
+                "File taxes 2025-04-30 #finance",
+                "File taxes",
+                &["finance"],
+                Some("2025-04-30"),
+                None,
+            ),
+        ];
+        for (text, title, tags, due_day, priority) in cases {
+            let shorthand = parse(text, now());
+            assert_eq!(shorthand.title, title, "{text}");
+            assert_eq!(shorthand.tags, tags, "{text}");
+            assert_eq!(shorthand.due_at, due_day.and_then(due), "{text}");
+            assert_eq!(shorthand.priority, priority, "{text}");
+        }
+    }
+
+    #[test]
+    fn collapses_whitespace() {
+        assert_eq!(parse("  Fix \t the   gate  ", now()).title, "Fix the gate");
+    }
+}
@@ -0,0 +1,108 @@
+//! Pulling a shared template set from a git repository.
+//!
+//! The remote's files land in `templates/managed/`, which is replaced on
+//! every sync and should not be edited by hand. A local template with the
+//! same name as a managed one takes precedence, see `init_hbs`.
+
+use crate::config::TemplateSource;
+use crate::git;
+use anyhow::anyhow;
+use std::path::Path;
+
+pub const MANAGED_DIR: &str = "managed";
+
+/// Scratch directory the remote is cloned into, below the data dir.
+const CLONE_DIR: &str = ".templates-sync";
+
+#[derive(Debug)]
+pub struct Synced {
+    pub url: String,
+    pub revision: String,
+    pub files: usize,
+}
+
+/// Replaces `templates/managed/` with the files of the configured source.
+/// The previous set is restored when the new one fails to load.
+pub async fn pull(data_dir: &Path, source: &TemplateSource) -> anyhow::Result<Synced> {
+    let url = source.source.as_deref().ok_or_else(|| {
+        anyhow!("no template source configured, set [templates] source in config.toml")
+    })?;
+
+    let clone_dir = data_dir.join(CLONE_DIR);
+    remove_dir_if_exists(&clone_dir)?;
+    let result = fetch_into_managed(data_dir, &clone_dir, url, source.rev.as_deref()).await;
+    remove_dir_if_exists(&clone_dir)?;
+    result
+}
+
+async fn fetch_into_managed(
+    data_dir: &Path,
+    clone_dir: &Path,
+    url: &str,
+    rev: Option<&str>,
+) -> anyhow::Result<Synced> {
+    let target = clone_dir.to_str().ok_or_else(|| anyhow!("data dir is not valid UTF-8"))?;
+    let mut args = vec!["clone", "--quiet", "--depth", "1"];
+    if let Some(rev) = rev {
+        args.extend(["--branch", rev]);
+    }
+    args.extend(["--", url, target]);
+    git::run(data_dir, &args).await?;
+    let revision = git::run(clone_dir, &["rev-parse", "HEAD"]).await?.trim().to_string();
+
+    let templates_dir = data_dir.join("templates");
+    let managed = templates_dir.join(MANAGED_DIR);
+    let staged = templates_dir.join(format!("{MANAGED_DIR}.new"));
+    let previous = templates_dir.join(format!("{MANAGED_DIR}.old"));
+    remove_dir_if_exists(&staged)?;
+    remove_dir_if_exists(&previous)?;
+
+    let files = copy_tree(clone_dir, &staged)?;
+    if managed.exists() {
+        std::fs::rename(&managed, &previous)?;
+    }
+    std::fs::rename(&staged, &managed)?;
+
+    if let Err(err) = crate::init_hbs(data_dir) {
+        remove_dir_if_exists(&managed)?;
+        if previous.exists() {
+            std::fs::rename(&previous, &managed)?;
+        }
+        return Err(anyhow!("templates from {url} do not load, kept the previous set: {err}"));
+    }
+    remove_dir_if_exists(&previous)?;
+
+    Ok(Synced {
+        url: url.to_string(),
+        revision,
+        files,
+    })
+}
+
+/// Copies regular files and directories, skipping `.git` and symlinks so
+/// a remote can't smuggle in references to files outside the collection.
+fn copy_tree(from: &Path, to: &Path) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(to)?;
+    let mut files = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if entry.file_name() == ".git" || file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            files += copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), to.join(entry.file_name()))?;
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+fn remove_dir_if_exists(dir: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
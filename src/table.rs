@@ -0,0 +1,29 @@
+//! Plain-text tables for human output.
+
+use std::fmt::Write;
+
+/// Left-aligns every column to its widest cell, two spaces apart. The
+/// last column is not padded so lines carry no trailing whitespace.
+pub fn render(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let headers: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+    for row in std::iter::once(&headers).chain(rows) {
+        let mut line = String::new();
+        for (i, (cell, width)) in row.iter().zip(&widths).enumerate() {
+            if i + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                write!(line, "{cell:<width$}  ").unwrap();
+            }
+        }
+        writeln!(out, "{}", line.trim_end()).unwrap();
+    }
+    out
+}
@@ -0,0 +1,59 @@
+//! Time tracked on todos with `start` and `stop`, summed up per todo and
+//! per tag.
+
+use crate::{Collection, DataId, TimeEntry};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Todos with tracked time, in id order.
+    pub todos: Vec<(DataId, TimeDelta)>,
+    /// A todo's time counts towards each of its tags.
+    pub tags: BTreeMap<String, TimeDelta>,
+    pub total: TimeDelta,
+}
+
+/// Sums up the time tracked since `since`, or ever. An entry still running
+/// counts up to `now`, and only the part of an entry after `since` counts.
+pub fn report(
+    collection: &Collection,
+    since: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Report {
+    let mut report = Report::default();
+    for todo in collection.values() {
+        let front_matter = &todo.data.front_matter;
+        let tracked = front_matter
+            .time_entries
+            .iter()
+            .map(|entry| tracked(entry, since, now))
+            .fold(TimeDelta::zero(), |sum, tracked| sum + tracked);
+        if tracked.is_zero() {
+            continue;
+        }
+
+        report.todos.push((front_matter.id, tracked));
+        for tag in &front_matter.tags {
+            *report.tags.entry(tag.clone()).or_default() += tracked;
+        }
+        report.total += tracked;
+    }
+    report
+}
+
+/// The time within an entry after `since`.
+pub fn tracked(entry: &TimeEntry, since: Option<DateTime<Utc>>, now: DateTime<Utc>) -> TimeDelta {
+    let start = since.map_or(entry.start, |since| since.max(entry.start));
+    let end = entry.end.unwrap_or(now);
+    (end - start).max(TimeDelta::zero())
+}
+
+/// Hours and minutes, like `2h 05m`, or just minutes below an hour.
+pub fn format(duration: TimeDelta) -> String {
+    let minutes = duration.num_minutes();
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h {minutes:02}m"),
+    }
+}
@@ -0,0 +1,236 @@
+//! Conversion between Trello board exports and todos.
+//!
+//! Cards become todos, labels become tags and each card's list is kept as
+//! a `list:<name>` tag, so exporting an imported board restores its lists.
+//! Checklists are written into the body as `## <checklist>` sections of
+//! checkbox items and parsed back from there on export.
+
+use crate::checklist;
+use crate::{Collection, Status};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Tag prefix recording which Trello list a card was on.
+pub const LIST_TAG_PREFIX: &str = "list:";
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub lists: Vec<List>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    #[serde(default)]
+    pub cards: Vec<Card>,
+    #[serde(default)]
+    pub checklists: Vec<Checklist>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Label {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Card {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    pub id_list: String,
+    #[serde(default)]
+    pub id_labels: Vec<String>,
+    #[serde(default)]
+    pub due: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Checklist {
+    pub id: String,
+    pub id_card: String,
+    pub name: String,
+    #[serde(default)]
+    pub check_items: Vec<CheckItem>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckItem {
+    pub name: String,
+    /// `complete` or `incomplete`
+    pub state: String,
+    #[serde(default)]
+    pub pos: f64,
+}
+
+/// A todo to be created from a card, before it has an id.
+#[derive(Debug)]
+pub struct Draft {
+    pub title: String,
+    pub tags: Vec<String>,
+    pub due_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub body: String,
+}
+
+impl Board {
+    /// Converts all open cards on open lists into drafts, in board order.
+    pub fn into_drafts(self) -> Vec<Draft> {
+        let lists: BTreeMap<_, _> = self
+            .lists
+            .iter()
+            .filter(|list| !list.closed)
+            .map(|list| (list.id.as_str(), list.name.as_str()))
+            .collect();
+        let labels: BTreeMap<_, _> = self
+            .labels
+            .iter()
+            .map(|label| (label.id.as_str(), label))
+            .collect();
+
+        let mut drafts = vec![];
+        for card in self.cards.iter().filter(|card| !card.closed) {
+            let Some(list) = lists.get(card.id_list.as_str()) else {
+                continue;
+            };
+
+            let mut tags = vec![format!("{LIST_TAG_PREFIX}{list}")];
+            for label in card.id_labels.iter().filter_map(|id| labels.get(id.as_str())) {
+                let tag = match (label.name.as_str(), label.color.as_deref()) {
+                    ("", Some(color)) => color,
+                    (name, _) => name,
+                };
+                if !tag.is_empty() && !tags.iter().any(|known| known == tag) {
+                    tags.push(tag.to_string());
+                }
+            }
+
+            let mut body = card.desc.trim().to_string();
+            for checklist in self.checklists.iter().filter(|c| c.id_card == card.id) {
+                let mut items: Vec<_> = checklist.check_items.iter().collect();
+                items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+
+                let checklist = checklist::Checklist {
+                    name: checklist.name.clone(),
+                    items: items
+                        .into_iter()
+                        .map(|item| checklist::Item {
+                            done: item.state == "complete",
+                            text: item.name.clone(),
+                        })
+                        .collect(),
+                };
+                body.push_str("\n\n");
+                body.push_str(checklist.to_markdown().as_str());
+            }
+
+            drafts.push(Draft {
+                title: card.name.clone(),
+                tags,
+                due_at: card.due,
+                body: body.trim().to_string(),
+            });
+        }
+        drafts
+    }
+
+    /// Builds a board from the collection, one list per `list:` tag plus
+    /// a default list for todos without one.
+    pub fn from_collection(name: &str, collection: &Collection) -> Self {
+        let mut board = Board {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let mut list_ids = BTreeMap::new();
+        let mut label_ids = BTreeMap::new();
+
+        for todo in collection.values() {
+            let front_matter = &todo.data.front_matter;
+            let list = front_matter
+                .tags
+                .iter()
+                .find_map(|tag| tag.strip_prefix(LIST_TAG_PREFIX))
+                .unwrap_or("Todo");
+            let id_list = list_ids
+                .entry(list.to_string())
+                .or_insert_with(|| {
+                    let id = format!("list-{}", board.lists.len() + 1);
+                    board.lists.push(List {
+                        id: id.clone(),
+                        name: list.to_string(),
+                        closed: false,
+                    });
+                    id
+                })
+                .clone();
+
+            let mut id_labels = vec![];
+            for tag in &front_matter.tags {
+                if tag.starts_with(LIST_TAG_PREFIX) {
+                    continue;
+                }
+                let id = label_ids.entry(tag.clone()).or_insert_with(|| {
+                    let id = format!("label-{}", board.labels.len() + 1);
+                    board.labels.push(Label {
+                        id: id.clone(),
+                        name: tag.clone(),
+                        color: None,
+                    });
+                    id
+                });
+                id_labels.push(id.clone());
+            }
+
+            let card_id = format!("card-{}", front_matter.id);
+            let (desc, checklists) = checklist::split(todo.data.body());
+            for checklist in checklists {
+                board.checklists.push(Checklist {
+                    id: format!("checklist-{}", board.checklists.len() + 1),
+                    id_card: card_id.clone(),
+                    name: checklist.name,
+                    check_items: checklist
+                        .items
+                        .into_iter()
+                        .enumerate()
+                        .map(|(pos, item)| CheckItem {
+                            name: item.text,
+                            state: if item.done { "complete" } else { "incomplete" }.to_string(),
+                            pos: pos as f64,
+                        })
+                        .collect(),
+                });
+            }
+
+            board.cards.push(Card {
+                id: card_id,
+                name: todo.data.title().unwrap_or_default().to_string(),
+                desc,
+                id_list,
+                id_labels,
+                due: front_matter.due_at,
+                closed: front_matter.status == Status::Cancelled,
+            });
+        }
+
+        board
+    }
+}
@@ -0,0 +1,197 @@
+//! Checking todo files before they are committed.
+//!
+//! Meant to run from a pre-commit hook of a task repository:
+//!
+//! ```sh
+//! #!/bin/sh
+//! exec todo validate --staged
+//! ```
+//!
+//! Problems are reported as `path:line:column: message`, which editors
+//! and CI systems pick up like compiler errors.
+
+use crate::config::Config;
+use crate::{front_matter_from_table, git, split_front_matter, yaml};
+use crate::{DataId, FrontMatter, FrontMatterStyle};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::Path;
+
+/// A todo file as it is about to be committed.
+#[derive(Debug)]
+pub struct Source {
+    /// Relative to the data dir.
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.path, self.line, self.column, self.message)
+    }
+}
+
+/// The todo files staged in git, with their staged content.
+pub async fn staged(data_dir: &Path) -> anyhow::Result<Vec<Source>> {
+    let listing = git::run(
+        data_dir,
+        &["diff", "--cached", "--name-only", "--relative", "--diff-filter=ACMR", "--", "tasks"],
+    )
+    .await?;
+
+    let mut sources = vec![];
+    for path in listing.lines().filter(|path| path.ends_with(".md")) {
+        let content = git::run(data_dir, &["show", format!(":./{path}").as_str()]).await?;
+        sources.push(Source {
+            path: path.to_string(),
+            content,
+        });
+    }
+    Ok(sources)
+}
+
+/// The todo files in `tasks/`.
+pub async fn worktree(data_dir: &Path) -> anyhow::Result<Vec<Source>> {
+    let mut entries = tokio::fs::read_dir(data_dir.join("tasks")).await?;
+    let mut sources = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if !entry.file_type().await?.is_file() || path.extension().is_none_or(|ext| ext != "md") {
+            continue;
+        }
+        sources.push(Source {
+            path: format!("tasks/{}", entry.file_name().to_string_lossy()),
+            content: tokio::fs::read_to_string(path.as_path()).await?,
+        });
+    }
+    sources.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(sources)
+}
+
+/// Checks that every file in `sources` parses, follows the tag rules and
+/// has an id no other file uses. `others` are the rest of the collection,
+/// consulted for ids only; files also in `sources` are skipped.
+pub fn check(sources: &[Source], others: &[Source], config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut parsed = vec![];
+    for source in sources {
+        match parse(source) {
+            Ok(front_matter) => parsed.push((source, front_matter)),
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+
+    let mut paths_by_id: BTreeMap<DataId, Vec<&str>> = BTreeMap::new();
+    for (source, front_matter) in &parsed {
+        paths_by_id.entry(front_matter.id).or_default().push(source.path.as_str());
+    }
+    for other in others {
+        if sources.iter().any(|source| source.path == other.path) {
+            continue;
+        }
+        if let Ok(front_matter) = parse(other) {
+            paths_by_id.entry(front_matter.id).or_default().push(other.path.as_str());
+        }
+    }
+
+    for (source, front_matter) in &parsed {
+        for tag in &front_matter.tags {
+            let problem = match config.tags.normalize(tag) {
+                Ok(normalized) if normalized == *tag => continue,
+                Ok(normalized) => format!("tag '{tag}' should be written '{normalized}'"),
+                Err(err) => err.to_string(),
+            };
+            let offset = source.content.find(&format!("\"{tag}\"")).unwrap_or(0);
+            diagnostics.push(diagnostic_at(source, offset, problem));
+        }
+
+        let duplicates: Vec<&str> = paths_by_id[&front_matter.id]
+            .iter()
+            .copied()
+            .filter(|path| *path != source.path)
+            .collect();
+        if !duplicates.is_empty() {
+            let problem = format!("id {} is also used by {}", front_matter.id, duplicates.join(", "));
+            let offset = id_offset(source.content.as_str());
+            diagnostics.push(diagnostic_at(source, offset, problem));
+        }
+    }
+
+    diagnostics.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+    diagnostics
+}
+
+fn parse(source: &Source) -> Result<FrontMatter, Diagnostic> {
+    let content = source.content.as_str();
+    let (style, front_matter, _) = split_front_matter(content).map_err(|err| Diagnostic {
+        path: source.path.clone(),
+        line: 1,
+        column: 1,
+        message: err.to_string(),
+    })?;
+    // the front matter is a slice of the content, so offsets carry over
+    let start = front_matter.as_ptr() as usize - content.as_ptr() as usize;
+
+    if style == FrontMatterStyle::Yaml {
+        let (first_line, _) = position(content, start);
+        let table = yaml::from_str(front_matter).map_err(|err| Diagnostic {
+            path: source.path.clone(),
+            line: first_line + err.line - 1,
+            column: 1,
+            message: err.message,
+        })?;
+        return front_matter_from_table(&table)
+            .map_err(|err| diagnostic_at(source, start, err.message().to_string()));
+    }
+
+    toml::from_str(front_matter).map_err(|err| {
+        let offset = start + err.span().map_or(0, |span| span.start);
+        let (line, column) = position(content, offset);
+        Diagnostic {
+            path: source.path.clone(),
+            line,
+            column,
+            message: err.message().to_string(),
+        }
+    })
+}
+
+fn diagnostic_at(source: &Source, offset: usize, message: String) -> Diagnostic {
+    let (line, column) = position(source.content.as_str(), offset);
+    Diagnostic {
+        path: source.path.clone(),
+        line,
+        column,
+        message,
+    }
+}
+
+/// The start of the `id = ...` or `id: ...` line, or of the file.
+fn id_offset(content: &str) -> usize {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.strip_prefix("id").is_some_and(|rest| rest.trim_start().starts_with(['=', ':'])) {
+            return offset;
+        }
+        offset += line.len();
+    }
+    0
+}
+
+/// 1-based line and column, in chars, of a byte offset.
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
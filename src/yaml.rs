@@ -0,0 +1,627 @@
+//! YAML front matter, for todos fenced with `---` instead of `+++`.
+//!
+//! This is the part of YAML front matter needs: block and flow mappings
+//! and sequences of strings, numbers, booleans and dates, and comments.
+//! Anchors, tags, multi-line strings and the like are rejected instead of
+//! guessed at. Every value is one TOML has, so front matter converts
+//! between both styles without losing a value: a null leaves its field
+//! out, and plain dates and times are TOML datetimes. The layout is not
+//! kept: fields come in the order todos write them, and numbers and
+//! strings are written the way each style writes them. Dates of the todo
+//! itself, like `created_at`, are quoted strings just as in TOML, and a
+//! plain one is rejected by name.
+
+use std::fmt::Display;
+use std::str::FromStr;
+use toml::value::Datetime;
+use toml::{Table, Value};
+
+#[derive(Debug)]
+pub struct Error {
+    /// Counted from the first line of the front matter, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn error(line: usize, message: impl Into<String>) -> Error {
+    Error {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Writes `table` as a block mapping, with sequences of plain values and
+/// empty collections in flow style, like `tags: ["a", "b"]`.
+pub fn to_string(table: &Table) -> String {
+    let mut out = String::new();
+    write_mapping(&mut out, table, 0);
+    out
+}
+
+fn write_mapping(out: &mut String, table: &Table, indent: usize) {
+    let pad = " ".repeat(indent);
+    for (key, value) in table {
+        let key = key_text(key);
+        match value {
+            Value::Table(table) if !table.is_empty() => {
+                out.push_str(&format!("{pad}{key}:\n"));
+                write_mapping(out, table, indent + 2);
+            }
+            Value::Array(items) if items.iter().any(Value::is_table) => {
+                out.push_str(&format!("{pad}{key}:\n"));
+                for item in items {
+                    write_item(out, item, indent + 2);
+                }
+            }
+            value => out.push_str(&format!("{pad}{key}: {}\n", flow(value))),
+        }
+    }
+}
+
+/// A sequence item, with the first field of a mapping next to its dash.
+fn write_item(out: &mut String, item: &Value, indent: usize) {
+    let pad = " ".repeat(indent);
+    match item {
+        Value::Table(table) if !table.is_empty() => {
+            let mut mapping = String::new();
+            write_mapping(&mut mapping, table, indent + 2);
+            out.push_str(&format!("{pad}- {}", &mapping[indent + 2..]));
+        }
+        item => out.push_str(&format!("{pad}- {}\n", flow(item))),
+    }
+}
+
+fn flow(value: &Value) -> String {
+    match value {
+        Value::String(text) => quote(text),
+        Value::Integer(number) => number.to_string(),
+        Value::Float(number) if number.is_nan() => ".nan".to_string(),
+        Value::Float(number) if number.is_infinite() && *number > 0.0 => ".inf".to_string(),
+        Value::Float(number) if number.is_infinite() => "-.inf".to_string(),
+        Value::Float(number) => format!("{number:?}"),
+        Value::Boolean(flag) => flag.to_string(),
+        Value::Datetime(datetime) => datetime.to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(flow).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Table(table) => {
+            let fields: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key_text(key), flow(value)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+/// JSON strings are YAML double-quoted strings.
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap()
+}
+
+/// A key as it is, when it reads back as the same string.
+fn key_text(key: &str) -> String {
+    let plain = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-'))
+        && matches!(resolve(key), Some(Value::String(_)));
+    match plain {
+        true => key.to_string(),
+        false => quote(key),
+    }
+}
+
+pub fn from_str(text: &str) -> Result<Table, Error> {
+    let mut lines = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let content = strip_comment(line).trim_end();
+        let text = content.trim_start_matches(' ');
+        if text.is_empty() {
+            continue;
+        }
+        if text.starts_with('\t') {
+            return Err(error(number, "tabs can't indent YAML, use spaces"));
+        }
+        let indent = content.len() - text.len();
+        lines.push(Line {
+            number,
+            indent,
+            text: text.to_string(),
+        });
+    }
+
+    let Some(first) = lines.first() else {
+        return Ok(Table::new());
+    };
+    let (number, indent) = (first.number, first.indent);
+    if first.is_item() {
+        return Err(error(number, "front matter has to be fields, not a list"));
+    }
+    let mut parser = Parser { lines, at: 0 };
+    let Value::Table(table) = parser.mapping(indent)? else {
+        unreachable!("mappings parse to tables")
+    };
+    match parser.lines.get(parser.at) {
+        Some(line) => Err(error(line.number, "unexpected indentation")),
+        None => Ok(table),
+    }
+}
+
+#[derive(Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+impl Line {
+    fn is_item(&self) -> bool {
+        self.text == "-" || self.text.starts_with("- ")
+    }
+}
+
+/// Parses the block structure, indented lines, down to the values on them.
+struct Parser {
+    lines: Vec<Line>,
+    at: usize,
+}
+
+impl Parser {
+    fn mapping(&mut self, indent: usize) -> Result<Value, Error> {
+        let mut table = Table::new();
+        while let Some(line) = self.lines.get(self.at).filter(|line| line.indent == indent) {
+            let line = line.clone();
+            if line.is_item() {
+                return Err(error(line.number, "expected a field, not a list item"));
+            }
+            let Some((key, rest)) = split_key(&line.text) else {
+                return Err(error(line.number, "expected a field like `key: value`"));
+            };
+            self.at += 1;
+            let value = match rest.is_empty() {
+                true => self.nested(indent, true)?,
+                false => inline(rest, line.number)?,
+            };
+            if table.contains_key(&key) {
+                return Err(error(line.number, format!("field '{key}' is given twice")));
+            }
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+        }
+        Ok(Value::Table(table))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, Error> {
+        let mut items = vec![];
+        while let Some(line) = self.lines.get(self.at).filter(|line| line.indent == indent) {
+            let line = line.clone();
+            if !line.is_item() {
+                break;
+            }
+            let rest = line.text[1..].trim_start();
+            let item = if rest.is_empty() {
+                self.at += 1;
+                self.nested(indent, false)?
+            } else if split_key(rest).is_some() {
+                // the mapping goes on below, aligned with its first field
+                let indent = indent + line.text.len() - rest.len();
+                self.lines[self.at] = Line {
+                    number: line.number,
+                    indent,
+                    text: rest.to_string(),
+                };
+                Some(self.mapping(indent)?)
+            } else {
+                self.at += 1;
+                inline(rest, line.number)?
+            };
+            items.push(item.ok_or_else(|| error(line.number, NULL_ITEM))?);
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// The block below a field or dash with nothing after it, if any. A
+    /// field's sequence may start at the field's own indentation.
+    fn nested(&mut self, indent: usize, field: bool) -> Result<Option<Value>, Error> {
+        let Some(next) = self.lines.get(self.at) else {
+            return Ok(None);
+        };
+        let value = match (next.indent, next.is_item()) {
+            (deeper, true) if deeper > indent => self.sequence(deeper)?,
+            (deeper, false) if deeper > indent => self.mapping(deeper)?,
+            (same, true) if same == indent && field => self.sequence(indent)?,
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+}
+
+const NULL_ITEM: &str = "a list can't hold null, TOML has no such value";
+
+/// Splits `key: value`, or `None` when `text` is no field.
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let (key, rest) = match text.starts_with(['"', '\'']) {
+        true => {
+            let mut scanner = Scanner::new(text, 0);
+            let key = scanner.quoted().ok()?;
+            (key, &text[scanner.pos..])
+        }
+        false => {
+            let (at, _) = text.match_indices(':').find(|(at, _)| {
+                let after = &text[at + 1..];
+                after.is_empty() || after.starts_with(' ')
+            })?;
+            let key = text[..at].trim_end();
+            if key.is_empty() || key.starts_with(['[', '{', '-', '#']) {
+                return None;
+            }
+            (key.to_string(), &text[at..])
+        }
+    };
+    let rest = rest.trim_start().strip_prefix(':')?;
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((key, rest.trim()))
+}
+
+/// Cuts a `#` comment, which starts a line or follows a space outside of
+/// quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    let mut chars = line.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match (quote, c) {
+            (Some('"'), '\\') => {
+                chars.next();
+            }
+            (Some('\''), '\'') if chars.peek().is_some_and(|(_, next)| *next == '\'') => {
+                chars.next();
+            }
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if matches!(previous, ' ' | '[' | '{' | ',' | ':') => {
+                quote = Some(c)
+            }
+            (None, '#') if previous == ' ' => return &line[..at],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// The value on a line after a field or dash; `None` for null.
+fn inline(text: &str, line: usize) -> Result<Option<Value>, Error> {
+    let mut scanner = Scanner::new(text, line);
+    let value = scanner.value(false)?;
+    scanner.skip_spaces();
+    match &text[scanner.pos..] {
+        "" => Ok(value),
+        rest => Err(scanner.error(format!("unexpected '{rest}' after the value"))),
+    }
+}
+
+/// Reads values from the text of one line, including flow collections.
+struct Scanner<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str, line: usize) -> Self {
+        Self { text, pos: 0, line }
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        error(self.line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(' ') {
+            self.pos += 1;
+        }
+    }
+
+    /// A value; inside `[]` or `{}` plain ones end at `,`, `]` and `}`.
+    fn value(&mut self, in_flow: bool) -> Result<Option<Value>, Error> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('"' | '\'') => Ok(Some(Value::String(self.quoted()?))),
+            Some('[') => self.flow_sequence().map(Some),
+            Some('{') => self.flow_mapping().map(Some),
+            Some('|' | '>') => Err(self.error("multi-line strings are not supported, use \"\\n\"")),
+            Some(c @ ('&' | '*' | '!')) => {
+                Err(self.error(format!("anchors, aliases and tags like '{c}' are not supported")))
+            }
+            Some(c @ ('@' | '`' | '%')) => Err(self.error(format!("'{c}' can't start a value"))),
+            _ => {
+                let rest = &self.text[self.pos..];
+                let len = match in_flow {
+                    true => rest.find([',', ']', '}']).unwrap_or(rest.len()),
+                    false => rest.len(),
+                };
+                self.pos += len;
+                Ok(resolve(rest[..len].trim_end()))
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, Error> {
+        let open = self.bump().unwrap();
+        let mut text = String::new();
+        loop {
+            match (open, self.bump()) {
+                (_, None) => return Err(self.error("the quote is never closed")),
+                ('\'', Some('\'')) if self.peek() == Some('\'') => {
+                    self.pos += 1;
+                    text.push('\'');
+                }
+                (_, Some(c)) if c == open => return Ok(text),
+                ('"', Some('\\')) => text.push(self.escape()?),
+                (_, Some(c)) => text.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, Error> {
+        let digits = match self.bump() {
+            Some('"') => return Ok('"'),
+            Some('\\') => return Ok('\\'),
+            Some('/') => return Ok('/'),
+            Some(' ') => return Ok(' '),
+            Some('0') => return Ok('\0'),
+            Some('b') => return Ok('\u{8}'),
+            Some('t') => return Ok('\t'),
+            Some('n') => return Ok('\n'),
+            Some('f') => return Ok('\u{c}'),
+            Some('r') => return Ok('\r'),
+            Some('e') => return Ok('\u{1b}'),
+            Some('x') => 2,
+            Some('u') => 4,
+            Some('U') => 8,
+            Some(c) => return Err(self.error(format!("unknown escape '\\{c}'"))),
+            None => return Err(self.error("the quote is never closed")),
+        };
+        let hex = self.text.get(self.pos..self.pos + digits).unwrap_or_default();
+        let c = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+        self.pos += hex.len();
+        c.ok_or_else(|| self.error(format!("'{hex}' is no unicode character")))
+    }
+
+    fn flow_sequence(&mut self) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value(true)?.ok_or_else(|| self.error(NULL_ITEM))?);
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err(self.error("expected ',' or ']' in the list")),
+            }
+        }
+    }
+
+    fn flow_mapping(&mut self) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut table = Table::new();
+        loop {
+            self.skip_spaces();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Table(table));
+            }
+            let key = match self.peek() {
+                Some('"' | '\'') => Some(self.quoted()?),
+                _ => {
+                    let rest = &self.text[self.pos..];
+                    let len = rest.find([':', ',', '}']).unwrap_or(rest.len());
+                    self.pos += len;
+                    Some(rest[..len].trim().to_string()).filter(|key| !key.is_empty())
+                }
+            };
+            self.skip_spaces();
+            let Some(key) = key.filter(|_| self.bump() == Some(':')) else {
+                return Err(self.error("expected `key: value` in the mapping"));
+            };
+            let value = self.value(true)?;
+            if table.contains_key(&key) {
+                return Err(self.error(format!("field '{key}' is given twice")));
+            }
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+            self.skip_spaces();
+            match self.bump() {
+                Some(',') => {}
+                Some('}') => return Ok(Value::Table(table)),
+                _ => return Err(self.error("expected ',' or '}' in the mapping")),
+            }
+        }
+    }
+}
+
+/// What a plain, unquoted value stands for; `None` for null.
+fn resolve(plain: &str) -> Option<Value> {
+    let value = match plain {
+        "" | "~" | "null" | "Null" | "NULL" => return None,
+        "true" | "True" | "TRUE" => Value::Boolean(true),
+        "false" | "False" | "FALSE" => Value::Boolean(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Value::Float(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => Value::Float(f64::NEG_INFINITY),
+        ".nan" | ".NaN" | ".NAN" => Value::Float(f64::NAN),
+        _ => {
+            let digits = |prefix: &str, radix| {
+                let digits = plain.strip_prefix(prefix)?;
+                i64::from_str_radix(digits, radix).ok()
+            };
+            let number = plain.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c))
+                && plain.contains(|c: char| c.is_ascii_digit());
+            if let Some(number) = plain.parse().ok().or(digits("0x", 16)).or(digits("0o", 8)) {
+                Value::Integer(number)
+            } else if let Some(number) = number.then(|| plain.parse().ok()).flatten() {
+                Value::Float(number)
+            } else if let Some(datetime) = plain
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| Datetime::from_str(plain).ok())
+                .flatten()
+            {
+                Value::Datetime(datetime)
+            } else {
+                Value::String(plain.to_string())
+            }
+        }
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> Table {
+        from_str(text).unwrap()
+    }
+
+    fn error(text: &str) -> String {
+        from_str(text).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn writes_block_mappings_with_flow_lists() {
+        let table: Table = toml::from_str(
+            "id = 3\ntitle = \"Say \\\"hi\\\"\"\ntags = [\"a\", \"b\"]\nempty = []\n\
+             ratio = 1.0\ndone = false\nat = 1979-05-27T07:32:00Z\n\"odd key\" = 1\n\
+             maybe = \"true\"\n[[entries]]\nstart = 1\nend = 2\n[[entries]]\nstart = 3\n\
+             [meta]\nsource = { kind = \"trello\", ids = [1] }\nnone = {}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            to_string(&table),
+            "id: 3\n\
+             title: \"Say \\\"hi\\\"\"\n\
+             tags: [\"a\", \"b\"]\n\
+             empty: []\n\
+             ratio: 1.0\n\
+             done: false\n\
+             at: 1979-05-27T07:32:00Z\n\
+             \"odd key\": 1\n\
+             maybe: \"true\"\n\
+             entries:\n  - start: 1\n    end: 2\n  - start: 3\n\
+             meta:\n  source:\n    kind: \"trello\"\n    ids: [1]\n  none: {}\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_every_toml_value() {
+        let table: Table = toml::from_str(
+            "s = \"tab\\t, quote \\\" and \\u00e9 # not a comment\"\nempty = \"\"\n\
+             i = -42\nf = 6.02e23\nneg = -0.0\ninf = -inf\nt = true\n\
+             offset = 1979-05-27T00:32:00.999-07:00\nlocal = 1979-05-27T07:32:00\n\
+             day = 1979-05-27\ntime = 07:32:00\nnested = [[1, 2], [\"a\"], []]\n\
+             mixed = [{ a = 1 }, 2, { b = { c = [3] } }]\n\"\" = 1\n\"1\" = \"one\"\n\
+             \"null\" = \"~\"\n[deep.er]\nleaf = { x = {} }\n",
+        )
+        .unwrap();
+        assert_eq!(parsed(&to_string(&table)), table);
+    }
+
+    #[test]
+    fn reads_handwritten_yaml() {
+        let table = parsed(
+            "# a todo\nid: 3   # the id\ncreated_at: \"2025-01-01T09:00:00Z\"\n\
+             due_at: ~\ntags:\n- backend\n- 'it''s'\nowner:\n  name: Kim #lead\n  \
+             team: api\nlinks:\n  - url: https://example.com/a#b\n    title: A\n  - plain\n\
+             note: a: b, c\nhex: 0x1f\n\n",
+        );
+        let expected: Table = toml::from_str(
+            "id = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = [\"backend\", \"it's\"]\n\
+             note = \"a: b, c\"\nhex = 31\n[owner]\nname = \"Kim\"\nteam = \"api\"\n\
+             [[links]]\nurl = \"https://example.com/a#b\"\ntitle = \"A\"\n",
+        )
+        .unwrap();
+        let mut table = table;
+        let links = table["links"].as_array_mut().unwrap().pop();
+        assert_eq!(links, Some(Value::String("plain".to_string())));
+        assert_eq!(table, expected);
+        assert!(parsed("").is_empty());
+        assert!(parsed("# only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn resolves_plain_values() {
+        let cases = [
+            ("7", Some(Value::Integer(7))),
+            ("+7", Some(Value::Integer(7))),
+            ("0o17", Some(Value::Integer(15))),
+            ("1.5", Some(Value::Float(1.5))),
+            ("1e3", Some(Value::Float(1000.0))),
+            ("TRUE", Some(Value::Boolean(true))),
+            ("null", None),
+            ("", None),
+            ("inf", Some(Value::String("inf".to_string()))),
+            ("1-2", Some(Value::String("1-2".to_string()))),
+            ("2025-13-01", Some(Value::String("2025-13-01".to_string()))),
+            ("yes", Some(Value::String("yes".to_string()))),
+        ];
+        for (plain, expected) in cases {
+            assert_eq!(resolve(plain), expected, "{plain}");
+        }
+        let day = resolve("2025-01-31").unwrap();
+        assert_eq!(day.as_datetime().map(ToString::to_string).as_deref(), Some("2025-01-31"));
+    }
+
+    #[test]
+    fn rejects_what_it_does_not_support() {
+        assert_eq!(error("a: 1\na: 2\n"), "line 2: field 'a' is given twice");
+        assert_eq!(error("- a\n"), "line 1: front matter has to be fields, not a list");
+        assert_eq!(error("a: 1\n  b: 2\n"), "line 2: unexpected indentation");
+        assert_eq!(error("a:\n  - 1\n  b: 2\n"), "line 3: unexpected indentation");
+        assert_eq!(error("just text\n"), "line 1: expected a field like `key: value`");
+        assert_eq!(error("a:\n\t- 1\n"), "line 2: tabs can't indent YAML, use spaces");
+        assert_eq!(error("a: \"open\n"), "line 1: the quote is never closed");
+        assert_eq!(error("a: [1, 2\n"), "line 1: expected ',' or ']' in the list");
+        assert_eq!(error("a: {b 1}\n"), "line 1: expected `key: value` in the mapping");
+        assert_eq!(error("a: [1] 2\n"), "line 1: unexpected '2' after the value");
+        assert_eq!(error("a: [1, ~]\n"), format!("line 1: {NULL_ITEM}"));
+        assert_eq!(error("a:\n  -\n"), format!("line 2: {NULL_ITEM}"));
+        assert_eq!(error("a: \"\\q\"\n"), "line 1: unknown escape '\\q'");
+        assert_eq!(
+            error("a: |\n  text\n"),
+            "line 1: multi-line strings are not supported, use \"\\n\""
+        );
+        assert_eq!(
+            error("a: &anchor 1\n"),
+            "line 1: anchors, aliases and tags like '&' are not supported"
+        );
+        assert_eq!(error("a: @b\n"), "line 1: '@' can't start a value");
+    }
+}
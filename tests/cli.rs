@@ -0,0 +1,1982 @@
+//! End-to-end tests of the CLI surface.
+//!
+//! Each test runs the built binary against its own throwaway data dir and
+//! compares stdout, stderr and the exit code against a snapshot in
+//! `tests/snapshots/`. Run with `UPDATE_SNAPSHOTS=1` to accept changes.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "todo-cli-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self { dir }
+    }
+
+    /// A sandbox with `init` already run.
+    fn initialized() -> Self {
+        let sandbox = Self::new();
+        assert_eq!(sandbox.run(&["init"]).code, 0);
+        sandbox
+    }
+
+    fn run(&self, args: &[&str]) -> Run {
+        self.run_in(&self.dir, args)
+    }
+
+    /// Runs against another data dir, still from inside the sandbox.
+    fn run_in(&self, data_dir: &Path, args: &[&str]) -> Run {
+        self.spawn(data_dir, args, "", &[])
+    }
+
+    /// Runs with `input` on stdin, for commands that ask.
+    fn run_with_input(&self, args: &[&str], input: &str) -> Run {
+        self.spawn(&self.dir, args, input, &[])
+    }
+
+    /// Runs with extra environment variables, like a `PATH` with fakes of
+    /// external tools.
+    fn run_with_env(&self, args: &[&str], envs: &[(&str, String)]) -> Run {
+        self.spawn(&self.dir, args, "", envs)
+    }
+
+    fn spawn(&self, data_dir: &Path, args: &[&str], input: &str, envs: &[(&str, String)]) -> Run {
+        use std::io::Write;
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_todo"))
+            .arg("--data-dir")
+            .arg(data_dir)
+            .args(args)
+            .current_dir(&self.dir)
+            .env("RUST_BACKTRACE", "0")
+            .env("TZ", "UTC")
+            .envs(envs.iter().map(|(key, value)| (key, value)))
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+
+        Run {
+            args: args.join(" "),
+            code: output.status.code().unwrap_or(-1),
+            stdout: self.redact(&String::from_utf8_lossy(&output.stdout)),
+            stderr: self.redact(&String::from_utf8_lossy(&output.stderr)),
+        }
+    }
+
+    fn read(&self, path: &str) -> String {
+        self.redact(&std::fs::read_to_string(self.dir.join(path)).unwrap())
+    }
+
+    fn write(&self, path: &str, content: &str) {
+        let path = self.dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// Runs `git` in `dir` below the sandbox with a fixed identity and
+    /// clock, so commit hashes are stable.
+    fn git(&self, dir: &str, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(self.dir.join(dir))
+            .args(args)
+            .env("GIT_AUTHOR_NAME", "test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_AUTHOR_DATE", "2025-01-01T09:00:00Z")
+            .env("GIT_COMMITTER_NAME", "test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_DATE", "2025-01-01T09:00:00Z")
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {} failed", args.join(" "));
+    }
+
+    /// Replaces the parts of output that differ between runs.
+    fn redact(&self, text: &str) -> String {
+        let name = self.dir.file_name().unwrap().to_str().unwrap();
+        redact_timestamps(
+            &text
+                .replace(self.dir.to_str().unwrap(), "[DATA_DIR]")
+                .replace(name, "[DATA_DIR_NAME]"),
+        )
+    }
+}
+
+/// Replaces timestamps taken from the clock, recognizable by their
+/// fractional seconds (`2024-05-01T10:00:00.123Z`, `... 10:00:00.123 UTC`).
+/// Fixed timestamps written by tests carry no fraction and are kept.
+fn redact_timestamps(text: &str) -> String {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mut out = String::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let candidate = rest.get(..20).filter(|head| {
+            head.is_ascii()
+                && is_digits(&head[0..4])
+                && &head[4..5] == "-"
+                && is_digits(&head[5..7])
+                && (&head[10..11] == "T" || &head[10..11] == " ")
+                && is_digits(&head[11..13])
+                && &head[19..20] == "."
+        });
+
+        match candidate {
+            Some(_) => {
+                let mut len = 20 + rest[20..].bytes().take_while(u8::is_ascii_digit).count();
+                if rest[len..].starts_with('Z') {
+                    len += 1;
+                } else if rest[len..].starts_with(" UTC") {
+                    len += 4;
+                }
+                out.push_str("[TIMESTAMP]");
+                rest = &rest[len..];
+            }
+            None => {
+                let ch = rest.chars().next().unwrap();
+                out.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+
+    out
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+struct Run {
+    args: String,
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+impl std::fmt::Display for Run {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "$ todo {}", self.args)?;
+        writeln!(f, "exit: {}", self.code)?;
+        if !self.stdout.is_empty() {
+            writeln!(f, "--- stdout\n{}", self.stdout)?;
+        }
+        if !self.stderr.is_empty() {
+            writeln!(f, "--- stderr\n{}", self.stderr)?;
+        }
+        Ok(())
+    }
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.snap"));
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "snapshot '{name}' is missing, run with UPDATE_SNAPSHOTS=1 to record it\n{actual}"
+    );
+
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        expected == actual,
+        "snapshot '{name}' differs, rerun with UPDATE_SNAPSHOTS=1 to accept\n\
+         --- expected\n{expected}\n--- actual\n{actual}"
+    );
+}
+
+fn transcript(runs: &[Run]) -> String {
+    runs.iter().map(Run::to_string).collect::<Vec<_>>().join("\n")
+}
+
+#[test]
+fn init_then_new() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run(&["new", "--title", "Call the dentist", "-t", "Health"]),
+        sandbox.run(&["new", "--quiet", "--title", "Pay rent"]),
+        sandbox.run(&["new", "--porcelain"]),
+    ];
+    assert_snapshot("init_then_new", &transcript(&runs));
+    assert_snapshot("init_then_new_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn init_twice_fails() {
+    let sandbox = Sandbox::initialized();
+    assert_snapshot("init_twice_fails", &sandbox.run(&["init"]).to_string());
+}
+
+#[test]
+fn porcelain_records() {
+    let sandbox = Sandbox::new();
+    let runs = [
+        sandbox.run(&["init", "--porcelain"]),
+        sandbox.run(&["new", "--porcelain"]),
+        sandbox.run(&["fsck", "--write-manifest", "--porcelain"]),
+        sandbox.run(&["fsck", "--verify", "--porcelain"]),
+    ];
+    assert_snapshot("porcelain_records", &transcript(&runs));
+}
+
+#[test]
+fn porcelain_records_of_list_new_done_and_bulk() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run(&["--porcelain", "new", "--title", "Water plants", "-t", "home"]),
+        sandbox.run(&["--porcelain", "new", "--title", "Pay rent", "--due", "2099-02-01"]),
+        sandbox.run(&["--porcelain", "new", "--title", "Call mum", "-t", "family"]),
+        sandbox.run(&["--porcelain", "new"]),
+        sandbox.run(&["recur", "2", "FREQ=MONTHLY"]),
+        sandbox.run(&["--porcelain", "list"]),
+        sandbox.run(&["--porcelain", "tag", "1,3", "--add", "weekend"]),
+        sandbox.run(&["--porcelain", "tag", "3", "--remove", "family", "--remove", "weekend"]),
+        sandbox.run(&["--porcelain", "done", "1-2", "--dry-run"]),
+        sandbox.run(&["--porcelain", "done", "1-2"]),
+        sandbox.run(&[
+            "--porcelain",
+            "cancel",
+            "--filter",
+            "tag=weekend",
+            "--reason",
+            "away",
+            "--dry-run",
+        ]),
+        sandbox.run(&["--porcelain", "cancel", "3", "--reason", "moved"]),
+        sandbox.run(&["--porcelain", "delete", "4", "--dry-run"]),
+        sandbox.run(&["--porcelain", "delete", "4"]),
+        sandbox.run(&["--porcelain", "list", "--status", "done,cancelled"]),
+        sandbox.run(&["--porcelain", "list", "--tag", "nothing"]),
+    ];
+    assert_snapshot("porcelain_records_of_list_new_done_and_bulk", &transcript(&runs));
+}
+
+#[test]
+fn porcelain_fields_are_escaped() {
+    let sandbox = Sandbox::initialized();
+    let created = sandbox.run(&["--porcelain", "new", "--title", "Plan\tC:\\temp"]);
+    let path = sandbox.dir.join("tasks/0000000001.todo.md");
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, format!("{content}\tcopy C:\\temp\r\n")).unwrap();
+    let runs = [
+        created,
+        sandbox.run(&["--porcelain", "list"]),
+        sandbox.run(&["--porcelain", "search", "temp"]),
+        sandbox.run(&["--porcelain", "block", "1", "the\tvendor"]),
+        sandbox.run(&["--porcelain", "blocked"]),
+    ];
+    assert_snapshot("porcelain_fields_are_escaped", &transcript(&runs));
+}
+
+#[test]
+fn templates_are_sandboxed() {
+    let tags: Vec<String> = (0..20).map(|n| format!("t{n}")).collect();
+    let mut huge = vec!["new", "--template", "huge"];
+    for tag in &tags {
+        huge.extend(["-t", tag.as_str()]);
+    }
+    /// Templates by name, then the command using them.
+    type Case<'a> = (&'a [(&'a str, &'a str)], &'a [&'a str]);
+    let cases: [Case; 5] = [
+        (&[("evil", r#"{{include "/etc/passwd"}}"#)], &["new", "--template", "evil"]),
+        (&[("evil", "{{#if (exec \"ls\")}}yes{{/if}}")], &["new", "--template", "evil"]),
+        (&[("a", "{{> b}}"), ("b", "{{#if x}}{{> a}}{{/if}}")], &["new", "--template", "a"]),
+        (&[("evil", "{{> (lookup this \"name\")}}")], &["new", "--template", "evil"]),
+        (
+            &[(
+                "huge",
+                "{{#each tags}}{{#each ../tags}}{{#each ../../tags}}0123456789\
+                 {{/each}}{{/each}}{{/each}}",
+            )],
+            &huge,
+        ),
+    ];
+
+    let mut runs = vec![];
+    for (templates, args) in cases {
+        let sandbox = Sandbox::initialized();
+        for (name, template) in templates {
+            sandbox.write(&format!("templates/{name}.md.hbs"), template);
+        }
+        runs.push(sandbox.run(args));
+        assert!(!sandbox.dir.join("tasks/0000000001.todo.md").exists());
+    }
+    assert_snapshot("templates_are_sandboxed", &transcript(&runs));
+}
+
+#[test]
+fn fsck_reports_modified_files() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new"]);
+    sandbox.run(&["fsck", "--write-manifest"]);
+    sandbox.write("tasks/0000000001.todo.md", "+++\nid = 1\n+++\n");
+    sandbox.write("tasks/0000000002.todo.md", "");
+
+    assert_snapshot(
+        "fsck_reports_modified_files",
+        &sandbox.run(&["fsck", "--verify"]).to_string(),
+    );
+}
+
+#[test]
+fn tags_follow_the_tag_policy() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run(&["new", "--title", "Plan", "-t", "Work Stuff", "-t", "work-stuff", "-t", "b"]),
+        sandbox.run(&["new", "--title", "Brew", "-t", "  Home\tOffice ", "-t", "ops:Q1/2025"]),
+        sandbox.run(&["new", "-t", "a&b"]),
+        sandbox.run(&["tags"]),
+    ];
+    assert_snapshot("tags_follow_the_tag_policy", &transcript(&runs));
+    // every tag once and quoted, without the index the template used to add
+    assert_snapshot("tags_follow_the_tag_policy_file", &sandbox.read("tasks/0000000001.todo.md"));
+
+    let custom = Sandbox::initialized();
+    custom.write(
+        "config.toml",
+        "[tags]\nlowercase = false\nseparator = \"_\"\nallowed_separators = [\"_\"]\n",
+    );
+    let runs = [
+        custom.run(&["--quiet", "new", "-t", "Work Stuff", "-t", "Home"]),
+        custom.run(&["new", "-t", "ops:q1"]),
+        custom.run(&["tags"]),
+    ];
+    assert_snapshot("tags_follow_the_tag_policy_custom", &transcript(&runs));
+}
+
+#[test]
+fn invalid_tags_are_rejected() {
+    let sandbox = Sandbox::initialized();
+    assert_snapshot(
+        "invalid_tags_are_rejected",
+        &sandbox.run(&["new", "-t", "a,b"]).to_string(),
+    );
+}
+
+#[test]
+fn trello_round_trip() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "board.json",
+        r#"{
+            "lists": [{"id": "l1", "name": "Doing"}],
+            "labels": [{"id": "b1", "name": "Backend"}],
+            "cards": [{"id": "c1", "name": "Ship it", "desc": "Soon.", "idList": "l1",
+                       "idLabels": ["b1"], "due": "2025-03-01T12:00:00Z"}],
+            "checklists": [{"id": "k1", "idCard": "c1", "name": "Steps",
+                            "checkItems": [{"name": "build", "state": "complete", "pos": 1}]}]
+        }"#,
+    );
+
+    let runs = [
+        sandbox.run(&["import", "--from", "trello", "board.json"]),
+        sandbox.run(&["export", "--format", "trello"]),
+    ];
+    assert_snapshot("trello_round_trip", &transcript(&runs));
+}
+
+#[test]
+fn typos_get_suggestions() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "-t", "health"]);
+    let runs = [
+        sandbox.run(&["nwe"]),
+        sandbox.run(&["new", "--template", "tsak"]),
+        sandbox.run(&["new", "-t", "helth"]),
+    ];
+    assert_snapshot("typos_get_suggestions", &transcript(&runs));
+}
+
+#[test]
+fn configured_id_format() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write("config.toml", "[ids]\nprefix = \"T-\"\nwidth = 3\n");
+    let runs = [
+        sandbox.run(&["new", "--title", "First"]),
+        sandbox.run(&["checklist", "add", "t-1", "--from", "missing"]),
+        sandbox.run(&["checklist", "add", "#7", "--from", "missing"]),
+        sandbox.run(&["checklist", "add", "X-1", "--from", "missing"]),
+    ];
+    assert_snapshot("configured_id_format", &transcript(&runs));
+}
+
+#[test]
+fn reserved_ids() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run(&["reserve", "2"]),
+        sandbox.run(&["new", "--title", "Unplanned"]),
+        sandbox.run(&["new", "--id", "2", "--title", "Planned"]),
+        sandbox.run(&["new", "--id", "2"]),
+        sandbox.run(&["new", "--id", "9"]),
+        sandbox.run(&["reserve", "--porcelain"]),
+    ];
+    assert_snapshot("reserved_ids", &transcript(&runs));
+}
+
+#[test]
+fn suggest_titles_and_tags() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Release notes", "-t", "release", "-t", "docs"]);
+    sandbox.run(&["new", "--title", "Rehearse talk", "-t", "release"]);
+    sandbox.run(&["new", "--title", "Pay rent", "-t", "home"]);
+    let runs = [
+        sandbox.run(&["suggest", "--prefix", "re"]),
+        sandbox.run(&["suggest", "--kind", "title", "--prefix", "RE"]),
+        sandbox.run(&["suggest", "--porcelain"]),
+    ];
+    assert_snapshot("suggest_titles_and_tags", &transcript(&runs));
+}
+
+#[test]
+fn ics_for_due_todo() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ndue_at = \"2025-03-01T12:00:00Z\"\n\
+         tags = [\"work\"]\n+++\n\n# Send invoice, then relax\n\nBefore noon.\n",
+    );
+    sandbox.run(&["new"]);
+
+    let mut runs = [sandbox.run(&["ics", "1"]), sandbox.run(&["ics", "2"])];
+    // DTSTAMP is the time of the export; CRLF line ends make diffs noisy
+    runs[0].stdout = runs[0]
+        .stdout
+        .lines()
+        .map(|line| {
+            let line = if line.starts_with("DTSTAMP:") { "DTSTAMP:[TIMESTAMP]" } else { line };
+            format!("{line}\n")
+        })
+        .collect();
+    assert_snapshot("ics_for_due_todo", &transcript(&runs));
+}
+
+#[test]
+fn cancel_with_reason() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Old plan"]);
+    sandbox.run(&["new", "--title", "New plan"]);
+    let runs = [
+        sandbox.run(&["cancel", "1", "--reason", "superseded", "--superseded-by", "2"]),
+        sandbox.run(&["cancel", "1", "--reason", "again"]),
+        sandbox.run(&["cancel", "2", "--reason", "loop", "--superseded-by", "2"]),
+    ];
+    assert_snapshot("cancel_with_reason", &transcript(&runs));
+    assert_snapshot("cancel_with_reason_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn template_fixtures() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "templates/tests/task/titled.toml",
+        "id = 7\ncreated_at = \"2025-01-01T09:00:00Z\"\ntitle = \"Fixture\"\ntags = [\"a\", \"b\"]\n",
+    );
+    let mut runs = vec![
+        sandbox.run(&["template", "test"]),
+        sandbox.run(&["template", "test", "--update"]),
+        sandbox.run(&["template", "test"]),
+    ];
+    sandbox.write(
+        "templates/task.md.hbs",
+        &sandbox.read("templates/task.md.hbs").replace("# ", "## "),
+    );
+    runs.push(sandbox.run(&["template", "test", "--porcelain"]));
+    assert_snapshot("template_fixtures", &transcript(&runs));
+    assert_snapshot("template_fixtures_golden", &sandbox.read("templates/tests/task/titled.md"));
+}
+
+#[test]
+fn sync_templates_from_git() {
+    let sandbox = Sandbox::initialized();
+    let front_matter = "+++\nid = {{ id }}\ncreated_at = \"{{ created_at }}\"\n";
+    sandbox.write(
+        "remote/bug.md.hbs",
+        &format!("{front_matter}tags = [ \"bug\" ]\n+++\n\n# Bug: {{{{title}}}}\n"),
+    );
+    sandbox.write(
+        "remote/task.md.hbs",
+        &format!("{front_matter}tags = [ \"shared\" ]\n+++\n"),
+    );
+    sandbox.git("remote", &["init", "--quiet", "--initial-branch", "main"]);
+    sandbox.git("remote", &["add", "."]);
+    sandbox.git("remote", &["commit", "--quiet", "-m", "templates"]);
+
+    let mut runs = vec![sandbox.run(&["sync-templates"])];
+    sandbox.write("config.toml", "[templates]\nsource = \"remote\"\n");
+    runs.extend([
+        sandbox.run(&["sync-templates"]),
+        sandbox.run(&["new", "--template", "bug", "--title", "Crash"]),
+        sandbox.run(&["new", "--title", "Local wins"]),
+    ]);
+    assert_snapshot("sync_templates_from_git", &transcript(&runs));
+    assert_snapshot("sync_templates_from_git_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn list_with_filters() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ndue_at = \"2025-03-01T12:00:00Z\"\n\
+         tags = [\"work\"]\n+++\n\n# File taxes\n",
+    );
+    sandbox.run(&["new", "--title", "Write report", "-t", "work", "-t", "writing"]);
+    sandbox.run(&["new", "--title", "Old idea"]);
+    sandbox.run(&["cancel", "3", "--reason", "not needed"]);
+
+    let runs = [
+        sandbox.run(&["list"]),
+        sandbox.run(&["list", "-t", "writing"]),
+        sandbox.run(&["list", "--overdue", "--quiet"]),
+        sandbox.run(&["list", "--status", "cancelled", "--porcelain"]),
+    ];
+    assert_snapshot("list_with_filters", &transcript(&runs));
+}
+
+#[test]
+fn show_a_todo() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ndue_at = \"2025-03-01T12:00:00Z\"\n\
+         tags = [\"work\", \"money\"]\n+++\n\n# File taxes\n\nReceipts are in the blue folder.\n",
+    );
+    let runs = [
+        sandbox.run(&["show", "1"]),
+        sandbox.run(&["show", "1", "--porcelain"]),
+        sandbox.run(&["show", "2"]),
+    ];
+    assert_snapshot("show_a_todo", &transcript(&runs));
+}
+
+#[test]
+fn done_completes_a_todo() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.run(&["new", "--title", "Feed cat"]);
+    let runs = [
+        sandbox.run(&["done", "1"]),
+        sandbox.run(&["done", "1"]),
+        sandbox.run(&["cancel", "1", "--reason", "late"]),
+        sandbox.run(&["list"]),
+        sandbox.run(&["list", "--status", "done", "--quiet"]),
+    ];
+    assert_snapshot("done_completes_a_todo", &transcript(&runs));
+    assert_snapshot("done_completes_a_todo_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn edit_validates_the_result() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Draft"]);
+    let runs = [
+        sandbox.run(&["edit", "1", "--editor", "sed -i s/Draft/Final/"]),
+        sandbox.run(&["edit", "1", "--editor", "sed -i s/^id.=.1/id=9/"]),
+        sandbox.run(&["edit", "1", "--editor", "truncate -s 0"]),
+        sandbox.run(&["edit", "1", "--editor", "false"]),
+    ];
+    assert_snapshot("edit_validates_the_result", &transcript(&runs));
+    assert_snapshot("edit_validates_the_result_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn delete_moves_to_trash() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Keep"]);
+    sandbox.run(&["new", "--title", "Trash"]);
+    sandbox.run(&["new", "--title", "Purge"]);
+    let runs = [
+        sandbox.run(&["delete", "2", "--porcelain"]),
+        sandbox.run(&["delete", "3", "--purge"]),
+        sandbox.run(&["delete", "3"]),
+        sandbox.run(&["list", "--quiet"]),
+        sandbox.run(&["trash", "empty"]),
+        sandbox.run(&["trash", "empty"]),
+    ];
+    assert_snapshot("delete_moves_to_trash", &transcript(&runs));
+}
+
+#[test]
+fn list_with_excerpts() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n+++\n\n# Taxes\n\n\
+         ## Notes\n\nAll receipts from last year are in the blue folder on the shelf.\n",
+    );
+    sandbox.write(
+        "tasks/0000000002.todo.md",
+        "+++\nid = 2\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n\
+         summary = \"Quarterly review\"\n+++\n\n# Review\n\nBody text.\n",
+    );
+    sandbox.run(&["new", "--title", "Empty"]);
+    assert_snapshot("list_with_excerpts", &sandbox.run(&["list", "--excerpt"]).to_string());
+}
+
+#[test]
+fn reapply_template_defaults() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Old", "-t", "work"]);
+    sandbox.write(
+        "tasks/0000000002.todo.md",
+        "+++\nid = 2\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n\
+         effort = \"large\"\n+++\n\n# Other\n",
+    );
+    let template = sandbox.read("templates/task.md.hbs").replacen(
+        "+++\n\n",
+        "effort = \"small\"\nstatus = \"open\"\n\
+         [review]\nby = \"{{#each tags}}{{this}}{{/each}}\"\n+++\n\n",
+        1,
+    );
+    sandbox.write("templates/task.md.hbs", &template);
+
+    let runs = [
+        sandbox.run(&["reapply-template"]),
+        sandbox.run(&["reapply-template", "-t", "work", "--apply", "--porcelain"]),
+        sandbox.run(&["reapply-template", "-t", "work"]),
+    ];
+    assert_snapshot("reapply_template_defaults", &transcript(&runs));
+    assert_snapshot("reapply_template_defaults_file", &sandbox.read("tasks/0000000001.todo.md"));
+}
+
+#[test]
+fn search_bodies_and_front_matter() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = [\"receipts\"]\n+++\n\n\
+         # Taxes 2024\n\nCollect every Receipt from the blue folder, the shoebox and the car glovebox.\n",
+    );
+    sandbox.run(&["new", "--title", "Call about invoice 1234"]);
+    // laid out unlike a rewritten file, so line numbers tell which is searched
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\n# moved over from the old tracker\nid = 3\ntags = [\n  \"receipts\",\n]\n\
+         created_at = \"2025-01-01T09:00:00Z\"\n\n[cancellation]\nreason = \"receipt lost\"\n\
+         at = \"2025-01-02T09:00:00Z\"\n+++\n\n# Scan receipts\n",
+    );
+    let runs = [
+        sandbox.run(&["search", "-i", "receipt"]),
+        sandbox.run(&["search", "created_at"]),
+        sandbox.run(&["search", "cancellation"]),
+        sandbox.run(&["search", "--regex", r"^# [A-Z]\w+ \d+$"]),
+        sandbox.run(&["search", "--regex", r"(invoice|taxes) \d{4}", "-i", "--porcelain"]),
+        sandbox.run(&["search", "--regex", "(unclosed"]),
+        sandbox.run(&["search", "nothing like this", "--quiet"]),
+    ];
+    assert_snapshot("search_bodies_and_front_matter", &transcript(&runs));
+}
+
+#[test]
+fn list_sorted_and_limited() {
+    let sandbox = Sandbox::initialized();
+    for (id, created, due) in [
+        (1, "2025-01-03", Some("2025-05-01")),
+        (2, "2025-01-01", None),
+        (3, "2025-01-02", Some("2025-04-01")),
+        (4, "2025-01-02", Some("2025-05-01")),
+    ] {
+        let due = due.map_or(String::new(), |due| format!("due_at = \"{due}T12:00:00Z\"\n"));
+        sandbox.write(
+            &format!("tasks/000000000{id}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"{created}T09:00:00Z\"\n{due}\
+                 tags = []\n+++\n\n# Todo {id}\n"
+            ),
+        );
+    }
+    let runs = [
+        sandbox.run(&["list", "--sort", "due"]),
+        sandbox.run(&["list", "--sort", "created,due", "--quiet"]),
+        sandbox.run(&["list", "--sort", "due", "--reverse", "--limit", "2", "--quiet"]),
+        sandbox.run(&["list", "--sort", "size"]),
+    ];
+    assert_snapshot("list_sorted_and_limited", &transcript(&runs));
+}
+
+#[test]
+fn tag_and_tags() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "-t", "home", "-t", "errand"]);
+    sandbox.run(&["new", "-t", "work"]);
+    let runs = [
+        sandbox.run(&["tag", "1", "--add", "Work", "--add", "errand", "--remove", "home"]),
+        sandbox.run(&["tag", "1", "--remove", "work", "--remove", "errand"]),
+        sandbox.run(&["tag", "2", "--add", "a,b"]),
+        sandbox.run(&["tag", "2", "--add", "urgent", "--porcelain"]),
+        sandbox.run(&["tags"]),
+    ];
+    assert_snapshot("tag_and_tags", &transcript(&runs));
+}
+
+#[test]
+fn tag_graph_suggests_cleanups() {
+    let sandbox = Sandbox::initialized();
+    let todos: [&[&str]; 7] = [
+        &["backend", "api"],
+        &["backend", "api", "urgent"],
+        &["backend", "api"],
+        &["backend"],
+        &["bakend", "urgent"],
+        &["urgent", "home"],
+        &["misc"],
+    ];
+    for tags in todos {
+        let mut args = vec!["new"];
+        for tag in tags {
+            args.extend(["-t", tag]);
+        }
+        sandbox.run(&args);
+    }
+
+    let runs = [
+        sandbox.run(&["tag", "graph"]),
+        sandbox.run(&["--porcelain", "tag", "graph", "--limit", "2"]),
+        sandbox.run(&["tag", "rename", "bakend", "backend"]),
+        sandbox.run(&["tag", "merge", "bakend", "--into", "backend"]),
+        sandbox.run(&["--porcelain", "tag", "merge", "api", "nothing", "--into", "backend"]),
+        sandbox.run(&["tag", "merge", "api", "--into", "backend"]),
+        sandbox.run(&["tag", "rename", "misc", "Someday Maybe"]),
+        sandbox.run(&["tag", "graph"]),
+        sandbox.run(&["tag", "2", "--remove", "urgent"]),
+        sandbox.run(&["tags"]),
+    ];
+    assert_snapshot("tag_graph_suggests_cleanups", &transcript(&runs));
+}
+
+#[test]
+fn git_merge_driver() {
+    let sandbox = Sandbox::new();
+    let todo = |front_matter: &str, body: &str| {
+        format!(
+            "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\n{front_matter}\n+++\n\n# Plan\n{body}"
+        )
+    };
+    sandbox.write("base.md", &todo("tags = [\"a\", \"b\"]", "\nNotes.\n"));
+    sandbox.write(
+        "ours.md",
+        &todo("due_at = \"2025-02-01T12:00:00Z\"\ntags = [\"a\", \"b\", \"mine\"]", "\nNotes.\n"),
+    );
+    sandbox.write(
+        "theirs.md",
+        &todo(
+            "tags = [\"b\", \"theirs\"]\nstatus = \"done\"\ncompleted_at = \"2025-01-05T10:00:00Z\"",
+            "\nNotes, updated.\n",
+        ),
+    );
+    let mut runs = vec![sandbox.run(&["git-merge-driver", "base.md", "ours.md", "theirs.md"])];
+    let clean = sandbox.read("ours.md");
+
+    sandbox.write("ours.md", &todo("tags = [\"a\", \"b\"]", "\nNotes, mine.\n"));
+    runs.push(sandbox.run(&["git-merge-driver", "base.md", "ours.md", "theirs.md"]));
+    assert_snapshot("git_merge_driver", &transcript(&runs));
+    assert_snapshot("git_merge_driver_clean", &clean);
+    assert_snapshot("git_merge_driver_conflict", &sandbox.read("ours.md"));
+}
+
+#[test]
+fn due_set_and_clear() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Ship it"]);
+    let runs = [
+        sandbox.run(&["due", "1", "2025-03-01"]),
+        sandbox.run(&["--porcelain", "due", "1", "2025-03-01T12:00:00+02:00"]),
+        sandbox.run(&["due", "1", "someday"]),
+        sandbox.run(&["due", "1"]),
+        sandbox.run(&["list"]),
+        sandbox.run(&["--porcelain", "due", "1", "--clear"]),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("due_set_and_clear", &transcript(&runs));
+}
+
+#[test]
+fn priority_set_filter_and_sort() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Someday"]);
+    sandbox.run(&["new", "--title", "Minor", "--priority", "low"]);
+    sandbox.run(&["new", "--title", "Urgent"]);
+    let runs = [
+        sandbox.run(&["priority", "3", "high"]),
+        sandbox.run(&["--porcelain", "priority", "1", "medium"]),
+        sandbox.run(&["priority", "1", "whenever"]),
+        sandbox.run(&["list", "--sort", "priority"]),
+        sandbox.run(&["list", "--priority", "low"]),
+        sandbox.run(&["priority", "1", "--clear"]),
+        sandbox.run(&["list", "--sort", "priority"]),
+    ];
+    assert_snapshot("priority_set_filter_and_sort", &transcript(&runs));
+    assert_snapshot(
+        "priority_set_filter_and_sort_file",
+        &sandbox.read("tasks/0000000003.todo.md"),
+    );
+}
+
+#[test]
+fn serve_captures_over_a_socket() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let sandbox = Sandbox::initialized();
+    let socket = sandbox.dir.join("todo.sock");
+    let mut server = Command::new(env!("CARGO_BIN_EXE_todo"))
+        .arg("--data-dir")
+        .arg(&sandbox.dir)
+        .arg("serve")
+        .arg("--socket")
+        .arg(&socket)
+        .stdout(std::process::Stdio::null())
+        .spawn()
+        .unwrap();
+    let stream = (0..100)
+        .find_map(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            UnixStream::connect(&socket).ok()
+        })
+        .expect("server did not start");
+
+    let mut writer = stream.try_clone().unwrap();
+    let mut lines = BufReader::new(stream).lines();
+    let mut responses = String::new();
+    for request in [
+        r#"{"title": "Call back", "tags": ["work"]}"#,
+        r#"{"title": "Buy milk"}"#,
+        r#"{"title": "Nope", "template": "missing"}"#,
+        r#"{"name": "Nope"}"#,
+    ] {
+        writeln!(writer, "{request}").unwrap();
+        responses.push_str(&format!("> {request}\n< {}\n", lines.next().unwrap().unwrap()));
+    }
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let runs = [sandbox.run(&["list"])];
+    assert_snapshot(
+        "serve_captures_over_a_socket",
+        &format!("{}\n{}", sandbox.redact(&responses), transcript(&runs)),
+    );
+}
+
+#[test]
+fn relative_due_dates() {
+    use chrono::{Datelike, Days, Months, Utc};
+
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Ship it", "--due", "tomorrow"]);
+    let due = |input: &str| {
+        let run = sandbox.run(&["--porcelain", "due", "1", input]);
+        assert_eq!(run.code, 0, "{input}: {}", run.stderr);
+        run.stdout.trim_end().rsplit('\t').next().unwrap()[..10].to_string()
+    };
+
+    // the sandbox runs in UTC
+    let today = Utc::now().date_naive();
+    let days = |n| today.checked_add_days(Days::new(n)).unwrap().to_string();
+    let until_friday = (chrono::Weekday::Fri.days_since(today.weekday()) + 6) % 7 + 1;
+    let listed = sandbox.run(&["--porcelain", "list"]).stdout;
+    assert_eq!(listed.split('\t').nth(3).unwrap()[..10], days(1));
+    assert_eq!(due("eod"), days(0));
+    assert_eq!(due("Tomorrow"), days(1));
+    assert_eq!(due("in 3 days"), days(3));
+    assert_eq!(due("in 2 weeks"), days(14));
+    assert_eq!(due("next week"), days(7));
+    assert_eq!(due("next fri"), days(until_friday.into()));
+    assert_eq!(
+        due("in 1 month"),
+        today.checked_add_months(Months::new(1)).unwrap().to_string()
+    );
+}
+
+#[test]
+fn snooze_pushes_the_due_date() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Ship it", "--due", "2025-03-01"]);
+    sandbox.run(&["new", "--title", "Someday"]);
+    let runs = [
+        sandbox.run(&["snooze", "1", "3d"]),
+        sandbox.run(&["--porcelain", "snooze", "1", "12h"]),
+        sandbox.run(&["snooze", "1", "2w"]),
+        sandbox.run(&["snooze", "1", "1m"]),
+        sandbox.run(&["snooze", "1", "3x"]),
+        sandbox.run(&["snooze", "1", "d"]),
+        sandbox.run(&["--quiet", "snooze", "2", "1d"]),
+    ];
+    assert_snapshot("snooze_pushes_the_due_date", &transcript(&runs));
+}
+
+#[test]
+fn validate_staged_files() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Committed"]);
+    sandbox.git("", &["init", "--quiet"]);
+    sandbox.git("", &["add", "tasks"]);
+    sandbox.git("", &["commit", "--quiet", "-m", "initial"]);
+
+    sandbox.write(
+        "tasks/0000000002.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = [\"Work\", \"a b!\"]\n+++\n\n# Copy\n",
+    );
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = 2025\n+++\n\n# Broken\n",
+    );
+    sandbox.write("tasks/0000000004.todo.md", "# No front matter\n");
+    sandbox.git("", &["add", "tasks"]);
+    let staged = sandbox.run(&["validate", "--staged"]);
+    let porcelain = sandbox.run(&["--porcelain", "validate", "--staged"]);
+
+    // fixing the working copy doesn't help until the fix is staged
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n+++\n\n# Fixed\n",
+    );
+    let unstaged_fix = sandbox.run(&["validate", "--staged"]);
+    std::fs::remove_file(sandbox.dir.join("tasks/0000000002.todo.md")).unwrap();
+    std::fs::remove_file(sandbox.dir.join("tasks/0000000004.todo.md")).unwrap();
+    let runs = [staged, porcelain, unstaged_fix, sandbox.run(&["validate"])];
+    assert_snapshot("validate_staged_files", &transcript(&runs));
+}
+
+#[test]
+fn migrate_warns_about_files_it_cannot_read() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.write("tasks/0000000002.todo.md", "+++\nid = \"two\"\n+++\n\n# Broken\n");
+    sandbox.write("archive/2024/02/0000000003.todo.md", "no front matter\n");
+    let runs = [
+        sandbox.run(&["migrate", "--front-matter", "yaml"]),
+        sandbox.run(&["migrate", "--front-matter", "yaml"]),
+    ];
+    assert_snapshot("migrate_warns_about_files_it_cannot_read", &transcript(&runs));
+}
+
+#[test]
+fn front_matter_styles_convert_losslessly() {
+    let sandbox = Sandbox::initialized();
+    let original = "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\n\
+                    due_at = \"2099-01-31T23:59:59Z\"\ntags = [\"a\", \"b c\"]\n\
+                    priority = \"high\"\nowner = \"kim # lead\"\nestimate = 1.5\n\
+                    reviewed = 2025-01-02\n\n\
+                    [source]\nkind = \"trello\"\nids = [1, 2]\n\n+++\n\n# Plan\n\n---\n\nNotes.\n";
+    sandbox.write("tasks/0000000001.todo.md", original);
+    sandbox.run(&["new", "--title", "Ship", "-t", "release"]);
+    sandbox.run(&["done", "2"]);
+    sandbox.run(&["--quiet", "archive", "2"]);
+
+    let mut runs = vec![
+        sandbox.run(&["--porcelain", "migrate", "--front-matter", "yaml"]),
+        sandbox.run(&["migrate", "--front-matter", "yaml"]),
+        sandbox.run(&["show", "1"]),
+        sandbox.run(&["search", "kim"]),
+    ];
+    let yaml = sandbox.read("tasks/0000000001.todo.md");
+
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "---\nid: 3  # written by hand\ncreated_at: '2025-01-03T09:00:00Z'\ntags:\n  - home\n\
+         due_at: ~\nblocked_by:\n  - on: 1\n    since: \"2025-01-03T10:00:00Z\"\n\
+         \x20 - on: the vendor\n    since: \"2025-01-03T10:00:00Z\"\n\
+         \x20   cleared: {at: \"2025-01-04T10:00:00Z\"}\n\
+         ---\n\n# Paint the fence\n",
+    );
+    sandbox.write(
+        "tasks/0000000004.todo.md",
+        "---\nid: 4\ncreated_at: \"2025-01-03T09:00:00Z\"\ntags: [home\n---\n\n# Broken\n",
+    );
+    runs.push(sandbox.run(&["validate"]));
+    std::fs::remove_file(sandbox.dir.join("tasks/0000000004.todo.md")).unwrap();
+    runs.push(sandbox.run(&["list"]));
+
+    sandbox.write("config.toml", "[files]\nfront_matter = \"yaml\"\n");
+    runs.push(sandbox.run(&["new", "--title", "Sand the fence"]));
+    let created = sandbox.read("tasks/0000000004.todo.md");
+    runs.push(sandbox.run(&["migrate", "--front-matter", "toml"]));
+    assert_snapshot("front_matter_styles_convert_losslessly", &transcript(&runs));
+    let handwritten = sandbox.read("tasks/0000000003.todo.md");
+    assert_snapshot(
+        "front_matter_styles_convert_losslessly_files",
+        &format!("{yaml}\n{created}\n{handwritten}"),
+    );
+
+    let converted_back = std::fs::read_to_string(sandbox.dir.join("tasks/0000000001.todo.md"));
+    assert_eq!(converted_back.unwrap(), original);
+}
+
+#[test]
+fn heatmap_of_a_year() {
+    let sandbox = Sandbox::initialized();
+    let todo = |id: u32, created_at: &str, completed_at: Option<&str>| {
+        let completion = completed_at
+            .map(|at| format!("status = \"done\"\ncompleted_at = \"{at}\"\n"))
+            .unwrap_or_default();
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!("+++\nid = {id}\ncreated_at = \"{created_at}\"\ntags = []\n{completion}+++\n\n# Task {id}\n"),
+        );
+    };
+    todo(1, "2024-01-01T09:00:00Z", Some("2024-01-03T09:00:00Z"));
+    todo(2, "2024-01-01T10:00:00Z", Some("2024-01-03T10:00:00Z"));
+    todo(3, "2024-03-15T09:00:00Z", Some("2024-03-16T09:00:00Z"));
+    todo(4, "2024-06-30T09:00:00Z", Some("2025-01-02T09:00:00Z"));
+    todo(5, "2024-12-31T09:00:00Z", None);
+    let runs = [
+        sandbox.run(&["heatmap", "--year", "2024"]),
+        sandbox.run(&["heatmap", "--year", "2024", "--metric", "created"]),
+        sandbox.run(&["--porcelain", "heatmap", "--year", "2024"]),
+        sandbox.run(&["--quiet", "heatmap", "--year", "2025"]),
+    ];
+    assert_snapshot("heatmap_of_a_year", &transcript(&runs));
+}
+
+#[test]
+fn today_and_overdue_agenda() {
+    let sandbox = Sandbox::initialized();
+    let empty = sandbox.run(&["today"]);
+    for (title, due) in [
+        ("Renew passport", "2021-06-01"),
+        ("File taxes", "2020-04-15"),
+        ("Stand-up notes", "eod"),
+        ("Book flights", "in 3 days"),
+        ("Plan offsite", "in 30 days"),
+        ("Old and done", "2020-01-01"),
+    ] {
+        sandbox.run(&["new", "--title", title, "--due", due]);
+    }
+    sandbox.run(&["done", "6"]);
+
+    let agenda = sandbox.run(&["--porcelain", "today"]);
+    let grouped: Vec<String> = agenda
+        .stdout
+        .lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            format!("{} {}", fields[1], fields[2])
+        })
+        .collect();
+    assert_eq!(grouped, ["overdue 2", "overdue 1", "today 3", "week 4"]);
+    assert_eq!(sandbox.run(&["--quiet", "today"]).stdout, "2\n1\n3\n4\n");
+
+    let runs = [empty, sandbox.run(&["overdue"])];
+    assert_snapshot("today_and_overdue_agenda", &transcript(&runs));
+}
+
+#[test]
+fn replicate_by_query() {
+    let sandbox = Sandbox::initialized();
+    let mirror = Sandbox::initialized();
+    mirror.run(&["new", "--title", "Mirror's own"]);
+    mirror.run(&["new", "--title", "Another one"]);
+    sandbox.run(&["new", "--title", "Private"]);
+    sandbox.run(&["new", "--title", "Release notes", "-t", "public"]);
+    sandbox.run(&["new", "--title", "Roadmap", "-t", "public"]);
+    let to = mirror.dir.to_str().unwrap();
+
+    let mut runs = vec![
+        sandbox.run(&["replicate", "--query", "tag:public", "--to", to]),
+        sandbox.run(&["--porcelain", "replicate", "--query", "tag:public", "--to", to]),
+    ];
+    sandbox.run(&["tag", "2", "--remove", "public"]);
+    sandbox.run(&["done", "3"]);
+    sandbox.run(&["new", "--title", "Changelog", "-t", "public"]);
+    runs.extend([
+        sandbox.run(&["replicate", "--query", "tag:public", "--to", to]),
+        sandbox.run(&["replicate", "--query", "tag:public status:later", "--to", to]),
+        sandbox.run(&["replicate", "--query", "public", "--to", to]),
+        mirror.run(&["list", "--status", "done"]),
+        mirror.run(&["list"]),
+    ]);
+    assert_snapshot("replicate_by_query", &transcript(&runs).replace(to, "[MIRROR]"));
+    assert_snapshot(
+        "replicate_by_query_mapping",
+        &sandbox.read("replicas.toml").replace(to, "[MIRROR]"),
+    );
+}
+
+#[test]
+fn weekly_agenda() {
+    use chrono::{Days, Utc};
+
+    let sandbox = Sandbox::initialized();
+    for (title, due) in [
+        ("Overdue", Some("2020-04-15")),
+        ("Later today", Some("eod")),
+        ("In two days", Some("in 2 days")),
+        ("Also in two days", Some("in 2 days")),
+        ("Next month", Some("in 30 days")),
+        ("Someday", None),
+    ] {
+        let mut args = vec!["new", "--title", title];
+        args.extend(due.iter().flat_map(|due| ["--due", *due]));
+        sandbox.run(&args);
+    }
+
+    // the sandbox runs in UTC
+    let today = Utc::now().date_naive();
+    let in_two_days = today.checked_add_days(Days::new(2)).unwrap();
+    let expected = [
+        "overdue\t1\t2020-04-15\tOverdue".to_string(),
+        format!("day\t2\t{today}\tLater today"),
+        format!("day\t3\t{in_two_days}\tIn two days"),
+        format!("day\t4\t{in_two_days}\tAlso in two days"),
+        "unscheduled\t6\t\tSomeday".to_string(),
+    ];
+    let porcelain = sandbox.run(&["--porcelain", "agenda", "--week"]).stdout;
+    let records: Vec<&str> = porcelain.lines().map(|line| &line[3..]).collect();
+    assert_eq!(records, expected);
+
+    let human = sandbox.run(&["agenda", "--days", "3"]).stdout;
+    let headings: Vec<&str> = human.lines().filter(|line| line.ends_with(':')).collect();
+    let day = |offset| {
+        let day = today.checked_add_days(Days::new(offset)).unwrap();
+        day.format("%a %Y-%m-%d").to_string()
+    };
+    assert_eq!(
+        headings,
+        [
+            "overdue:".to_string(),
+            format!("{} (today):", day(0)),
+            format!("{}:", day(1)),
+            format!("{}:", day(2)),
+            "unscheduled:".to_string(),
+        ]
+    );
+    assert_eq!(sandbox.run(&["--quiet", "agenda", "--days", "1"]).stdout, "1\n2\n6\n");
+}
+
+#[test]
+fn archive_closed_todos() {
+    let sandbox = Sandbox::initialized();
+    let todo = |id: u32, status: &str| {
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"2024-01-01T09:00:00Z\"\ntags = []\n{status}+++\n\n# Task {id}\n"
+            ),
+        );
+    };
+    todo(1, "status = \"done\"\ncompleted_at = \"2024-02-10T09:00:00Z\"\n");
+    todo(
+        2,
+        "status = \"cancelled\"\n\n[cancellation]\nat = \"2024-03-05T09:00:00Z\"\nreason = \"moot\"\n",
+    );
+    todo(3, "");
+    sandbox.run(&["new", "--title", "Just finished"]);
+    sandbox.run(&["done", "4"]);
+
+    let runs = [
+        sandbox.run(&["archive", "--done", "--older-than", "30d"]),
+        sandbox.run(&["--porcelain", "archive", "--done", "--older-than", "30d"]),
+        sandbox.run(&["archive", "--cancelled", "--older-than", "30d"]),
+        sandbox.run(&["archive", "--older-than", "30d"]),
+        sandbox.run(&["archive", "4", "--done"]),
+        sandbox.run(&["list", "--status", "done"]),
+        sandbox.run(&["list", "--status", "done", "--include-archived"]),
+        sandbox.run(&["show", "1"]),
+        sandbox.run(&["--quiet", "show", "1", "--include-archived"]),
+        sandbox.run(&["--quiet", "archive", "4"]),
+        sandbox.run(&["--porcelain", "new", "--title", "After archiving"]),
+    ];
+    assert_snapshot("archive_closed_todos", &transcript(&runs));
+}
+
+#[test]
+fn archive_keeps_going_past_failures() {
+    let sandbox = Sandbox::initialized();
+    for id in 1..=3 {
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"2024-01-01T09:00:00Z\"\ntags = []\n\
+                 status = \"done\"\ncompleted_at = \"2024-02-10T09:00:00Z\"\n+++\n\n# Task {id}\n"
+            ),
+        );
+    }
+    sandbox.write("archive/2024/02/0000000002.todo.md", "taken\n");
+
+    let runs = [
+        sandbox.run(&["archive", "--done"]),
+        sandbox.run(&["--quiet", "list", "--status", "done"]),
+    ];
+    assert_snapshot("archive_keeps_going_past_failures", &transcript(&runs));
+}
+
+#[test]
+fn acceptance_criteria_block_done() {
+    let sandbox = Sandbox::initialized();
+    let todo = |id: u32, criteria: &str| {
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n+++\n\n# Ship login\n\n\
+                 ## Acceptance Criteria\n\n{criteria}\n\n## Steps\n\n- [x] branch\n- [ ] review\n- [ ] merge\n"
+            ),
+        );
+    };
+    todo(1, "- [x] users can log in\n- [ ] wrong passwords are rejected\n- [ ] sessions expire");
+    todo(2, "- [x] users can log in");
+    todo(3, "- [ ] nobody checked this yet");
+    let mut runs = vec![
+        sandbox.run(&["show", "1"]),
+        sandbox.run(&["done", "1"]),
+        sandbox.run(&["done", "2"]),
+    ];
+    sandbox.write("config.toml", "[done]\nrequire_acceptance_criteria = false\n");
+    runs.push(sandbox.run(&["done", "3"]));
+    assert_snapshot("acceptance_criteria_block_done", &transcript(&runs));
+}
+
+#[test]
+fn reopen_restores_todos() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.run(&["new", "--title", "Feed cat"]);
+    sandbox.run(&["new", "--title", "Walk dog"]);
+    sandbox.run(&["done", "1"]);
+    sandbox.run(&["cancel", "2", "--reason", "no cat"]);
+    sandbox.run(&["archive", "2"]);
+    sandbox.run(&["done", "3"]);
+    sandbox.run(&["archive", "3"]);
+    // something took the archived todo's place in the meantime
+    sandbox.write("tasks/0000000003.todo.md", "not a todo\n");
+
+    let runs = [
+        sandbox.run(&["reopen", "1"]),
+        sandbox.run(&["reopen", "1"]),
+        sandbox.run(&["--porcelain", "reopen", "2"]),
+        sandbox.run(&["reopen", "3"]),
+        sandbox.run(&["reopen", "4"]),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("reopen_restores_todos", &transcript(&runs));
+    assert_snapshot("reopen_restores_todos_file", &sandbox.read("tasks/0000000002.todo.md"));
+}
+
+#[test]
+fn show_activity() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2024-12-30T08:00:00Z\"\ntags = []\n+++\n\n# Renew passport\n",
+    );
+    let outside_git = sandbox.run(&["--porcelain", "show", "1", "--activity"]);
+
+    sandbox.git("", &["init", "--quiet"]);
+    sandbox.git("", &["add", "tasks"]);
+    sandbox.git("", &["commit", "--quiet", "-m", "Add passport todo"]);
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2024-12-30T08:00:00Z\"\ntags = []\nstatus = \"cancelled\"\n\n\
+         [cancellation]\nat = \"2025-01-03T10:00:00Z\"\nreason = \"not travelling\"\n+++\n\n# Renew passport\n",
+    );
+    sandbox.git("", &["commit", "--quiet", "-am", "Cancel passport todo"]);
+
+    let runs = [outside_git, sandbox.run(&["show", "1", "--activity"])];
+    assert_snapshot("show_activity", &transcript(&runs));
+}
+
+#[test]
+fn generate_is_reproducible() {
+    let sandbox = Sandbox::new();
+    let runs = [
+        sandbox.run(&["generate", "--count", "30", "--seed", "42", "--into", "."]),
+        sandbox.run(&["--porcelain", "generate", "--count", "30", "--seed", "42", "--into", "b"]),
+        sandbox.run(&["list", "--status", "done", "--quiet"]),
+        sandbox.run(&["list", "--priority", "high", "--quiet"]),
+        sandbox.run(&["validate"]),
+    ];
+    assert_snapshot("generate_is_reproducible", &transcript(&runs));
+    for id in 1..=30 {
+        let path = format!("tasks/{id:010}.todo.md");
+        assert_eq!(sandbox.read(&path), sandbox.read(&format!("b/{path}")));
+    }
+}
+
+#[test]
+fn diff_branches_reports_task_changes() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants", "--due", "2099-03-01"]);
+    sandbox.run(&["new", "--title", "Pay rent"]);
+    sandbox.run(&["new", "--title", "Call mum", "-t", "family"]);
+    sandbox.run(&["new", "--title", "Old idea"]);
+    sandbox.run(&["new", "--title", "Untouched"]);
+    sandbox.git("", &["init", "--quiet", "--initial-branch", "main"]);
+    sandbox.git("", &["add", "tasks"]);
+    sandbox.git("", &["commit", "--quiet", "-m", "Plan the week"]);
+
+    sandbox.git("", &["checkout", "--quiet", "-b", "feature"]);
+    sandbox.run(&["due", "1", "2099-03-05"]);
+    sandbox.run(&["done", "2"]);
+    let call = sandbox.dir.join("tasks/0000000003.todo.md");
+    let retitled = std::fs::read_to_string(&call).unwrap().replace("Call mum", "Call mum and dad");
+    std::fs::write(&call, retitled).unwrap();
+    sandbox.run(&["tag", "3", "--add", "weekend"]);
+    sandbox.run(&["delete", "4", "--purge"]);
+    sandbox.run(&["new", "--title", "Book flights"]);
+    sandbox.git("", &["add", "--all", "tasks"]);
+    sandbox.git("", &["commit", "--quiet", "-m", "Work through the week"]);
+
+    let runs = [
+        sandbox.run(&["diff-branches", "main", "feature"]),
+        sandbox.run(&["diff-branches", "main"]),
+        sandbox.run(&["diff-branches", "feature", "main"]),
+        sandbox.run(&["diff-branches", "main", "main"]),
+        sandbox.run(&["--porcelain", "diff-branches", "main"]),
+        sandbox.run(&["--quiet", "diff-branches", "main"]),
+        sandbox.run(&["diff-branches", "nowhere"]),
+    ];
+    assert_snapshot("diff_branches_reports_task_changes", &transcript(&runs));
+}
+
+#[test]
+fn diff_ignores_configured_fields() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write("config.toml", "[diff]\nignore_fields = [\"updated_at\"]\n");
+    let todo = |updated_at: &str, body: &str| {
+        format!(
+            "+++\nid = 1\ncreated_at = \"2024-12-30T08:00:00Z\"\ntags = []\n\
+             updated_at = \"{updated_at}\"\n+++\n\n# Water plants\n{body}"
+        )
+    };
+    sandbox.write("tasks/0000000001.todo.md", &todo("2025-01-01T08:00:00Z", ""));
+    sandbox.git("", &["init", "--quiet"]);
+    sandbox.git("", &["add", "tasks"]);
+    sandbox.git("", &["commit", "--quiet", "-m", "Add plants todo"]);
+    sandbox.write("tasks/0000000001.todo.md", &todo("2025-01-02T08:00:00Z", ""));
+    sandbox.git("", &["commit", "--quiet", "-am", "Sync"]);
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        &todo("2025-01-03T08:00:00Z", "\nThe ones on the balcony too.\n"),
+    );
+    sandbox.git("", &["commit", "--quiet", "-am", "Mention the balcony"]);
+
+    let runs = [
+        sandbox.run(&["diff-branches", "HEAD~2", "HEAD~1"]),
+        sandbox.run(&["diff-branches", "HEAD~2"]),
+        sandbox.run(&["show", "1", "--activity"]),
+    ];
+    assert_snapshot("diff_ignores_configured_fields", &transcript(&runs));
+}
+
+#[test]
+fn status_lifecycle() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Write report"]);
+    sandbox.run(&["new", "--title", "Order parts"]);
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.run(&["done", "3"]);
+    let runs = [
+        sandbox.run(&["status", "1", "in-progress"]),
+        sandbox.run(&["--porcelain", "status", "2", "blocked"]),
+        sandbox.run(&["status", "2", "cancelled"]),
+        sandbox.run(&["list"]),
+        sandbox.run(&["list", "--status", "blocked,done", "--quiet"]),
+        sandbox.run(&["status", "3", "open"]),
+        sandbox.run(&["reopen", "1"]),
+        sandbox.run(&["status", "1", "done"]),
+        sandbox.run(&["list", "--status", "open,done", "--quiet"]),
+    ];
+    assert_snapshot("status_lifecycle", &transcript(&runs));
+    assert_snapshot("status_lifecycle_file", &sandbox.read("tasks/0000000002.todo.md"));
+}
+
+#[test]
+fn time_tracking_report() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = [\"work\"]\n\n\
+         [[time_entries]]\nstart = \"2025-01-06T09:00:00Z\"\nend = \"2025-01-06T10:30:00Z\"\n\
+         +++\n\n# Write report\n",
+    );
+    sandbox.write(
+        "tasks/0000000002.todo.md",
+        "+++\nid = 2\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = [\"work\", \"docs\"]\n\n\
+         [[time_entries]]\nstart = \"2025-01-06T11:00:00Z\"\nend = \"2025-01-06T11:20:00Z\"\n\n\
+         [[time_entries]]\nstart = \"2025-01-07T08:00:00Z\"\nend = \"2025-01-07T08:25:00Z\"\n\
+         +++\n\n# Update docs\n",
+    );
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n+++\n\n# Water plants\n",
+    );
+    let runs = [
+        sandbox.run(&["time", "report"]),
+        sandbox.run(&["--porcelain", "time", "report"]),
+        sandbox.run(&["time", "report", "--week"]),
+        sandbox.run(&["stop"]),
+        sandbox.run(&["--quiet", "start", "3"]),
+        sandbox.run(&["--quiet", "start", "1"]),
+        sandbox.run(&["start", "1"]),
+        sandbox.run(&["--quiet", "stop"]),
+        sandbox.run(&["list", "--status", "in-progress", "--quiet"]),
+    ];
+    assert_snapshot("time_tracking_report", &transcript(&runs));
+}
+
+#[test]
+fn sandbox_apply_and_discard() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Plan trip"]);
+    sandbox.run(&["new", "--title", "Book hotel"]);
+
+    let opened = sandbox.run(&["--quiet", "sandbox", "open"]);
+    let tried = PathBuf::from(opened.stdout.trim());
+    let mut runs = vec![
+        sandbox.run(&["sandbox", "open"]),
+        sandbox.run_in(&tried, &["due", "1", "2025-03-01"]),
+        sandbox.run_in(&tried, &["--quiet", "new", "--title", "Rent a car"]),
+        sandbox.run_in(&tried, &["done", "2"]),
+        sandbox.run(&["sandbox", "diff"]),
+        sandbox.run(&["list"]),
+        sandbox.run(&["sandbox", "apply"]),
+        sandbox.run(&["list", "--status", "open,done"]),
+        sandbox.run(&["sandbox", "diff"]),
+    ];
+
+    sandbox.run(&["sandbox", "open"]);
+    sandbox.run(&["new", "--title", "Pack bags"]);
+    runs.push(sandbox.run(&["sandbox", "apply"]));
+    runs.push(sandbox.run(&["sandbox", "discard"]));
+    runs.push(sandbox.run(&["list", "--quiet"]));
+
+    let transcript = transcript(&runs).replace(tried.to_str().unwrap(), "[SANDBOX]");
+    assert_snapshot("sandbox_apply_and_discard", &transcript);
+}
+
+#[test]
+fn groom_suggestions() {
+    let sandbox = Sandbox::initialized();
+    let todo = |id: u32, title: &str, extra: &str| {
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"2024-01-01T09:00:00Z\"\ntags = []\n{extra}+++\n\n\
+                 # {title}\n"
+            ),
+        )
+    };
+    todo(1, "Fix login", "");
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.run(&["new", "--title", "fix  Login!"]);
+    sandbox.run(&["new", "--title", "Renew passport", "--priority", "high"]);
+    sandbox.run(&["due", "4", "2024-02-01"]);
+    todo(5, "Old idea", "");
+
+    let runs = [
+        sandbox.run(&["groom", "--dry-run"]),
+        sandbox.run(&["--porcelain", "groom", "--dry-run", "--older-than", "1m"]),
+        sandbox.run_with_input(&["groom"], "y\nn\nyes\n"),
+        sandbox.run(&["list", "--status", "cancelled", "--excerpt"]),
+        sandbox.run(&["groom", "--yes"]),
+        sandbox.run(&["groom"]),
+        sandbox.run(&["groom", "--older-than", "soon"]),
+    ];
+    assert_snapshot("groom_suggestions", &transcript(&runs));
+}
+
+#[test]
+fn subtasks_tree() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Move house"]);
+    sandbox.run(&["new", "--title", "Water plants"]);
+    sandbox.run(&["new", "--title", "Pack boxes", "--parent", "1"]);
+    sandbox.run(&["new", "--title", "Buy tape", "--parent", "3"]);
+    sandbox.run(&["new", "--title", "Book van", "--parent", "1"]);
+    sandbox.run(&["done", "5"]);
+    let runs = [
+        sandbox.run(&["new", "--title", "Orphan", "--parent", "42"]),
+        sandbox.run(&["list", "--tree"]),
+        sandbox.run(&["list", "--tree", "--sort", "id", "--reverse", "--quiet"]),
+        sandbox.run(&["subtasks", "1"]),
+        sandbox.run(&["--porcelain", "subtasks", "1"]),
+        sandbox.run(&["subtasks", "2"]),
+        sandbox.run(&["done", "1"]),
+    ];
+    assert_snapshot("subtasks_tree", &transcript(&runs));
+    assert_snapshot("subtasks_tree_file", &sandbox.read("tasks/0000000003.todo.md"));
+}
+
+#[test]
+fn titles_are_normalized() {
+    let configs = [
+        "",
+        "[title]\ncasing = \"sentence\"\n",
+        "[title]\ncasing = \"title\"\n",
+        "[title]\nstrip_emoji = true\n",
+        "[title]\nmax_length = 12\n",
+        "[title]\nmax_length = 12\ntruncate = \"cut\"\n",
+        "[title]\nmax_length = 12\ntruncate = \"reject\"\n",
+    ];
+    let titles = ["  ship   the release\tof v2 🚀 ", "the 🎉 lord of the rings", "🚀 ✅", "short"];
+
+    let mut transcripts = vec![];
+    for config in configs {
+        let sandbox = Sandbox::initialized();
+        sandbox.write("config.toml", config);
+        let mut runs: Vec<Run> = titles
+            .iter()
+            .map(|title| sandbox.run(&["--quiet", "new", "--title", title]))
+            .collect();
+        runs.push(sandbox.run(&["list"]));
+        transcripts.push(format!("# config.toml: {config:?}\n\n{}", transcript(&runs)));
+    }
+    assert_snapshot("titles_are_normalized", &transcripts.join("\n"));
+}
+
+#[test]
+fn print_renders_html_pages() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Fix <b> & \"quotes\"", "-t", "work"]);
+    sandbox.run(&["new", "--title", "Water plants", "--due", "2099-03-01", "-t", "home"]);
+    sandbox.run(&["new", "--title", "Pay rent", "--due", "2099-02-01", "-t", "home"]);
+    sandbox.run(&["done", "3"]);
+    sandbox.run(&["cancel", "1", "--reason", "not <ours>"]);
+    let plants = sandbox.dir.join("tasks/0000000002.todo.md");
+    let body = "\nUse the **blue** can.\n\n## Steps\n\n- [x] kitchen\n- [ ] balcony\n";
+    let mut content = std::fs::read_to_string(&plants).unwrap();
+    content.push_str(body);
+    std::fs::write(&plants, content).unwrap();
+
+    // the page title carries the day it was printed
+    let undated = |html: &str| -> String {
+        html.lines()
+            .map(|line| match line.find("Todos ") {
+                Some(at) => format!("{}Todos [DAY]{}", &line[..at], &line[at + 16..]),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let mut runs = vec![
+        sandbox.run(&["print"]),
+        sandbox.run(&["print", "--layout", "cards", "-t", "home"]),
+        sandbox.run(&["print", "-t", "nothing", "--output", "empty.html"]),
+    ];
+    for run in &mut runs {
+        run.stdout = undated(&run.stdout);
+    }
+    assert_snapshot("print_renders_html_pages", &transcript(&runs));
+    assert_snapshot("print_renders_html_pages_file", &undated(&sandbox.read("empty.html")));
+}
+
+#[test]
+fn checklist_add_injects_named_checklists() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "templates/checklists/release.md",
+        "## Release\n\n- [ ] bump the version\n- [ ] tag\n",
+    );
+    sandbox.write("templates/checklists/review.md", "- [ ] read the diff\n- [ ] run it\n");
+    sandbox.run(&["new", "--title", "Ship 1.2"]);
+    sandbox.run(&["new", "--title", "Ship 1.3"]);
+    let marked = sandbox.dir.join("tasks/0000000002.todo.md");
+    let mut content = std::fs::read_to_string(&marked).unwrap();
+    content.push_str("Intro.\n\n<!-- checklists -->\n\n## Notes\n\nKeep this last.\n");
+    std::fs::write(&marked, content).unwrap();
+
+    let runs = [
+        sandbox.run(&["checklist", "add", "1", "--from", "release"]),
+        sandbox.run(&["checklist", "add", "1", "--from", "review"]),
+        sandbox.run(&["checklist", "add", "1", "--from", "release"]),
+        sandbox.run(&["--porcelain", "checklist", "add", "2", "--from", "release"]),
+        sandbox.run(&["checklist", "add", "2", "--from", "missing"]),
+        sandbox.run(&["get", "1", "checklist"]),
+    ];
+    assert_snapshot("checklist_add_injects_named_checklists", &transcript(&runs));
+    assert_snapshot(
+        "checklist_add_injects_named_checklists_end",
+        &sandbox.read("tasks/0000000001.todo.md"),
+    );
+    assert_snapshot(
+        "checklist_add_injects_named_checklists_marker",
+        &sandbox.read("tasks/0000000002.todo.md"),
+    );
+}
+
+#[test]
+fn copy_renders_to_the_clipboard() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "tasks/0000000001.todo.md",
+        "+++\nid = 1\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n+++\n\n\
+         # Release 1.2\n\nShip it **today**, see `CHANGELOG`.\n\n## Steps\n\n\
+         - [x] tag\n- [ ] publish\n- announce\n\n```\ncargo publish\n```\n",
+    );
+    // the first clipboard tool looked for, keeping what it is given
+    sandbox.write("bin/pbcopy", "#!/bin/sh\ncat > clipboard\n[ ! -e fail ]\n");
+    let pbcopy = sandbox.dir.join("bin/pbcopy");
+    std::fs::set_permissions(&pbcopy, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", pbcopy.parent().unwrap().display(), std::env::var("PATH").unwrap());
+    let env = [("PATH", path)];
+
+    let mut runs = vec![];
+    let mut clipboards = vec![];
+    for markup in ["markdown", "plain", "jira", "html"] {
+        runs.push(sandbox.run_with_env(&["copy", "1", "--as", markup], &env));
+        clipboards.push(format!("--- {markup}\n{}", sandbox.read("clipboard")));
+    }
+    runs.push(sandbox.run_with_env(&["--porcelain", "copy", "1"], &env));
+    runs.push(sandbox.run_with_env(&["copy", "2"], &env));
+    sandbox.write("fail", "");
+    runs.push(sandbox.run_with_env(&["copy", "1"], &env));
+    let nowhere = sandbox.dir.join("empty").display().to_string();
+    runs.push(sandbox.run_with_env(&["copy", "1"], &[("PATH", nowhere)]));
+    assert_snapshot("copy_renders_to_the_clipboard", &transcript(&runs));
+    assert_snapshot("copy_renders_to_the_clipboard_contents", &clipboards.join("\n"));
+}
+
+/// A stand-in for the GitHub CLI that numbers issues from 1, logs each
+/// request and fails for titles containing "Flaky" while `fail` exists.
+const FAKE_GH: &str = r#"#!/bin/sh
+echo "$*" >> gh.log
+case "$2" in
+  rate_limit) echo '{"resources":{"core":{"remaining":5000,"reset":0}}}'; exit 0 ;;
+esac
+case "$*" in
+  *Flaky*) if [ -e fail ]; then echo "HTTP 403: secondary rate limit" >&2; exit 1; fi ;;
+esac
+n=$(( $(cat counter 2>/dev/null || echo 0) + 1 ))
+echo $n > counter
+echo "{\"number\": $n, \"html_url\": \"https://github.com/acme/plans/issues/$n\"}"
+"#;
+
+#[test]
+fn github_push_resumes() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::initialized();
+    sandbox.write("bin/gh", FAKE_GH);
+    let gh = sandbox.dir.join("bin/gh");
+    std::fs::set_permissions(&gh, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", gh.parent().unwrap().display(), std::env::var("PATH").unwrap());
+    let env = [("PATH", path)];
+
+    sandbox.run(&["new", "--title", "Draft roadmap", "-t", "public"]);
+    sandbox.run(&["new", "--title", "Flaky build", "-t", "public"]);
+    sandbox.run(&["new", "--title", "Private notes"]);
+    sandbox.run(&["new", "--title", "Announce launch", "-t", "public"]);
+    sandbox.write("fail", "");
+    let push = [
+        "github", "push", "--query", "tag:public", "--repo", "acme/plans", "--interval", "0",
+    ];
+
+    let mut runs = vec![sandbox.run_with_env(&push, &env)];
+    std::fs::remove_file(sandbox.dir.join("fail")).unwrap();
+    runs.push(sandbox.run_with_env(&push, &env));
+    let porcelain: Vec<&str> = ["--porcelain"].iter().chain(&push).copied().collect();
+    runs.push(sandbox.run_with_env(&porcelain, &env));
+    assert_snapshot("github_push_resumes", &transcript(&runs));
+    assert_snapshot("github_push_resumes_issues", &sandbox.read("github.toml"));
+    assert_snapshot("github_push_resumes_requests", &sandbox.read("gh.log"));
+}
+
+#[test]
+fn dependencies_and_next() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Pour foundation"]);
+    sandbox.run(&["new", "--title", "Build walls"]);
+    sandbox.run(&["new", "--title", "Put on roof"]);
+    sandbox.run(&["new", "--title", "Order windows"]);
+    sandbox.run(&["status", "4", "blocked"]);
+    let runs = [
+        sandbox.run(&["link", "2", "--depends-on", "1"]),
+        sandbox.run(&["link", "3", "--depends-on", "2", "--depends-on", "4"]),
+        sandbox.run(&["link", "1", "--depends-on", "3"]),
+        sandbox.run(&["link", "2", "--depends-on", "9"]),
+        sandbox.run(&["next"]),
+        sandbox.run(&["done", "1"]),
+        sandbox.run(&["--porcelain", "next"]),
+        sandbox.run(&["--porcelain", "link", "3", "--remove", "4"]),
+        sandbox.run(&["done", "2"]),
+        sandbox.run(&["next", "--quiet"]),
+        sandbox.run(&["link", "3"]),
+    ];
+    assert_snapshot("dependencies_and_next", &transcript(&runs));
+    assert_snapshot("dependencies_and_next_file", &sandbox.read("tasks/0000000003.todo.md"));
+}
+
+#[test]
+fn recurring_todos() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants", "-t", "home", "--due", "2099-01-05"]);
+    sandbox.run(&["new", "--title", "Pay rent", "--due", "2099-01-31", "--priority", "high"]);
+    let runs = [
+        sandbox.run(&["recur", "1", "FREQ=WEEKLY;BYDAY=MO,TH"]),
+        sandbox.run(&["recur", "2", "FREQ=MONTHLY;BYMONTHDAY=-1"]),
+        sandbox.run(&["recur", "2", "FREQ=HOURLY"]),
+        sandbox.run(&["done", "1"]),
+        sandbox.run(&["done", "3"]),
+        sandbox.run(&["--porcelain", "status", "2", "done"]),
+        sandbox.run(&["recur", "5", "--clear"]),
+        sandbox.run(&["done", "5"]),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("recurring_todos", &transcript(&runs));
+    assert_snapshot("recurring_todos_file", &sandbox.read("tasks/0000000004.todo.md"));
+}
+
+#[test]
+fn recurring_todos_stay_open_without_a_next_occurrence() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Water plants", "--due", "2099-01-05"]);
+    sandbox.run(&["recur", "1", "FREQ=WEEKLY"]);
+    std::fs::remove_file(sandbox.dir.join("templates/task.md.hbs")).unwrap();
+    let runs = [sandbox.run(&["done", "1"]), sandbox.run(&["--quiet", "list"])];
+    assert_snapshot("recurring_todos_stay_open_without_a_next_occurrence", &transcript(&runs));
+    assert!(!sandbox.dir.join("tasks/0000000002.todo.md").exists());
+}
+
+#[test]
+fn external_blockers() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Review contract"]);
+    sandbox.run(&["new", "--title", "Sign contract"]);
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\nstatus = \"blocked\"\n\n\
+         [[blocked_by]]\non = \"https://example.com/tickets/7\"\nsince = \"2025-01-02T09:00:00Z\"\n\n\
+         [[blocked_by]]\non = \"waiting for legal\"\nsince = \"2025-01-03T09:00:00Z\"\n\
+         cleared = { at = \"2025-01-04T09:00:00Z\" }\n+++\n\n# Order laptops\n",
+    );
+    sandbox.run(&["new", "--title", "Plan rollout"]);
+    sandbox.run(&["status", "4", "blocked"]);
+    let runs = [
+        sandbox.run(&["block", "2", "1"]),
+        sandbox.run(&["block", "2", "waiting for the vendor"]),
+        sandbox.run(&["block", "2", "1"]),
+        sandbox.run(&["--porcelain", "block", "1", "Alex to confirm budget"]),
+        sandbox.run(&["unblock", "2", "1", "--reason", "reviewed"]),
+        sandbox.run(&["unblock", "2", "1"]),
+        sandbox.run(&["unblock", "3", "2"]),
+        sandbox.run(&["unblock", "3", "9"]),
+        sandbox.run(&["--porcelain", "blocked"]),
+        sandbox.run(&["unblock", "2", "--reason", "vendor shipped"]),
+        sandbox.run(&["next"]),
+    ];
+    assert_snapshot("external_blockers", &transcript(&runs));
+    assert_snapshot("external_blockers_file", &sandbox.read("tasks/0000000002.todo.md"));
+
+    sandbox.run(&["unblock", "1"]);
+    assert_snapshot("external_blockers_report", &transcript(&[sandbox.run(&["blocked"])]));
+}
+
+#[test]
+fn done_todos_stay_visible() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Send invoice", "--due", "2099-01-05"]);
+    sandbox.run(&["new", "--title", "Book flights"]);
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\nstatus = \"done\"\n\
+         completed_at = \"2025-01-02T09:00:00Z\"\n+++\n\n# Renew passport\n",
+    );
+    sandbox.run(&["done", "2"]);
+    let mut runs = vec![sandbox.run(&["list"])];
+    sandbox.write("config.toml", "[done]\nkeep_visible_days = 3\n");
+    runs.extend([
+        sandbox.run(&["list"]),
+        sandbox.run(&["list", "--quiet"]),
+        sandbox.run(&["list", "--status", "done"]),
+        sandbox.run(&["today"]),
+    ]);
+    assert_snapshot("done_todos_stay_visible", &transcript(&runs));
+}
+
+#[test]
+fn next_ranks_by_score() {
+    let sandbox = Sandbox::initialized();
+    let todo = |id: u32, extra: &str, title: &str| {
+        sandbox.write(
+            &format!("tasks/{id:010}.todo.md"),
+            &format!(
+                "+++\nid = {id}\ncreated_at = \"2025-01-01T09:00:00Z\"\ntags = []\n{extra}+++\n\n\
+                 # {title}\n"
+            ),
+        );
+    };
+    todo(1, "", "Tidy desk");
+    todo(2, "due_at = \"2025-02-01T23:59:59Z\"\n", "File taxes");
+    todo(3, "priority = \"high\"\n", "Fix outage");
+    todo(4, "due_at = \"2099-01-01T23:59:59Z\"\npriority = \"low\"\n", "Plan retreat");
+    todo(5, "depends_on = [4]\n", "Book venue");
+    todo(6, "depends_on = [4]\n", "Send invites");
+    let mut runs = vec![
+        sandbox.run(&["next"]),
+        sandbox.run(&["next", "--count", "10"]),
+    ];
+    sandbox.write("config.toml", "[next]\ndue = 0.0\npriority = 1.0\ndependents = 5.0\n");
+    runs.push(sandbox.run(&["--porcelain", "next", "--count", "2"]));
+    assert_snapshot("next_ranks_by_score", &transcript(&runs));
+}
+
+#[test]
+fn bulk_operations() {
+    let sandbox = Sandbox::initialized();
+    for (title, tag) in [
+        ("Fix login", "backend"),
+        ("Polish icons", "frontend"),
+        ("Migrate database", "backend"),
+        ("Write changelog", "docs"),
+        ("Cache sessions", "backend"),
+        ("Update screenshots", "docs"),
+    ] {
+        sandbox.run(&["new", "--title", title, "-t", tag]);
+    }
+    let runs = [
+        sandbox.run(&["tag", "--add", "sprint-42", "--filter", "tag=backend", "--dry-run"]),
+        sandbox.run(&["--porcelain", "tag", "--add", "sprint-42", "--filter", "tag=backend"]),
+        sandbox.run(&["done", "2", "4-6", "--dry-run"]),
+        sandbox.run(&["done", "2", "4-6", "9"]),
+        sandbox.run(&["done", "1,5"]),
+        sandbox.run(&["cancel", "--filter", "tag:sprint-42 status:open", "--reason", "descoped"]),
+        sandbox.run(&["delete", "6-2"]),
+        sandbox.run(&["delete", "--filter", "tag=nothing"]),
+        sandbox.run(&["done"]),
+        sandbox.run(&["list", "--status", "done,cancelled", "--quiet"]),
+    ];
+    assert_snapshot("bulk_operations", &transcript(&runs));
+}
+
+#[test]
+fn interactive_new_without_terminal() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run_with_input(
+            &["new", "-i", "--title", "Book flights", "-t", "travel"],
+            "ignored\n",
+        ),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("interactive_new_without_terminal", &transcript(&runs));
+}
+
+#[test]
+fn get_and_patch_fields() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Plan trip", "-t", "travel", "--due", "2099-03-01"]);
+    let path = sandbox.dir.join("tasks/0000000001.todo.md");
+    let content = std::fs::read_to_string(&path).unwrap();
+    let steps = "# Plan trip\n\n## Steps\n\n- [x] pick dates\n- [ ] book\n";
+    std::fs::write(&path, content.replace("# Plan trip\n", steps)).unwrap();
+    let patch = r#"{"front_matter": {"priority": "high", "due_at": null, "tags": ["travel", "home"]}}"#;
+    let runs = [
+        sandbox.run(&["get", "1", "front_matter.due_at"]),
+        sandbox.run(&["get", "1", "front_matter.tags.0"]),
+        sandbox.run(&["get", "1", "front_matter.priority"]),
+        sandbox.run(&["get", "1", "checklist.completed_count"]),
+        sandbox.run(&["get", "1", "checklist"]),
+        sandbox.run(&["get", "1", "front_matter.tags.3"]),
+        sandbox.run(&["set-json", "1", "--patch", patch]),
+        sandbox.run(&["set-json", "1", "--patch", r#"{"front_matter": {"priority": "soon"}}"#]),
+        sandbox.run(&["set-json", "1", "--patch", r#"{"title": "Plan holiday"}"#]),
+        sandbox.run(&["get", "1", "front_matter.tags"]),
+        sandbox.run(&["get", "1", "front_matter.due_at"]),
+    ];
+    assert_snapshot("get_and_patch_fields", &transcript(&runs));
+}
+
+#[test]
+fn quick_capture() {
+    let sandbox = Sandbox::initialized();
+    let runs = [
+        sandbox.run(&["new", "Call the dentist 2099-05-01 #health !p2"]),
+        sandbox.run(&["new", "#home Fix the   gate", "-t", "weekend", "--priority", "low"]),
+        sandbox.run(&["new", "Renew passport", "--title", "Something else"]),
+        sandbox.run(&["list"]),
+        sandbox.run(&["get", "1", "front_matter.priority"]),
+    ];
+    assert_snapshot("quick_capture", &transcript(&runs));
+}
+
+#[test]
+fn suggested_due_dates() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "config.toml",
+        "[due]\nmax_per_day = 1\n\n[[due.rules]]\ntemplate = \"bug\"\ndue = \"tomorrow\"\n\n\
+         [[due.rules]]\ntag = \"invoice\"\ndue = \"2099-06-30\"\n",
+    );
+    let runs = [
+        sandbox.run(&["new", "Send March invoice #invoice", "--suggest-due"]),
+        sandbox.run(&["new", "Send April invoice #invoice", "--suggest-due"]),
+        sandbox.run(&["new", "Send May invoice #invoice"]),
+        sandbox.run(&["new", "Water plants", "--suggest-due"]),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("suggested_due_dates", &transcript(&runs));
+}
+
+#[test]
+fn clone_todo() {
+    let sandbox = Sandbox::initialized();
+    sandbox.run(&["new", "--title", "Taxes"]);
+    sandbox.run(&["new", "--title", "Collect receipts"]);
+    sandbox.write(
+        "tasks/0000000003.todo.md",
+        "+++\nid = 3\ncreated_at = \"2025-01-01T09:00:00Z\"\ndue_at = \"2099-01-31T23:59:59Z\"\n\
+         tags = [\"finance\"]\npriority = \"high\"\nrecurrence = \"FREQ=MONTHLY\"\nparent = 1\n\
+         depends_on = [2]\nstatus = \"done\"\ncompleted_at = \"2025-01-20T09:00:00Z\"\n\
+         office = \"north\"\n\n[[time_entries]]\nstart = \"2025-01-20T08:00:00Z\"\n\
+         end = \"2025-01-20T09:00:00Z\"\n+++\n\n# File January taxes\n\n- [x] collect receipts\n",
+    );
+    let runs = [
+        sandbox.run(&["clone", "3", "--title", "file february taxes"]),
+        sandbox.run(&["--porcelain", "clone", "3", "--keep", "parent,depends-on"]),
+        sandbox.run(&["clone", "9"]),
+        sandbox.run(&["list"]),
+    ];
+    assert_snapshot("clone_todo", &transcript(&runs));
+    // created_at is the time of cloning, so only the fields after it
+    let front_matter = |id: u32| {
+        let todo = sandbox.read(&format!("tasks/{id:010}.todo.md"));
+        todo.lines().filter(|line| !line.starts_with("created_at")).collect::<Vec<_>>().join("\n")
+    };
+    assert_snapshot("clone_todo_plain", &front_matter(4));
+    assert_snapshot("clone_todo_kept_links", &front_matter(5));
+}
+
+#[test]
+fn template_values_stay_inside_toml_strings() {
+    let sandbox = Sandbox::initialized();
+    sandbox.write(
+        "templates/quote.md.hbs",
+        "+++\nid = {{ id }}\ncreated_at = \"{{ created_at }}\"\ntags = []\n\
+         summary = \"{{ title }}\"\n+++\n\n# {{{ title }}}\n",
+    );
+    let title = r#"Say "hi" \ wave" status = "done"#;
+    let runs = [
+        sandbox.run(&["new", "--template", "quote", "--title", title]),
+        sandbox.run(&["get", "1", "front_matter.summary"]),
+        sandbox.run(&["get", "1", "front_matter.status"]),
+        sandbox.run(&["get", "1", "title"]),
+    ];
+    assert_snapshot("template_values_stay_inside_toml_strings", &transcript(&runs));
+}